@@ -3,14 +3,15 @@ use std::process;
 
 use error::{AppError, Result};
 use std::{
+    collections::HashMap,
     fs,
     io::Write,
     path::{Path, PathBuf},
     process::{Command, Stdio},
-    sync::Arc,
+    sync::{Arc, Mutex as StdMutex},
     time::Duration,
 };
-use tauri::{api::path::config_dir, Runtime, Window};
+use tauri::{api::path::config_dir, Runtime, Url, Window};
 use tokio::{io::AsyncReadExt, process::Command as TokioCommand};
 use tokio::{sync::RwLock, task::JoinSet};
 use uuid::Uuid;
@@ -29,14 +30,17 @@ use crate::{
 };
 
 use super::{
-    constants::{COURSES_CACHE_KEY, RELATIONSHIP_CACHE_KEY},
+    constants::{
+        COURSES_CACHE_KEY, COURSE_FILES_CACHE_KEY_PREFIX, COURSE_FILES_CACHE_TTL_SECS,
+        RELATIONSHIP_CACHE_KEY,
+    },
     App,
 };
 
 const MY_CANVAS_FILES_FOLDER_NAME: &str = "我的Canvas文件";
 
 impl App {
-    fn ensure_directory(dir: &str) {
+    pub fn ensure_directory(dir: &str) {
         let metadata = fs::metadata(dir);
         tracing::info!("dir: {:?}", dir);
         if metadata.is_err() {
@@ -191,7 +195,13 @@ impl App {
         let config = App::read_config_from_file(&config_path).unwrap_or_default();
 
         let base_url = Self::get_base_url(&config.account_type);
-        let client = Client::with_base_url(base_url);
+        let mut client = Client::with_timeouts(base_url, config.network_timeouts)
+            .with_progress_interval(config.progress_interval_ms)
+            .with_transfer_limit(config.max_concurrent_transfers);
+        let proxy_url = config.network_proxy_url();
+        if let Err(e) = client.set_proxy(&proxy_url) {
+            tracing::error!("failed to set proxy, connect directly: {}", e);
+        }
 
         Self {
             client: Arc::new(client),
@@ -199,18 +209,37 @@ impl App {
             config: RwLock::new(config),
             handle: Default::default(),
             cache: Default::default(),
+            qr_logins: Default::default(),
+            app_handle: Default::default(),
+            download_tasks: Default::default(),
+            download_history: StdMutex::new(App::read_download_history()),
+            video_queue: StdMutex::new(App::read_video_queue()),
+            subjects_refresh: Default::default(),
         }
     }
 
     pub async fn init(&self) -> Result<()> {
+        let account = self.current_account.read().await.clone();
+        match self
+            .client
+            .load_session(&App::get_session_path(&account))
+            .await
+        {
+            Ok(true) => tracing::info!("Logged in with the saved session"),
+            Ok(false) => (),
+            Err(e) => tracing::warn!("Failed to restore the saved session: {}", e),
+        }
         let mut config = self.get_config().await;
         let cookies = &config.video_cookies;
         if !cookies.is_empty() {
             tracing::info!("Detected saved cookies: {}", cookies);
             self.client.init_cookie(cookies);
-            if let Ok(Some(consumer_key)) = self.client.get_oauth_consumer_key().await {
-                config.oauth_consumer_key = consumer_key;
-                self.save_config(config).await?;
+            match self.client.get_oauth_consumer_key().await {
+                Ok(consumer_key) => {
+                    config.oauth_consumer_key = consumer_key;
+                    self.save_config(config).await?;
+                }
+                Err(e) => tracing::warn!("failed to get oauth consumer key: {}", e),
             }
         }
         Ok(())
@@ -279,6 +308,30 @@ impl App {
         config_path
     }
 
+    fn get_session_path(account: &Account) -> String {
+        let config_dir = App::config_dir().unwrap();
+        let mut session_file_name = "sjtu_canvas_helper_session".to_owned();
+        if let Account::Custom(name) = account {
+            session_file_name += &format!("_{}", name);
+        }
+        format!("{}/{}.json", config_dir, session_file_name)
+    }
+
+    pub async fn save_session(&self) -> Result<()> {
+        let account = self.current_account.read().await.clone();
+        self.client.save_session(&App::get_session_path(&account))
+    }
+
+    // Drops the jaccount login of the current account, the canvas token is kept
+    pub async fn logout(&self) -> Result<()> {
+        let account = self.current_account.read().await.clone();
+        self.client.logout(&App::get_session_path(&account)).await?;
+        let mut config = self.get_config().await;
+        config.ja_auth_cookie.clear();
+        config.video_cookies.clear();
+        self.save_config(config).await
+    }
+
     pub async fn get_config(&self) -> AppConfig {
         self.config.read().await.clone()
     }
@@ -473,6 +526,56 @@ impl App {
         self.client.list_discussion_topics(course_id, &token).await
     }
 
+    pub async fn get_grades(&self, course_id: i64) -> Result<Vec<GradeEntry>> {
+        let token = self.config.read().await.token.clone();
+        let assignments = self
+            .client
+            .list_course_assignments(course_id, &token)
+            .await?;
+        let submissions = self.client.list_my_submissions(course_id, &token).await?;
+        let groups = self
+            .client
+            .list_assignment_groups(course_id, &token)
+            .await?;
+
+        let mut submissions: HashMap<i64, Submission> = submissions
+            .into_iter()
+            .map(|submission| (submission.assignment_id, submission))
+            .collect();
+        let groups: HashMap<i64, AssignmentGroup> =
+            groups.into_iter().map(|group| (group.id, group)).collect();
+        let entries = assignments
+            .into_iter()
+            .map(|assignment| {
+                let group = assignment
+                    .assignment_group_id
+                    .and_then(|group_id| groups.get(&group_id));
+                let submission = submissions.remove(&assignment.id).unwrap_or_default();
+                GradeEntry {
+                    assignment_id: assignment.id,
+                    assignment_name: assignment.name,
+                    group_name: group.map(|group| group.name.clone()),
+                    group_weight: group.map(|group| group.group_weight),
+                    score: submission.score,
+                    grade: submission.grade,
+                    points_possible: assignment.points_possible,
+                    graded_at: submission.graded_at,
+                    comments: submission.submission_comments,
+                }
+            })
+            .collect();
+        Ok(entries)
+    }
+
+    pub fn get_transfer_stats(&self) -> TransferStats {
+        self.client.transfer_stats()
+    }
+
+    pub async fn list_announcements(&self, course_id: i64) -> Result<Vec<Announcement>> {
+        let token = self.config.read().await.token.clone();
+        self.client.list_announcements(course_id, &token).await
+    }
+
     pub async fn get_full_discussion(
         &self,
         course_id: i64,
@@ -486,7 +589,127 @@ impl App {
 
     pub async fn list_course_files(&self, course_id: i64) -> Result<Vec<File>> {
         let token = self.config.read().await.token.clone();
-        self.client.list_course_files(course_id, &token).await
+        let files = self.client.list_course_files(course_id, &token).await?;
+        let cached = CachedCourseFiles {
+            fetched_at: utils::unix_timestamp(),
+            files: files.clone(),
+        };
+        self.cache
+            .set(App::course_files_cache_key(course_id), cached)?;
+        Ok(files)
+    }
+
+    fn course_files_cache_key(course_id: i64) -> String {
+        format!("{}{}", COURSE_FILES_CACHE_KEY_PREFIX, course_id)
+    }
+
+    // Filter the cached file list while it's fresh, otherwise let canvas do the search
+    pub async fn search_course_files(&self, course_id: i64, query: &str) -> Result<Vec<File>> {
+        let cached: Option<CachedCourseFiles> =
+            self.cache.get(&App::course_files_cache_key(course_id))?;
+        let files = match cached {
+            Some(cached)
+                if utils::unix_timestamp().saturating_sub(cached.fetched_at)
+                    < COURSE_FILES_CACHE_TTL_SECS =>
+            {
+                cached.files
+            }
+            _ if query.chars().count() >= 2 => {
+                let token = self.config.read().await.token.clone();
+                return self
+                    .client
+                    .search_canvas_files(course_id, query, &token)
+                    .await;
+            }
+            _ => self.list_course_files(course_id).await?,
+        };
+        let query = query.to_lowercase();
+        Ok(files
+            .into_iter()
+            .filter(|file| file.display_name.to_lowercase().contains(&query))
+            .collect())
+    }
+
+    pub async fn get_canvas_media_recordings(&self, course_id: i64) -> Result<Vec<MediaRecording>> {
+        let token = self.config.read().await.token.clone();
+        self.client
+            .get_canvas_media_recordings(course_id, &token)
+            .await
+    }
+
+    pub async fn get_canvas_appointment_groups(
+        &self,
+        course_id: i64,
+    ) -> Result<Vec<AppointmentGroup>> {
+        let token = self.config.read().await.token.clone();
+        self.client
+            .get_canvas_appointment_groups(course_id, &token)
+            .await
+    }
+
+    pub async fn get_canvas_collaborations(&self, course_id: i64) -> Result<Vec<Collaboration>> {
+        let token = self.config.read().await.token.clone();
+        self.client
+            .get_canvas_collaborations(course_id, &token)
+            .await
+    }
+
+    pub async fn get_canvas_groups(&self, course_id: i64) -> Result<Vec<CanvasGroup>> {
+        let token = self.config.read().await.token.clone();
+        self.client.get_canvas_groups(course_id, &token).await
+    }
+
+    pub async fn get_canvas_learning_outcomes(
+        &self,
+        course_id: i64,
+    ) -> Result<Vec<LearningOutcome>> {
+        let token = self.config.read().await.token.clone();
+        self.client
+            .get_canvas_learning_outcomes(course_id, &token)
+            .await
+    }
+
+    pub async fn get_canvas_outcome_rollups(&self, course_id: i64) -> Result<Vec<OutcomeRollup>> {
+        let token = self.config.read().await.token.clone();
+        self.client
+            .get_canvas_outcome_rollups(course_id, &token)
+            .await
+    }
+
+    pub async fn get_canvas_content_migrations(
+        &self,
+        course_id: i64,
+    ) -> Result<Vec<ContentMigration>> {
+        let token = self.config.read().await.token.clone();
+        self.client
+            .get_canvas_content_migrations(course_id, &token)
+            .await
+    }
+
+    pub async fn get_canvas_media_track(&self, media_id: &str) -> Result<Vec<MediaTrack>> {
+        let token = self.config.read().await.token.clone();
+        self.client.get_canvas_media_track(media_id, &token).await
+    }
+
+    // Captions of a canvas media object are saved next to it as `{save_name}.{locale}.srt`
+    pub async fn download_canvas_media_captions(
+        &self,
+        media_id: &str,
+        save_name: &str,
+    ) -> Result<Vec<String>> {
+        let save_dir = self.config.read().await.save_path.clone();
+        let stem = Path::new(save_name).with_extension("");
+        let mut paths = vec![];
+        for track in self.get_canvas_media_track(media_id).await? {
+            let Some(content) = track.content.filter(|content| !content.is_empty()) else {
+                continue;
+            };
+            let file_name = format!("{}.{}.srt", stem.to_string_lossy(), track.locale);
+            let path = Path::new(&save_dir).join(file_name);
+            fs::write(&path, content)?;
+            paths.push(path.to_string_lossy().into_owned());
+        }
+        Ok(paths)
     }
 
     pub async fn list_course_images(&self, course_id: i64) -> Result<Vec<File>> {
@@ -578,6 +801,34 @@ impl App {
             .await
     }
 
+    pub async fn get_student_view_student(&self, course_id: i64) -> Result<User> {
+        self.client
+            .get_student_view_student(course_id, &self.config.read().await.token)
+            .await
+    }
+
+    // The assignments of the course the way its test student sees them
+    pub async fn list_student_view_assignments(&self, course_id: i64) -> Result<Vec<Assignment>> {
+        self.login_canvas_website().await?;
+        let token = self.config.read().await.token.clone();
+        let client = self.client.student_view_client(course_id, &token).await?;
+        client.list_course_assignments(course_id, &token).await
+    }
+
+    pub async fn get_canvas_submission_comments(
+        &self,
+        course_id: i64,
+        assignment_id: i64,
+    ) -> Result<Vec<SubmissionComment>> {
+        self.client
+            .get_canvas_submission_comments(
+                course_id,
+                assignment_id,
+                &self.config.read().await.token,
+            )
+            .await
+    }
+
     pub async fn list_folder_files(&self, folder_id: i64) -> Result<Vec<File>> {
         self.client
             .list_folder_files(folder_id, &self.config.read().await.token)
@@ -619,13 +870,20 @@ impl App {
         file: &File,
         progress_handler: F,
     ) -> Result<()> {
-        let guard = self.config.read().await;
-        let token = &guard.token.clone();
-        let save_path = &guard.save_path.clone();
-        self.client
-            .download_file(file, token, save_path, progress_handler)
-            .await?;
-        Ok(())
+        let (token, save_path) = {
+            let config = self.config.read().await;
+            (config.token.clone(), config.save_path.clone())
+        };
+        let source = DownloadSource::File { file: file.clone() };
+        self.record_download(
+            &file.display_name,
+            None,
+            &Path::new(&save_path).join(&file.display_name),
+            source,
+            self.client
+                .download_file(file, &token, &save_path, progress_handler),
+        )
+        .await
     }
 
     pub async fn download_course_file<F: Fn(ProgressPayload) + Send>(
@@ -635,19 +893,31 @@ impl App {
         folder_path: &str,
         progress_handler: F,
     ) -> Result<()> {
-        let guard = self.config.read().await;
-        let token = &guard.token.clone();
+        let (token, save_dir) = {
+            let config = self.config.read().await;
+            (config.token.clone(), config.save_path.clone())
+        };
         let course_identifier = self.get_course_identifier(course);
-        let save_path = Path::new(&guard.save_path)
+        let save_path = Path::new(&save_dir)
             .join(course_identifier)
             .join(folder_path);
         let save_path = save_path.to_str().unwrap_or_default();
         App::ensure_directory(save_path);
         tracing::info!("Download file at path: {:?}", save_path);
-        self.client
-            .download_file(file, token, save_path, progress_handler)
-            .await?;
-        Ok(())
+        let source = DownloadSource::CourseFile {
+            file: file.clone(),
+            course: course.clone(),
+            folder_path: folder_path.to_owned(),
+        };
+        self.record_download(
+            &file.display_name,
+            Some(course.name.clone()),
+            &Path::new(save_path).join(&file.display_name),
+            source,
+            self.client
+                .download_file(file, &token, save_path, progress_handler),
+        )
+        .await
     }
 
     pub async fn download_my_file<F: Fn(ProgressPayload) + Send>(
@@ -656,18 +926,57 @@ impl App {
         folder_path: &str,
         progress_handler: F,
     ) -> Result<()> {
-        let guard = self.config.read().await;
-        let token = &guard.token.clone();
-        let save_path = Path::new(&guard.save_path)
+        let (token, save_dir) = {
+            let config = self.config.read().await;
+            (config.token.clone(), config.save_path.clone())
+        };
+        let save_path = Path::new(&save_dir)
             .join(MY_CANVAS_FILES_FOLDER_NAME)
             .join(folder_path);
         let save_path = save_path.to_str().unwrap_or_default();
         App::ensure_directory(save_path);
         tracing::info!("Download file at path: {:?}", save_path);
+        let source = DownloadSource::MyFile {
+            file: file.clone(),
+            folder_path: folder_path.to_owned(),
+        };
+        self.record_download(
+            &file.display_name,
+            None,
+            &Path::new(save_path).join(&file.display_name),
+            source,
+            self.client
+                .download_file(file, &token, save_path, progress_handler),
+        )
+        .await
+    }
+
+    // Without `folder_id` the whole file tree of the course is downloaded
+    pub async fn download_folder<F, G>(
+        &self,
+        course_id: i64,
+        folder_id: Option<i64>,
+        local_dir: &str,
+        progress_handler: F,
+        summary_handler: G,
+    ) -> Result<FolderDownloadReport>
+    where
+        F: Fn(ProgressPayload) + Send + Sync + 'static,
+        G: Fn(ProgressPayload),
+    {
+        let token = self.config.read().await.token.clone();
+        let folder = match folder_id {
+            Some(folder_id) => self.client.get_folder_by_id(folder_id, &token).await?,
+            None => {
+                self.client
+                    .get_course_root_folder(course_id, &token)
+                    .await?
+            }
+        };
         self.client
-            .download_file(file, token, save_path, progress_handler)
-            .await?;
-        Ok(())
+            .clone()
+            .download_folder(folder, local_dir, &token, progress_handler, summary_handler)
+            .await
     }
 
     fn get_course_identifier(&self, course: &Course) -> String {
@@ -725,6 +1034,14 @@ impl App {
         Ok(())
     }
 
+    // The url comes from the course, anything but a web link could reach local files
+    pub fn open_collaboration_url(&self, url: &str) -> Result<()> {
+        match Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => self.open_path(url),
+            _ => Err(AppError::InvalidCollaborationUrl(url.to_owned())),
+        }
+    }
+
     pub async fn open_save_dir(&self) -> Result<()> {
         let save_path = &self.config.read().await.save_path;
         self.open_path(save_path)
@@ -780,6 +1097,12 @@ impl App {
             .await
     }
 
+    pub async fn get_canvas_file_usage_rights(&self, file_id: i64) -> Result<UsageRights> {
+        self.client
+            .get_canvas_file_usage_rights(file_id, &self.config.read().await.token)
+            .await
+    }
+
     pub async fn get_colors(&self) -> Result<Colors> {
         self.client
             .get_colors(&self.config.read().await.token)
@@ -802,6 +1125,18 @@ impl App {
             .await
     }
 
+    pub async fn get_calendar_events(
+        &self,
+        course_id: i64,
+        start_date: &str,
+        end_date: &str,
+    ) -> Result<Vec<CalendarEvent>> {
+        let token = self.config.read().await.token.clone();
+        self.client
+            .get_calendar_events(course_id, start_date, end_date, &token)
+            .await
+    }
+
     pub async fn save_config(&self, config: AppConfig) -> Result<()> {
         let account = self.current_account.read().await.clone();
         let config_path = App::get_config_path(&account);
@@ -824,16 +1159,24 @@ impl App {
         false
     }
 
-    pub async fn submit_assignment(
+    pub async fn submit_assignment<F: Fn(ProgressPayload) + Send + Sync + 'static>(
         &self,
         course_id: i64,
         assignment_id: i64,
         file_paths: &[String],
         comment: Option<&str>,
+        progress_handler: F,
     ) -> Result<()> {
         let token = self.config.read().await.token.clone();
         self.client
-            .submit_assignment(course_id, assignment_id, file_paths, comment, &token)
+            .submit_assignment(
+                course_id,
+                assignment_id,
+                file_paths,
+                comment,
+                &token,
+                progress_handler,
+            )
             .await?;
         Ok(())
     }
@@ -882,6 +1225,47 @@ impl App {
         Ok(())
     }
 
+    pub async fn upload_canvas_profile_picture(&self, image_path: &str) -> Result<()> {
+        let token = self.config.read().await.token.clone();
+        self.client
+            .upload_canvas_profile_picture(image_path, &token)
+            .await
+    }
+
+    pub async fn export_calendar_events(
+        &self,
+        events: &[CalendarEvent],
+        save_name: &str,
+    ) -> Result<()> {
+        let save_path = self.config.read().await.save_path.clone();
+        let path = Path::new(&save_path).join(save_name);
+        fs::write(path, utils::export_ics(events))?;
+        Ok(())
+    }
+
+    // Tries the proxy before it's saved, the running client keeps its proxy until a restart
+    pub async fn check_network_proxy(&self, proxy_url: &str) -> Result<()> {
+        let (base_url, timeouts) = {
+            let config = self.config.read().await;
+            (
+                Self::get_base_url(&config.account_type),
+                config.network_timeouts,
+            )
+        };
+        let mut client = Client::with_timeouts(base_url, timeouts);
+        client.set_proxy(proxy_url)?;
+        client.check_proxy().await
+    }
+
+    pub async fn export_course_calendar(&self, course_id: i64, save_name: &str) -> Result<()> {
+        let token = self.config.read().await.token.clone();
+        let save_path = self.config.read().await.save_path.clone();
+        let path = Path::new(&save_path).join(save_name);
+        self.client
+            .export_course_calendar_as_ics(course_id, path.to_str().unwrap(), &token)
+            .await
+    }
+
     pub async fn export_users(&self, users: &[User], save_name: &str) -> Result<()> {
         let save_path = self.config.read().await.save_path.clone();
         let path = Path::new(&save_path).join(save_name);
@@ -45,7 +45,6 @@ impl Cache {
         Ok(())
     }
 
-    #[allow(dead_code)]
     pub fn remove<V>(&self, key: &str) -> Result<Option<V>>
     where
         V: DeserializeOwned + Serialize,
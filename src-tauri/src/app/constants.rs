@@ -1,2 +1,24 @@
+pub const DOWNLOAD_TASK_EVENT: &str = "download_task://progress";
 pub const COURSES_CACHE_KEY: &str = "courses_cache_key";
 pub const RELATIONSHIP_CACHE_KEY: &str = "relationship_cache_key";
+pub const VIDEO_API_VERSION_CACHE_KEY: &str = "video_api_version_cache_key";
+pub const VIDEO_INFO_MAX_ATTEMPTS: u32 = 3;
+pub const COURSE_FILES_CACHE_KEY_PREFIX: &str = "course_files_cache_key_";
+pub const COURSE_FILES_CACHE_TTL_SECS: u64 = 5 * 60;
+pub const SUBJECTS_CACHE_KEY: &str = "subjects_cache_key";
+pub const SUBJECTS_CACHE_TTL_SECS: u64 = 5 * 60;
+pub const VIDEO_COURSES_CACHE_KEY_PREFIX: &str = "video_courses_cache_key_";
+pub const VIDEO_COURSES_CACHE_TTL_SECS: u64 = 5 * 60;
+// subjects whose recordings are listed at the same time by a search
+pub const VIDEO_SEARCH_CONCURRENCY: usize = 4;
+// recordings whose details are fetched at the same time for an exported video list
+pub const VIDEO_LIST_INFO_CONCURRENCY: usize = 4;
+// how often the progress of queued video downloads is written to disk
+pub const VIDEO_QUEUE_SAVE_INTERVAL_SECS: u64 = 5;
+pub const CANVAS_COURSE_ID_CACHE_KEY_PREFIX: &str = "canvas_course_id_cache_key_";
+// the mapping itself never changes, the ttl only bounds how long a stale lti session is reused
+pub const CANVAS_COURSE_ID_CACHE_TTL_SECS: u64 = 12 * 60 * 60;
+// jaccount signs every QR code for a limited time, a new one has to be requested after that
+pub const QR_LOGIN_EXPIRE_SECS: u64 = 60;
+// how long a single poll waits for jaccount to push the confirmation
+pub const QR_LOGIN_POLL_WAIT_MS: u64 = 500;
@@ -0,0 +1,146 @@
+use std::{
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use uuid::Uuid;
+
+use super::App;
+use crate::{
+    error::{AppError, Result},
+    model::{
+        DownloadHistoryEntry, DownloadHistoryFilter, DownloadResult, DownloadSource,
+        VideoDownloadResult,
+    },
+    utils,
+};
+
+// What a successful download left on disk, for its history entry
+pub trait SavedDownload {
+    // The saved path and size, None when nothing was downloaded so there is nothing to record
+    fn saved(&self, requested_path: &Path) -> Option<(PathBuf, u64)>;
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path)
+        .map(|metadata| metadata.len())
+        .unwrap_or_default()
+}
+
+// Files are always saved at the requested path
+impl SavedDownload for () {
+    fn saved(&self, requested_path: &Path) -> Option<(PathBuf, u64)> {
+        Some((requested_path.to_owned(), file_size(requested_path)))
+    }
+}
+
+impl SavedDownload for DownloadResult {
+    fn saved(&self, _: &Path) -> Option<(PathBuf, u64)> {
+        match self.status {
+            VideoDownloadResult::Downloaded => {
+                Some((PathBuf::from(&self.path), self.bytes_written))
+            }
+            VideoDownloadResult::Skipped => None,
+        }
+    }
+}
+
+// Every finished download, failed ones included, kept in a json file besides the config.
+// The entries carry their download parameters so any of them can be started again.
+impl App {
+    fn download_history_path() -> Result<String> {
+        Ok(format!("{}/download_history.json", App::config_dir()?))
+    }
+
+    pub(super) fn read_download_history() -> Vec<DownloadHistoryEntry> {
+        App::download_history_path()
+            .and_then(|path| Ok(fs::read(path)?))
+            .and_then(|content| utils::parse_json(&content))
+            .unwrap_or_default()
+    }
+
+    fn save_download_history(history: &[DownloadHistoryEntry]) -> Result<()> {
+        let content = serde_json::to_vec(history)?;
+        fs::write(App::download_history_path()?, content)?;
+        Ok(())
+    }
+
+    // Run `download` and add how it went to the history. A failed one is recorded at the
+    // requested `save_path`, a skipped one isn't recorded.
+    pub async fn record_download<T: SavedDownload>(
+        &self,
+        name: &str,
+        course: Option<String>,
+        save_path: &Path,
+        source: DownloadSource,
+        download: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let started = Instant::now();
+        let result = download.await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let (saved_path, size) = match &result {
+            Ok(saved) => match saved.saved(save_path) {
+                Some(saved) => saved,
+                None => return result,
+            },
+            Err(_) => (save_path.to_owned(), file_size(save_path)),
+        };
+        let entry = DownloadHistoryEntry {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_owned(),
+            course,
+            save_path: saved_path.to_string_lossy().into_owned(),
+            size,
+            duration_ms,
+            average_speed: size * 1000 / duration_ms.max(1),
+            completed_at: utils::unix_timestamp(),
+            error: result.as_ref().err().map(ToString::to_string),
+            source,
+        };
+        if let Err(e) = self.add_download_history(entry).await {
+            tracing::warn!("failed to record download history: {}", e);
+        }
+        result
+    }
+
+    async fn add_download_history(&self, entry: DownloadHistoryEntry) -> Result<()> {
+        let limit = self.config.read().await.download_history_limit;
+        let mut history = self
+            .download_history
+            .lock()
+            .map_err(|_| AppError::MutexError)?;
+        history.push(entry);
+        // oldest first, so the pruned ones are at the front
+        let excess = history.len().saturating_sub(limit);
+        history.drain(..excess);
+        App::save_download_history(&history)
+    }
+
+    // Newest first
+    pub fn get_download_history(
+        &self,
+        filter: &DownloadHistoryFilter,
+    ) -> Result<Vec<DownloadHistoryEntry>> {
+        let history = self
+            .download_history
+            .lock()
+            .map_err(|_| AppError::MutexError)?;
+        Ok(history
+            .iter()
+            .rev()
+            .filter(|entry| filter.matches(entry))
+            .cloned()
+            .collect())
+    }
+
+    pub fn clear_download_history(&self) -> Result<()> {
+        let mut history = self
+            .download_history
+            .lock()
+            .map_err(|_| AppError::MutexError)?;
+        history.clear();
+        App::save_download_history(&history)
+    }
+}
@@ -1,15 +1,27 @@
 use cache::Cache;
-use std::sync::Arc;
-use tokio::{sync::RwLock, task::JoinHandle};
+use queue::VideoQueue;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex as StdMutex, OnceLock},
+};
+use tauri::AppHandle;
+use tokio::{
+    sync::{Mutex, RwLock},
+    task::JoinHandle,
+};
+use video::QrLogin;
 
 use crate::{
     client::Client,
-    model::{Account, AppConfig},
+    model::{Account, AppConfig, DownloadHistoryEntry, DownloadTask},
 };
 pub mod basic;
 pub mod cache;
 mod constants;
+mod history;
 pub mod jbox;
+mod queue;
+mod task;
 pub mod video;
 
 pub struct App {
@@ -18,13 +30,27 @@ pub struct App {
     config: RwLock<AppConfig>,
     handle: RwLock<Option<JoinHandle<()>>>,
     cache: Cache,
+    // pending QR code logins by uuid
+    qr_logins: Mutex<HashMap<String, QrLogin>>,
+    // set once the tauri app is built, download progress is emitted through it
+    app_handle: OnceLock<AppHandle>,
+    download_tasks: StdMutex<HashMap<String, DownloadTask>>,
+    download_history: StdMutex<Vec<DownloadHistoryEntry>>,
+    video_queue: StdMutex<VideoQueue>,
+    // held while the subject list is fetched, so concurrent callers share one fetch
+    subjects_refresh: Mutex<()>,
 }
 
 #[cfg(test)]
 mod test {
+    use std::time::Instant;
+
+    use futures::TryStreamExt;
+
     use crate::{
+        client::{constants::VIDEO_BASE_URL, video::paged_stream},
         error::Result,
-        model::{Account, File},
+        model::{Account, ConflictPolicy, File, Subject, VideoQuality},
         App,
     };
 
@@ -114,6 +140,34 @@ mod test {
         Ok(())
     }
 
+    #[ignore]
+    #[tokio::test]
+    async fn test_get_subjects_parallel() -> Result<()> {
+        tracing_subscriber::fmt::init();
+        let app = App::new();
+        app.init().await?;
+        app.login_video_website().await?;
+
+        let start = Instant::now();
+        let url = format!(
+            "{}/system/course/subject/findSubjectVodList?",
+            VIDEO_BASE_URL
+        );
+        let sequential: Vec<Subject> = paged_stream(app.client.clone(), url).try_collect().await?;
+        let sequential_elapsed = start.elapsed();
+        let start = Instant::now();
+        let parallel = app.client.clone().get_subjects().await?;
+        let parallel_elapsed = start.elapsed();
+        tracing::info!(
+            "{} subjects, sequential: {:?}, parallel: {:?}",
+            parallel.len(),
+            sequential_elapsed,
+            parallel_elapsed
+        );
+        assert_eq!(sequential, parallel);
+        Ok(())
+    }
+
     #[ignore]
     #[tokio::test]
     async fn test_video_apis() -> Result<()> {
@@ -124,10 +178,10 @@ mod test {
         let subjects = app.get_subjects().await?;
         tracing::info!("{:?}", subjects);
         let subject = &subjects[0];
-        let course = app
-            .get_video_course(subject.subject_id, subject.tecl_id)
-            .await?
-            .unwrap();
+        let courses = app
+            .get_video_courses(subject.subject_id, Some(subject.tecl_id))
+            .await?;
+        let course = &courses[0];
         tracing::info!("{:?}", course);
         let video = app.get_video_info(course.response_vo_list[0].id).await?;
         tracing::info!("video = {:?}", video);
@@ -135,6 +189,8 @@ mod test {
         app.download_video(
             &video.video_play_response_vo_list[0],
             "download.mp4",
+            VideoQuality::Auto,
+            ConflictPolicy::Overwrite,
             |_| {},
         )
         .await?;
@@ -151,7 +207,9 @@ mod test {
         let courses = app.list_courses().await?;
         let course = courses.last().unwrap();
         tracing::info!("course = {:?}", course.id);
-        let videos = app.get_canvas_videos(course.id).await?;
+        let videos = app
+            .get_canvas_videos(course.id, None, Default::default())
+            .await?;
         tracing::info!("videos = {:?}", videos.len());
         let video = videos.first().unwrap();
         let video_info = app.get_canvas_video_info(&video.video_id).await?;
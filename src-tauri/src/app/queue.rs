@@ -0,0 +1,174 @@
+use std::{fs, path::Path, time::Instant};
+
+use super::{constants::VIDEO_QUEUE_SAVE_INTERVAL_SECS, App};
+use crate::{
+    error::{AppError, Result},
+    model::{
+        DownloadTaskState, ProgressPayload, QueuedVideoDownload, VideoPlayInfo, VideoQuality,
+        VideoRepairReport,
+    },
+    utils,
+};
+
+#[derive(Default)]
+pub(super) struct VideoQueue {
+    entries: Vec<QueuedVideoDownload>,
+    // progress is only written out now and then, state changes right away
+    saved_at: Option<Instant>,
+}
+
+// The video downloads of the download table, kept in a json file besides the config so
+// a queue interrupted by closing the app can be resumed after a restart
+impl App {
+    fn video_queue_path() -> Result<String> {
+        Ok(format!("{}/video_download_queue.json", App::config_dir()?))
+    }
+
+    pub(super) fn read_video_queue() -> VideoQueue {
+        let mut entries: Vec<QueuedVideoDownload> = App::video_queue_path()
+            .and_then(|path| Ok(fs::read(path)?))
+            .and_then(|content| utils::parse_json(&content))
+            .unwrap_or_default();
+        // nothing is running right after a start, interrupted downloads wait to be resumed
+        for entry in &mut entries {
+            if entry.state == DownloadTaskState::Downloading {
+                entry.state = DownloadTaskState::Queued;
+            }
+        }
+        VideoQueue {
+            entries,
+            saved_at: None,
+        }
+    }
+
+    fn save_video_queue(queue: &mut VideoQueue) -> Result<()> {
+        let content = serde_json::to_vec(&queue.entries)?;
+        fs::write(App::video_queue_path()?, content)?;
+        queue.saved_at = Some(Instant::now());
+        Ok(())
+    }
+
+    fn update_video_queue<F>(&self, update: F) -> Result<()>
+    where
+        F: FnOnce(&mut VideoQueue) -> bool,
+    {
+        let mut queue = self.video_queue.lock().map_err(|_| AppError::MutexError)?;
+        if update(&mut queue) {
+            App::save_video_queue(&mut queue)?;
+        }
+        Ok(())
+    }
+
+    // Adds a download to the queue, a video already in it starts over as queued
+    pub fn queue_video_download(
+        &self,
+        video: &VideoPlayInfo,
+        save_name: &str,
+        quality: VideoQuality,
+    ) -> Result<()> {
+        let entry = QueuedVideoDownload {
+            video: video.clone(),
+            save_name: save_name.to_owned(),
+            quality,
+            state: DownloadTaskState::Queued,
+            updated_at: utils::unix_timestamp(),
+            ..Default::default()
+        };
+        self.update_video_queue(|queue| {
+            match queue
+                .entries
+                .iter_mut()
+                .find(|queued| queued.video.id == video.id)
+            {
+                Some(queued) => *queued = entry,
+                None => queue.entries.push(entry),
+            }
+            true
+        })
+    }
+
+    pub fn update_queued_video_progress(&self, progress: &ProgressPayload) {
+        let result = self.update_video_queue(|queue| {
+            let Some(entry) = queue
+                .entries
+                .iter_mut()
+                .find(|entry| entry.video.id.to_string() == progress.uuid)
+            else {
+                return false;
+            };
+            let started = entry.state != DownloadTaskState::Downloading;
+            entry.state = DownloadTaskState::Downloading;
+            entry.processed = progress.processed;
+            entry.total = progress.total;
+            entry.updated_at = utils::unix_timestamp();
+            let saved_lately = queue.saved_at.is_some_and(|saved_at| {
+                saved_at.elapsed().as_secs() < VIDEO_QUEUE_SAVE_INTERVAL_SECS
+            });
+            started || !saved_lately
+        });
+        if let Err(e) = result {
+            tracing::warn!("failed to save video download queue: {}", e);
+        }
+    }
+
+    pub fn finish_queued_video<T>(&self, video_id: i64, result: &Result<T>) {
+        let result = self.update_video_queue(|queue| {
+            let Some(entry) = queue
+                .entries
+                .iter_mut()
+                .find(|entry| entry.video.id == video_id)
+            else {
+                return false;
+            };
+            match result {
+                Ok(_) => {
+                    entry.state = DownloadTaskState::Succeeded;
+                    entry.processed = entry.total;
+                    entry.error = None;
+                }
+                Err(e) => {
+                    entry.state = DownloadTaskState::Failed;
+                    entry.error = Some(e.to_string());
+                }
+            }
+            entry.updated_at = utils::unix_timestamp();
+            true
+        });
+        if let Err(e) = result {
+            tracing::warn!("failed to save video download queue: {}", e);
+        }
+    }
+
+    pub fn list_video_queue(&self) -> Result<Vec<QueuedVideoDownload>> {
+        let queue = self.video_queue.lock().map_err(|_| AppError::MutexError)?;
+        Ok(queue.entries.clone())
+    }
+
+    pub fn remove_queued_videos(&self, video_ids: &[i64]) -> Result<()> {
+        self.update_video_queue(|queue| {
+            queue
+                .entries
+                .retain(|entry| !video_ids.contains(&entry.video.id));
+            true
+        })
+    }
+
+    // Continues an interrupted download from its `.part` file by refilling what's missing.
+    // None when there's nothing to continue from, the video has to be downloaded again.
+    pub async fn resume_video_download(
+        &self,
+        video: &VideoPlayInfo,
+        save_name: &str,
+        quality: VideoQuality,
+    ) -> Result<Option<VideoRepairReport>> {
+        let save_dir = self.config.read().await.save_path.clone();
+        let part_path = Path::new(&save_dir).join(format!("{}.part", save_name));
+        if !part_path.exists() {
+            return Ok(None);
+        }
+        self.queue_video_download(video, save_name, quality)?;
+        let result = self.repair_video(video, save_name, quality).await;
+        self.finish_queued_video(video.id, &result);
+        result.map(Some)
+    }
+}
@@ -0,0 +1,58 @@
+use tauri::{AppHandle, Manager};
+
+use super::{constants::DOWNLOAD_TASK_EVENT, App};
+use crate::{
+    error::Result,
+    model::{DownloadTask, DownloadTaskState, ProgressPayload},
+};
+
+// Download tasks are tracked here rather than by every command, so the progress of
+// any download can be listed and listened to the same way
+impl App {
+    pub fn set_app_handle(&self, handle: AppHandle) {
+        let _ = self.app_handle.set(handle);
+    }
+
+    fn update_download_task(&self, task: DownloadTask) {
+        if let Ok(mut tasks) = self.download_tasks.lock() {
+            tasks.insert(task.task_id.clone(), task.clone());
+        }
+        if let Some(handle) = self.app_handle.get() {
+            let _ = handle.emit_all(DOWNLOAD_TASK_EVENT, task);
+        }
+    }
+
+    // Progress closure for the download apis of the client, built on the event path
+    pub fn download_progress_handler(
+        &'static self,
+    ) -> impl Fn(ProgressPayload) + Send + Sync + 'static {
+        move |progress| self.update_download_task(progress.into())
+    }
+
+    pub fn finish_download_task<T>(&self, task_id: &str, result: &Result<T>) {
+        let mut task = self
+            .download_tasks
+            .lock()
+            .ok()
+            .and_then(|tasks| tasks.get(task_id).cloned())
+            .unwrap_or_else(|| DownloadTask {
+                task_id: task_id.to_owned(),
+                ..Default::default()
+            });
+        match result {
+            Ok(_) => task.state = DownloadTaskState::Succeeded,
+            Err(e) => {
+                task.state = DownloadTaskState::Failed;
+                task.error = Some(e.to_string());
+            }
+        }
+        self.update_download_task(task);
+    }
+
+    pub fn list_download_tasks(&self) -> Vec<DownloadTask> {
+        self.download_tasks
+            .lock()
+            .map(|tasks| tasks.values().cloned().collect())
+            .unwrap_or_default()
+    }
+}
@@ -1,18 +1,120 @@
-use std::path::Path;
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::NaiveDate;
+use tokio::{process::Command, sync::Semaphore, task::JoinSet};
 
-use super::App;
+use super::{
+    constants::{
+        CANVAS_COURSE_ID_CACHE_KEY_PREFIX, CANVAS_COURSE_ID_CACHE_TTL_SECS, QR_LOGIN_EXPIRE_SECS,
+        QR_LOGIN_POLL_WAIT_MS, SUBJECTS_CACHE_KEY, SUBJECTS_CACHE_TTL_SECS,
+        VIDEO_API_VERSION_CACHE_KEY, VIDEO_COURSES_CACHE_KEY_PREFIX, VIDEO_COURSES_CACHE_TTL_SECS,
+        VIDEO_INFO_MAX_ATTEMPTS, VIDEO_LIST_INFO_CONCURRENCY, VIDEO_SEARCH_CONCURRENCY,
+    },
+    App,
+};
 use crate::{
+    client::{
+        constants::VIDEO_REFERER,
+        video::{
+            list_terms, recommended_videos, render_video_list, signed_url_expiry,
+            sort_canvas_videos, subject_classes, video_direct_urls, video_list_entry,
+            video_search_hits,
+        },
+        Client, QrLoginSocket,
+    },
     error::{AppError, Result},
-    model::{CanvasVideo, ProgressPayload, Subject, VideoCourse, VideoInfo, VideoPlayInfo},
+    model::{
+        ApiVersion, AudioFormat, CachedCanvasCourseId, CachedSubjects, CachedVideoCourses,
+        CanvasVideo, CanvasVideoSortKey, ConflictPolicy, DownloadResult, DownloadSource,
+        LoginStatus, MergeLayout, ProgressPayload, ProgressStage, QrSession, SortOrder, Subject,
+        SubjectClass, Term, ThumbnailReady, TranscriptSegment, Video, VideoClipRange, VideoCourse,
+        VideoDirectUrl, VideoDownloadFailure, VideoDownloadReport, VideoDownloadResult,
+        VideoFilenameFields, VideoIdSource, VideoInfo, VideoListFormat, VideoPlayInfo,
+        VideoPlaylist, VideoQuality, VideoRepairReport, VideoSearchHit, VideoStream,
+        VideoStreamsDownload, VideoUrlStatus, WatchRecord,
+    },
+    utils,
 };
+
+pub struct QrLogin {
+    socket: QrLoginSocket,
+    started_at: Instant,
+}
+
 // Apis for course video
 impl App {
-    pub async fn get_uuid(&self) -> Result<Option<String>> {
+    pub async fn get_uuid(&self) -> Result<String> {
         self.client.get_uuid().await
     }
 
     pub async fn express_login(&self, uuid: &str) -> Result<Option<String>> {
-        self.client.express_login(uuid).await
+        let cookie = self.client.express_login(uuid).await?;
+        if cookie.is_some() {
+            self.save_session().await?;
+        }
+        Ok(cookie)
+    }
+
+    // Expired codes are dropped on the next poll, call this again for a new one
+    pub async fn start_qr_login(&self) -> Result<QrSession> {
+        let (session, socket) = self.client.start_qr_login().await?;
+        self.qr_logins.lock().await.insert(
+            session.uuid.clone(),
+            QrLogin {
+                socket,
+                started_at: Instant::now(),
+            },
+        );
+        Ok(session)
+    }
+
+    // Once confirmed the JAAuthCookie is saved to the config, so `login_video_website`
+    // and `login_canvas_website` can be called right away
+    pub async fn poll_qr_login(&self, uuid: &str) -> Result<LoginStatus> {
+        let mut logins = self.qr_logins.lock().await;
+        let Some(login) = logins.get_mut(uuid) else {
+            return Ok(LoginStatus::Expired);
+        };
+        if login.started_at.elapsed() > Duration::from_secs(QR_LOGIN_EXPIRE_SECS) {
+            logins.remove(uuid);
+            return Ok(LoginStatus::Expired);
+        }
+        let wait = Duration::from_millis(QR_LOGIN_POLL_WAIT_MS);
+        match tokio::time::timeout(wait, Client::wait_qr_login(&mut login.socket)).await {
+            Err(_) => return Ok(LoginStatus::Pending),
+            Ok(Ok(true)) => (),
+            Ok(Ok(false)) => {
+                logins.remove(uuid);
+                return Ok(LoginStatus::Expired);
+            }
+            Ok(Err(e)) => {
+                tracing::error!("qr login channel of {} broken: {}", uuid, e);
+                logins.remove(uuid);
+                return Ok(LoginStatus::Expired);
+            }
+        }
+        logins.remove(uuid);
+        drop(logins);
+
+        let Some(cookie) = self.express_login(uuid).await? else {
+            return Err(AppError::LoginError);
+        };
+        let mut config = self.get_config().await;
+        config.ja_auth_cookie = cookie;
+        self.save_config(config).await?;
+        Ok(LoginStatus::Confirmed)
+    }
+
+    pub async fn check_session_valid(&self) -> Result<bool> {
+        self.client.check_session_valid().await
     }
 
     pub async fn get_cookie(&self) -> String {
@@ -24,10 +126,12 @@ impl App {
         if let Some(cookies) = self.client.login_video_website(&cookie).await? {
             let mut config = self.get_config().await;
             config.video_cookies = cookies;
-            if let Ok(Some(consumer_key)) = self.client.get_oauth_consumer_key().await {
-                config.oauth_consumer_key = consumer_key;
+            match self.client.get_oauth_consumer_key().await {
+                Ok(consumer_key) => config.oauth_consumer_key = consumer_key,
+                Err(e) => tracing::warn!("failed to get oauth consumer key: {}", e),
             }
             self.save_config(config).await?;
+            self.save_session().await?;
             Ok(())
         } else {
             Err(AppError::LoginError)
@@ -39,42 +143,1078 @@ impl App {
         self.client.login_canvas_website(&cookie).await
     }
 
+    pub async fn get_video_platform_api_version(&self) -> Result<ApiVersion> {
+        if let Some(version) = self.cache.get(VIDEO_API_VERSION_CACHE_KEY)? {
+            return Ok(version);
+        }
+        let version = self.client.detect_video_platform_api_version().await?;
+        self.cache.set(VIDEO_API_VERSION_CACHE_KEY, version)?;
+        Ok(version)
+    }
+
+    // feature flag for newer video platform apis, e.g. `ApiVersion::new(2, 0, 0)`
+    pub async fn video_platform_supports(&self, min_version: ApiVersion) -> bool {
+        match self.get_video_platform_api_version().await {
+            Ok(version) => version >= min_version,
+            Err(e) => {
+                tracing::warn!("failed to detect video platform api version: {}", e);
+                false
+            }
+        }
+    }
+
     pub async fn get_subjects(&self) -> Result<Vec<Subject>> {
-        self.client.get_subjects().await
+        self.client.clone().get_subjects().await
+    }
+
+    fn fresh_cached_subjects(&self) -> Result<Option<Vec<Subject>>> {
+        let cached: Option<CachedSubjects> = self.cache.get(SUBJECTS_CACHE_KEY)?;
+        Ok(cached
+            .filter(|cached| {
+                utils::unix_timestamp().saturating_sub(cached.fetched_at) < SUBJECTS_CACHE_TTL_SECS
+            })
+            .map(|cached| cached.subjects))
+    }
+
+    // Paging through every subject takes seconds, so the list is reused while fresh
+    // unless `force_refresh` is set
+    pub async fn get_subjects_cached(&self, force_refresh: bool) -> Result<Vec<Subject>> {
+        if !force_refresh {
+            if let Some(subjects) = self.fresh_cached_subjects()? {
+                return Ok(subjects);
+            }
+        }
+        let _guard = self.subjects_refresh.lock().await;
+        // another caller may have fetched the list while this one was waiting
+        if !force_refresh {
+            if let Some(subjects) = self.fresh_cached_subjects()? {
+                return Ok(subjects);
+            }
+        }
+        let subjects = self.get_subjects().await?;
+        self.cache.set(
+            SUBJECTS_CACHE_KEY,
+            CachedSubjects {
+                fetched_at: utils::unix_timestamp(),
+                subjects: subjects.clone(),
+            },
+        )?;
+        Ok(subjects)
+    }
+
+    // The subject api has no term parameter, terms come from the cached subject list
+    pub async fn get_terms(&self, force_refresh: bool) -> Result<Vec<Term>> {
+        let subjects = self.get_subjects_cached(force_refresh).await?;
+        Ok(list_terms(&subjects))
+    }
+
+    pub async fn get_video_platform_history_by_date(
+        &self,
+        date: NaiveDate,
+    ) -> Result<Vec<WatchRecord>> {
+        self.client
+            .clone()
+            .get_video_platform_history_by_date(date)
+            .await
     }
 
     pub async fn get_video_info(&self, video_id: i64) -> Result<VideoInfo> {
         let consumer_key = &self.config.read().await.oauth_consumer_key;
-        self.client.get_video_info(video_id, consumer_key).await
+        self.client
+            .get_video_info_with_retry(video_id, consumer_key, VIDEO_INFO_MAX_ATTEMPTS)
+            .await
+    }
+
+    pub async fn get_video_info_diff(
+        &self,
+        video_id: i64,
+        cached: &VideoInfo,
+    ) -> Result<Option<Vec<String>>> {
+        let consumer_key = &self.config.read().await.oauth_consumer_key;
+        self.client
+            .get_video_info_diff(video_id, consumer_key, cached)
+            .await
+    }
+
+    // Summary line of a video for copying, the size is that of its main stream
+    pub async fn get_video_summary(&self, video: &VideoInfo, quality: VideoQuality) -> String {
+        let size = match video.video_play_response_vo_list.first() {
+            Some(play) => self
+                .client
+                .get_video_play_size(play, quality)
+                .await
+                .ok()
+                .filter(|size| *size > 0),
+            None => None,
+        };
+        video.summary(size)
     }
 
     pub async fn get_canvas_video_info(&self, video_id: &str) -> Result<VideoInfo> {
         self.client.get_canvas_video_info(video_id).await
     }
 
-    pub async fn get_canvas_videos(&self, course_id: i64) -> Result<Vec<CanvasVideo>> {
-        self.client.get_canvas_videos(course_id).await
+    // Stream links of a recording for use outside the app, one per camera
+    pub async fn get_video_direct_url(
+        &self,
+        video_id: &str,
+        source: VideoIdSource,
+        quality: VideoQuality,
+    ) -> Result<Vec<VideoDirectUrl>> {
+        let info = match source {
+            VideoIdSource::Canvas => self.get_canvas_video_info(video_id).await?,
+            VideoIdSource::Platform => {
+                let id = video_id
+                    .parse()
+                    .map_err(|_| AppError::InvalidVideoId(video_id.to_owned()))?;
+                self.get_video_info(id).await?
+            }
+        };
+        let urls = video_direct_urls(&info, quality);
+        if urls.is_empty() {
+            return Err(AppError::NoDownloadableStream(video_id.to_owned()));
+        }
+        Ok(urls)
+    }
+
+    fn canvas_course_id_cache_key(course_id: i64) -> String {
+        format!("{}{}", CANVAS_COURSE_ID_CACHE_KEY_PREFIX, course_id)
+    }
+
+    // Resolving the id takes a page scrape plus an lti launch, so it's reused while fresh
+    async fn canvas_course_id(&self, course_id: i64) -> Result<Option<String>> {
+        let cached: Option<CachedCanvasCourseId> = self
+            .cache
+            .get(&App::canvas_course_id_cache_key(course_id))?;
+        match cached {
+            Some(cached)
+                if utils::unix_timestamp().saturating_sub(cached.fetched_at)
+                    < CANVAS_COURSE_ID_CACHE_TTL_SECS =>
+            {
+                Ok(Some(cached.canvas_course_id))
+            }
+            _ => self.refresh_canvas_course_id(course_id).await,
+        }
+    }
+
+    pub async fn refresh_canvas_course_id(&self, course_id: i64) -> Result<Option<String>> {
+        let key = App::canvas_course_id_cache_key(course_id);
+        let token = self.config.read().await.token.clone();
+        let canvas_course_id = self.client.to_canvas_course_id(course_id, &token).await?;
+        match &canvas_course_id {
+            Some(id) => self.cache.set(
+                key,
+                CachedCanvasCourseId {
+                    fetched_at: utils::unix_timestamp(),
+                    canvas_course_id: id.clone(),
+                },
+            )?,
+            None => {
+                self.cache.remove::<CachedCanvasCourseId>(&key)?;
+            }
+        }
+        Ok(canvas_course_id)
+    }
+
+    // Without `sort_by` the videos keep the order of the platform
+    pub async fn get_canvas_videos(
+        &self,
+        course_id: i64,
+        sort_by: Option<CanvasVideoSortKey>,
+        order: SortOrder,
+    ) -> Result<Vec<CanvasVideo>> {
+        let mut videos = self.list_canvas_videos(course_id).await?;
+        if let Some(sort_by) = sort_by {
+            sort_canvas_videos(&mut videos, sort_by, order);
+        }
+        Ok(videos)
+    }
+
+    pub async fn get_recommended_videos(
+        &self,
+        course_id: i64,
+        video_id: &str,
+    ) -> Result<Vec<CanvasVideo>> {
+        let videos = self.list_canvas_videos(course_id).await?;
+        Ok(recommended_videos(videos, video_id))
+    }
+
+    // The details of a recording are only fetched when the list lacks its duration or the
+    // download urls are wanted, a recording whose details fail is exported without them
+    pub async fn export_video_list(
+        &self,
+        course_id: i64,
+        format: VideoListFormat,
+        save_name: &str,
+        include_urls: bool,
+    ) -> Result<()> {
+        let mut videos = self.list_canvas_videos(course_id).await?;
+        sort_canvas_videos(&mut videos, CanvasVideoSortKey::Date, SortOrder::Asc);
+        let infos = self
+            .get_canvas_video_infos(&videos, |video| {
+                include_urls || video.duration_secs().is_none()
+            })
+            .await?;
+
+        let entries: Vec<_> = videos
+            .iter()
+            .zip(infos)
+            .map(|(video, info)| {
+                let info = match info {
+                    Some(Ok(info)) => Some(info),
+                    Some(Err(e)) => {
+                        tracing::warn!("failed to get info of video {}: {}", video.video_name, e);
+                        None
+                    }
+                    None => None,
+                };
+                let mut entry = video_list_entry(video, info.as_ref(), VideoQuality::Auto);
+                if !include_urls {
+                    entry.download_urls.clear();
+                }
+                entry
+            })
+            .collect();
+        let save_path = self.config.read().await.save_path.clone();
+        let path = Path::new(&save_path).join(save_name);
+        fs::write(path, render_video_list(&entries, format, include_urls)?)?;
+        Ok(())
+    }
+
+    // Writes an m3u8 playlist of the course's recordings for external players. `refresh`
+    // rewrites an existing playlist with fresh links instead of saving a new one beside it.
+    pub async fn export_video_playlist(
+        &self,
+        course_id: i64,
+        save_name: &str,
+        refresh: bool,
+    ) -> Result<VideoPlaylist> {
+        let mut videos = self.list_canvas_videos(course_id).await?;
+        sort_canvas_videos(&mut videos, CanvasVideoSortKey::Date, SortOrder::Asc);
+        let infos = self.get_canvas_video_infos(&videos, |_| true).await?;
+
+        let mut entries = vec![];
+        let mut failed = vec![];
+        for (video, info) in videos.iter().zip(infos) {
+            let name = format!("{} {}", video.video_name, video.course_begin_time);
+            let info = match info {
+                Some(Ok(info)) => info,
+                Some(Err(e)) => {
+                    failed.push(VideoDownloadFailure {
+                        name,
+                        error: e.to_string(),
+                    });
+                    continue;
+                }
+                None => continue,
+            };
+            let urls: Vec<_> = info
+                .video_play_response_vo_list
+                .iter()
+                .filter_map(|play| play.select_url(VideoQuality::Auto))
+                .collect();
+            if urls.is_empty() {
+                let error = AppError::NoPlayableVideoUrl(info.id).to_string();
+                failed.push(VideoDownloadFailure { name, error });
+                continue;
+            }
+            // every camera of a recording gets its own entry
+            for (index, url) in urls.iter().enumerate() {
+                let title = if urls.len() > 1 {
+                    format!("{} ({})", name, index + 1)
+                } else {
+                    name.clone()
+                };
+                entries.push(utils::M3uEntry {
+                    title,
+                    duration_secs: Some(info.vide_play_time),
+                    url: url.to_string(),
+                });
+            }
+        }
+
+        let expires_at = entries
+            .iter()
+            .filter_map(|entry| signed_url_expiry(&entry.url))
+            .min();
+        let save_path = self.config.read().await.save_path.clone();
+        let mut path = Path::new(&save_path).join(save_name);
+        if !refresh && path.exists() {
+            path = utils::unique_path(&path);
+        }
+        fs::write(
+            &path,
+            utils::export_m3u(&entries, VIDEO_REFERER, expires_at),
+        )?;
+        Ok(VideoPlaylist {
+            path: path.to_string_lossy().into_owned(),
+            entries: entries.len(),
+            failed,
+            expires_at,
+        })
+    }
+
+    // Details of the `wanted` videos, VIDEO_LIST_INFO_CONCURRENCY at a time. The result
+    // lines up with `videos`, None for a video that wasn't wanted.
+    async fn get_canvas_video_infos<F: Fn(&CanvasVideo) -> bool>(
+        &self,
+        videos: &[CanvasVideo],
+        wanted: F,
+    ) -> Result<Vec<Option<Result<VideoInfo>>>> {
+        let mut infos: Vec<_> = videos.iter().map(|_| None).collect();
+        let semaphore = Arc::new(Semaphore::new(VIDEO_LIST_INFO_CONCURRENCY));
+        let mut tasks = JoinSet::new();
+        for (index, video) in videos.iter().enumerate() {
+            if !wanted(video) {
+                continue;
+            }
+            let client = self.client.clone();
+            let semaphore = semaphore.clone();
+            let video_id = video.video_id.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                (index, client.get_canvas_video_info(&video_id).await)
+            });
+        }
+        while let Some(res) = tasks.join_next().await {
+            let (index, info) = res?;
+            infos[index] = Some(info);
+        }
+        Ok(infos)
+    }
+
+    async fn list_canvas_videos(&self, course_id: i64) -> Result<Vec<CanvasVideo>> {
+        let Some(canvas_course_id) = self.canvas_course_id(course_id).await? else {
+            return Ok(vec![]);
+        };
+        match self.client.list_canvas_videos(&canvas_course_id).await {
+            // the lti session behind the cached id has expired, launch the tool again
+            Err(AppError::Unauthorized) => {
+                let Some(canvas_course_id) = self.refresh_canvas_course_id(course_id).await? else {
+                    return Ok(vec![]);
+                };
+                self.client.list_canvas_videos(&canvas_course_id).await
+            }
+            result => result,
+        }
+    }
+
+    // Covers are cached in the config dir, so refreshing the list only fetches new ones
+    fn video_covers_dir() -> Result<String> {
+        let dir = format!("{}/video_covers", App::config_dir()?);
+        App::ensure_directory(&dir);
+        Ok(dir)
+    }
+
+    pub async fn download_video_cover(&self, video_id: &str) -> Result<Option<String>> {
+        self.client
+            .download_video_cover(video_id, &App::video_covers_dir()?)
+            .await
+    }
+
+    pub async fn prefetch_thumbnails<F: Fn(ThumbnailReady)>(
+        &self,
+        videos: Vec<CanvasVideo>,
+        ready_handler: F,
+    ) -> Result<HashMap<String, String>> {
+        self.client
+            .clone()
+            .prefetch_thumbnails(videos, &App::video_covers_dir()?, ready_handler)
+            .await
+    }
+
+    // Covers of a whole course by video id. Videos without cover or whose cover failed
+    // are left out, the list shouldn't break because of a missing image.
+    pub async fn download_course_video_covers(
+        &self,
+        course_id: i64,
+    ) -> Result<HashMap<String, String>> {
+        let videos = self.list_canvas_videos(course_id).await?;
+        self.prefetch_thumbnails(videos, |_| ()).await
+    }
+
+    pub async fn default_conflict_policy(&self) -> ConflictPolicy {
+        self.config.read().await.conflict_policy
     }
 
     pub async fn download_video<F: Fn(ProgressPayload) + Send + 'static>(
         &self,
         video: &VideoPlayInfo,
         save_name: &str,
+        quality: VideoQuality,
+        conflict_policy: ConflictPolicy,
         progress_handler: F,
+    ) -> Result<DownloadResult> {
+        let save_dir = self.config.read().await.save_path.clone();
+        let save_path = Path::new(&save_dir).join(save_name);
+        let source = DownloadSource::Video {
+            video: video.clone(),
+            save_name: save_name.to_owned(),
+            quality,
+        };
+        self.record_download(
+            save_name,
+            None,
+            &save_path,
+            source,
+            self.client.clone().download_video(
+                video,
+                save_path.to_str().unwrap(),
+                quality,
+                conflict_policy,
+                progress_handler,
+            ),
+        )
+        .await
+    }
+
+    pub async fn probe_video_urls(
+        &self,
+        video: &VideoPlayInfo,
+        quality: VideoQuality,
+    ) -> VideoUrlStatus {
+        self.client.probe_video_urls(video, quality).await
+    }
+
+    pub async fn repair_video(
+        &self,
+        video: &VideoPlayInfo,
+        save_name: &str,
+        quality: VideoQuality,
+    ) -> Result<VideoRepairReport> {
+        let save_dir = self.config.read().await.save_path.clone();
+        let save_path = Path::new(&save_dir).join(save_name);
+        self.client
+            .repair_video(video, save_path.to_str().unwrap(), quality)
+            .await
+    }
+
+    // Writes the `.info.json` sidecar of a downloaded video if enabled in the config
+    pub async fn save_video_metadata(
+        &self,
+        video: &VideoInfo,
+        play: &VideoPlayInfo,
+        save_name: &str,
+        source_url: &str,
+        clip: Option<VideoClipRange>,
+    ) -> Result<()> {
+        let config = self.config.read().await;
+        if !config.write_video_metadata {
+            return Ok(());
+        }
+        let save_path = Path::new(&config.save_path).join(save_name);
+        Client::save_video_metadata(save_path.to_str().unwrap(), video, play, source_url, clip)
+    }
+
+    // Writes the subtitles of a downloaded video if enabled in the config. A missing transcript
+    // shouldn't fail the download, so errors are only logged.
+    pub async fn save_video_subtitles(&self, video: &VideoInfo, save_name: &str) {
+        let (save_dir, sidecars) = {
+            let config = self.config.read().await;
+            (config.save_path.clone(), config.video_sidecars())
+        };
+        if !sidecars.srt && !sidecars.vtt {
+            return;
+        }
+        let save_path = Path::new(&save_dir).join(save_name);
+        let save_path = save_path.to_str().unwrap();
+        if let Err(e) = self
+            .client
+            .save_video_subtitles(video.id, save_path, sidecars.srt, sidecars.vtt)
+            .await
+        {
+            tracing::warn!("failed to save subtitles of {}: {}", save_path, e);
+        }
+    }
+
+    pub async fn copy_video_subtitles_to_sidecar(&self, video_path: &str) -> Result<()> {
+        self.client
+            .copy_video_subtitles_to_sidecar(video_path)
+            .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn download_video_clip<F: Fn(ProgressPayload)>(
+        &self,
+        video: &VideoPlayInfo,
+        video_info: Option<&VideoInfo>,
+        save_name: &str,
+        quality: VideoQuality,
+        start: f64,
+        end: f64,
+        progress_handler: F,
+    ) -> Result<VideoClipRange> {
+        let save_dir = self.config.read().await.save_path.clone();
+        let save_path = Path::new(&save_dir).join(save_name);
+        let range = self
+            .client
+            .download_video_clip(
+                video,
+                save_path.to_str().unwrap(),
+                quality,
+                start,
+                end,
+                progress_handler,
+            )
+            .await?;
+        if let Some(video_info) = video_info {
+            let source_url = video.select_url(quality).unwrap_or_default();
+            self.save_video_metadata(video_info, video, save_name, source_url, Some(range))
+                .await?;
+        }
+        Ok(range)
+    }
+
+    pub async fn get_video_transcript(&self, video_id: i64) -> Result<Vec<TranscriptSegment>> {
+        self.client.get_video_transcript(video_id).await
+    }
+
+    pub async fn get_canvas_video_transcript(
+        &self,
+        video_id: &str,
+    ) -> Result<Vec<TranscriptSegment>> {
+        self.client.get_canvas_video_transcript(video_id).await
+    }
+
+    pub async fn get_video_platform_subtitle_languages(
+        &self,
+        video_id: i64,
+    ) -> Result<Vec<String>> {
+        self.client
+            .get_video_platform_subtitle_languages(video_id)
+            .await
+    }
+
+    pub async fn check_video_disk_space(
+        &self,
+        video: &VideoPlayInfo,
+        quality: VideoQuality,
+    ) -> Result<()> {
+        let save_dir = self.config.read().await.save_path.clone();
+        let needed = self.client.get_video_play_size(video, quality).await?;
+        utils::ensure_disk_space(&save_dir, needed)
+    }
+
+    pub async fn check_course_videos_disk_space(
+        &self,
+        course_id: i64,
+        save_dir: &str,
+        quality: VideoQuality,
     ) -> Result<()> {
+        App::ensure_directory(save_dir);
+        let videos = self.list_canvas_videos(course_id).await?;
+        let needed = self.client.get_course_videos_size(&videos, quality).await?;
+        utils::ensure_disk_space(save_dir, needed)
+    }
+
+    async fn run_ffmpeg<I, S>(args: I) -> Result<bool>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        if !App::is_ffmpeg_installed() {
+            return Err(AppError::FfmpegNotFound);
+        }
+        let status = Command::new("ffmpeg")
+            .arg("-y")
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await?;
+        if !status.success() {
+            tracing::error!("ffmpeg exited with {:?}", status.code());
+        }
+        Ok(status.success())
+    }
+
+    // Remux a bare flv/ts stream into a standard mp4 container without re-encoding,
+    // returns the size of the remuxed file
+    pub async fn remux_video(&self, save_name: &str) -> Result<u64> {
+        let save_dir = self.config.read().await.save_path.clone();
+        let save_path = Path::new(&save_dir).join(save_name);
+        let remux_path = save_path.with_extension("remux.mp4");
+        let args = [
+            OsStr::new("-i"),
+            save_path.as_os_str(),
+            OsStr::new("-c"),
+            OsStr::new("copy"),
+            OsStr::new("-f"),
+            OsStr::new("mp4"),
+            remux_path.as_os_str(),
+        ];
+        if !Self::run_ffmpeg(args).await? {
+            let _ = fs::remove_file(&remux_path);
+            return Err(AppError::VideoRemuxError(save_name.to_owned()));
+        }
+        fs::rename(&remux_path, &save_path)?;
+        tracing::info!("Successfully remuxed video {:?}", save_path);
+        Ok(fs::metadata(&save_path)?.len())
+    }
+
+    // Download the video to a temporary file first, then extract its audio track next to it and
+    // drop the video. `bitrate` is in kbps, the original audio track is kept when it's None.
+    pub async fn download_audio_only<F: Fn(ProgressPayload) + Send + Sync + 'static>(
+        &self,
+        video: &VideoPlayInfo,
+        save_name: &str,
+        quality: VideoQuality,
+        format: AudioFormat,
+        bitrate: Option<u32>,
+        progress_handler: F,
+    ) -> Result<String> {
+        // fail before downloading a whole video that can't be turned into audio
+        if !App::is_ffmpeg_installed() {
+            return Err(AppError::FfmpegNotFound);
+        }
+        let save_dir = self.config.read().await.save_path.clone();
+        let audio_path = Path::new(&save_dir)
+            .join(save_name)
+            .with_extension(format.extension());
+        let tmp_name = format!("{}.audio_tmp.mp4", save_name);
+        let video_path = Path::new(&save_dir).join(&tmp_name);
+        let progress_handler = Arc::new(progress_handler);
+        let download_progress_handler = progress_handler.clone();
+        self.download_video(
+            video,
+            &tmp_name,
+            quality,
+            ConflictPolicy::Overwrite,
+            move |progress| download_progress_handler(progress),
+        )
+        .await?;
+
+        let mut payload = ProgressPayload {
+            uuid: video.id.to_string(),
+            processed: 0,
+            total: 1,
+            stage: ProgressStage::Extract,
+        };
+        progress_handler(payload.clone());
+        let codec_args = match (format, bitrate) {
+            (AudioFormat::M4a, None) => vec!["-c:a".to_owned(), "copy".to_owned()],
+            (AudioFormat::M4a, Some(bitrate)) => {
+                vec![
+                    "-c:a".to_owned(),
+                    "aac".to_owned(),
+                    "-b:a".to_owned(),
+                    format!("{}k", bitrate),
+                ]
+            }
+            (AudioFormat::Mp3, None) => vec![
+                "-c:a".to_owned(),
+                "libmp3lame".to_owned(),
+                "-q:a".to_owned(),
+                "2".to_owned(),
+            ],
+            (AudioFormat::Mp3, Some(bitrate)) => vec![
+                "-c:a".to_owned(),
+                "libmp3lame".to_owned(),
+                "-b:a".to_owned(),
+                format!("{}k", bitrate),
+            ],
+        };
+        let mut args = vec![OsStr::new("-i"), video_path.as_os_str(), OsStr::new("-vn")];
+        args.extend(codec_args.iter().map(OsStr::new));
+        args.push(audio_path.as_os_str());
+        let extracted = Self::run_ffmpeg(args).await;
+        let _ = fs::remove_file(&video_path);
+        let _ = fs::remove_file(format!("{}.md5", video_path.to_string_lossy()));
+        if !extracted? {
+            let _ = fs::remove_file(&audio_path);
+            return Err(AppError::AudioExtractError(save_name.to_owned()));
+        }
+        payload.processed = 1;
+        progress_handler(payload);
+        tracing::info!("Successfully extracted audio to {:?}", audio_path);
+        Ok(audio_path.to_string_lossy().into_owned())
+    }
+
+    // Download the camera stream, the screen stream or both of a recording. The streams are
+    // the first two plays of the video, in that order.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn download_video_streams<F: Fn(ProgressPayload) + Send + Sync + 'static>(
+        &self,
+        video: &VideoInfo,
+        video_name: &str,
+        stream: VideoStream,
+        quality: VideoQuality,
+        layout: MergeLayout,
+        conflict_policy: ConflictPolicy,
+        progress_handler: F,
+    ) -> Result<VideoStreamsDownload> {
+        let index = match stream {
+            VideoStream::Camera => 0,
+            VideoStream::Screen => 1,
+            VideoStream::Both => {
+                return self
+                    .download_merged_video(
+                        video,
+                        video_name,
+                        quality,
+                        layout,
+                        conflict_policy,
+                        progress_handler,
+                    )
+                    .await
+            }
+        };
+        let Some(play) = video.video_play_response_vo_list.get(index) else {
+            return Err(AppError::NoPlayableVideoUrl(video.id));
+        };
+        let save_name = Client::video_play_save_name(video_name, index);
+        let result = self
+            .download_video(play, &save_name, quality, conflict_policy, progress_handler)
+            .await?;
+        Ok(VideoStreamsDownload {
+            paths: vec![result.path],
+            merge_skipped: false,
+        })
+    }
+
+    // Download the teacher and courseware streams of a dual-camera recording and merge them into
+    // one video. Recordings with a single stream are downloaded as is, and so are both streams
+    // when ffmpeg isn't installed. Progress of the downloads is reported as a whole under the
+    // video id, followed by the merging stage. `conflict_policy` applies to the merged video
+    // and to the streams alike.
+    pub async fn download_merged_video<F: Fn(ProgressPayload) + Send + Sync + 'static>(
+        &self,
+        video: &VideoInfo,
+        video_name: &str,
+        quality: VideoQuality,
+        layout: MergeLayout,
+        conflict_policy: ConflictPolicy,
+        progress_handler: F,
+    ) -> Result<VideoStreamsDownload> {
+        let save_dir = self.config.read().await.save_path.clone();
+        let progress_handler = Arc::new(progress_handler);
+        let plays = &video.video_play_response_vo_list;
+        let mut output_path = Path::new(&save_dir).join(format!("{}_合成.mp4", video_name));
+        if plays.len() >= 2 && output_path.exists() {
+            match conflict_policy {
+                ConflictPolicy::Overwrite => {}
+                ConflictPolicy::Skip => {
+                    tracing::info!("{:?} already exists, skip", output_path);
+                    return Ok(VideoStreamsDownload {
+                        paths: vec![output_path.to_string_lossy().into_owned()],
+                        merge_skipped: false,
+                    });
+                }
+                ConflictPolicy::Rename => output_path = utils::unique_path(&output_path),
+            }
+        }
+        // (processed, total) of every stream, keyed by play id
+        let stream_progress = Arc::new(Mutex::new(HashMap::new()));
+        let mut play_paths = vec![];
+        // streams that were on disk before are the user's and are kept after merging
+        let mut downloaded = vec![];
+        for (index, play) in plays.iter().take(2).enumerate() {
+            let save_name = Client::video_play_save_name(video_name, index);
+            let download_progress_handler = progress_handler.clone();
+            let stream_progress = stream_progress.clone();
+            let uuid = video.id.to_string();
+            let result = self
+                .download_video(
+                    play,
+                    &save_name,
+                    quality,
+                    conflict_policy,
+                    move |progress| {
+                        let mut stream_progress = stream_progress.lock().unwrap();
+                        stream_progress.insert(progress.uuid, (progress.processed, progress.total));
+                        let (processed, total) = stream_progress
+                            .values()
+                            .fold((0, 0), |(p, t), (processed, total)| {
+                                (p + processed, t + total)
+                            });
+                        download_progress_handler(ProgressPayload {
+                            uuid: uuid.clone(),
+                            processed,
+                            total,
+                            ..Default::default()
+                        });
+                    },
+                )
+                .await?;
+            downloaded.push(result.status == VideoDownloadResult::Downloaded);
+            play_paths.push(PathBuf::from(result.path));
+        }
+        let paths = play_paths
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        if play_paths.is_empty() {
+            return Err(AppError::NoPlayableVideoUrl(video.id));
+        }
+        if play_paths.len() < 2 {
+            return Ok(VideoStreamsDownload {
+                paths,
+                merge_skipped: false,
+            });
+        }
+        if !App::is_ffmpeg_installed() {
+            tracing::warn!(
+                "ffmpeg not found, keep the streams of {} unmerged",
+                video_name
+            );
+            return Ok(VideoStreamsDownload {
+                paths,
+                merge_skipped: true,
+            });
+        }
+        let (teacher_path, courseware_path) = (&play_paths[0], &play_paths[1]);
+
+        let mut payload = ProgressPayload {
+            uuid: video.id.to_string(),
+            processed: 0,
+            total: 1,
+            stage: ProgressStage::Merge,
+        };
+        progress_handler(payload.clone());
+        let filter = match layout {
+            MergeLayout::SideBySide => "[0:v]scale=-2:720[l];[1:v]scale=-2:720[r];[l][r]hstack[v]",
+            MergeLayout::PictureInPicture => {
+                "[0:v]scale=iw/4:-2[pip];[1:v][pip]overlay=W-w-10:H-h-10[v]"
+            }
+        };
+        let args = [
+            OsStr::new("-i"),
+            teacher_path.as_os_str(),
+            OsStr::new("-i"),
+            courseware_path.as_os_str(),
+            OsStr::new("-filter_complex"),
+            OsStr::new(filter),
+            OsStr::new("-map"),
+            OsStr::new("[v]"),
+            OsStr::new("-map"),
+            OsStr::new("0:a?"),
+            OsStr::new("-c:a"),
+            OsStr::new("copy"),
+            output_path.as_os_str(),
+        ];
+        if !Self::run_ffmpeg(args).await? {
+            // keep both streams so the user still has the recording
+            let _ = fs::remove_file(&output_path);
+            return Err(AppError::VideoMergeError(video_name.to_owned()));
+        }
+        for (path, downloaded) in play_paths.iter().zip(downloaded) {
+            if downloaded {
+                fs::remove_file(path)?;
+            }
+        }
+        payload.processed = 1;
+        progress_handler(payload);
+        tracing::info!("Successfully merged video to {:?}", output_path);
+        Ok(VideoStreamsDownload {
+            paths: vec![output_path.to_string_lossy().into_owned()],
+            merge_skipped: false,
+        })
+    }
+
+    pub async fn download_course_videos<F, G>(
+        &self,
+        course_id: i64,
+        save_dir: &str,
+        quality: VideoQuality,
+        conflict_policy: ConflictPolicy,
+        progress_handler: F,
+        summary_handler: G,
+    ) -> Result<VideoDownloadReport>
+    where
+        F: Fn(ProgressPayload) + Send + Sync + 'static,
+        G: Fn(ProgressPayload),
+    {
+        let (concurrency, filename_template, sidecars) = {
+            let config = self.config.read().await;
+            (
+                config.video_download_concurrency,
+                config.video_filename_template.clone(),
+                config.video_sidecars(),
+            )
+        };
+        let videos = self.list_canvas_videos(course_id).await?;
+        let course_name = self
+            .list_courses()
+            .await?
+            .into_iter()
+            .find(|course| course.id == course_id)
+            .map(|course| course.name)
+            .unwrap_or_default();
+        App::ensure_directory(save_dir);
+        self.client
+            .clone()
+            .download_course_videos(
+                course_id,
+                videos,
+                &course_name,
+                &filename_template,
+                save_dir,
+                quality,
+                conflict_policy,
+                sidecars,
+                concurrency,
+                progress_handler,
+                summary_handler,
+            )
+            .await
+    }
+
+    // Save name of the `index`-th play of a video, following the configured filename template
+    pub async fn video_save_name(&self, video: &VideoInfo, index: usize) -> String {
+        let template = self.config.read().await.video_filename_template.clone();
+        let fields = VideoFilenameFields::from_video_info(video, index);
+        let video_name = utils::format_video_filename(&template, &fields);
+        let video_name = video_name.strip_suffix(".mp4").unwrap_or(&video_name);
+        Client::video_play_save_name(video_name, index)
+    }
+
+    // Example filename for the settings page, `template` defaults to the configured one
+    pub async fn preview_video_filename(&self, template: Option<String>) -> String {
+        let template = match template {
+            Some(template) => template,
+            None => self.config.read().await.video_filename_template.clone(),
+        };
+        let fields = VideoFilenameFields {
+            course: "操作系统".to_owned(),
+            subject: "操作系统（A类）".to_owned(),
+            teacher: "张三".to_owned(),
+            classroom: "东上院101".to_owned(),
+            title: "第3周 周一 第1-2节".to_owned(),
+            date: "2024-03-11".to_owned(),
+            week: "3".to_owned(),
+            index: 0,
+        };
+        utils::format_video_filename(&template, &fields)
+    }
+
+    pub async fn is_video_downloaded(
+        &self,
+        video: &VideoPlayInfo,
+        save_name: &str,
+        quality: VideoQuality,
+    ) -> Result<bool> {
         let save_dir = self.config.read().await.save_path.clone();
         let save_path = Path::new(&save_dir).join(save_name);
+        self.client
+            .is_already_downloaded(video, save_path.to_str().unwrap(), quality)
+            .await
+    }
+
+    pub async fn get_subject_classes(&self, subject_id: i64) -> Result<Vec<SubjectClass>> {
+        let subjects = self.get_subjects_cached(false).await?;
+        Ok(subject_classes(&subjects, subject_id))
+    }
+
+    // A subject taught in a single class needs no choice, that class is picked
+    async fn resolve_tecl_id(&self, subject_id: i64, tecl_id: Option<i64>) -> Result<i64> {
+        if let Some(tecl_id) = tecl_id {
+            return Ok(tecl_id);
+        }
+        match self.get_subject_classes(subject_id).await?.as_slice() {
+            [class] => Ok(class.tecl_id),
+            classes => Err(AppError::SubjectClassRequired {
+                subject_id,
+                classes: classes.len(),
+            }),
+        }
+    }
+
+    pub async fn get_video_courses(
+        &self,
+        subject_id: i64,
+        tecl_id: Option<i64>,
+    ) -> Result<Vec<VideoCourse>> {
+        let tecl_id = self.resolve_tecl_id(subject_id, tecl_id).await?;
         self.client
             .clone()
-            .download_video(video, save_path.to_str().unwrap(), progress_handler)
+            .get_video_courses(subject_id, tecl_id)
             .await
     }
 
-    pub async fn get_video_course(
+    pub async fn search_subject_videos(
         &self,
         subject_id: i64,
-        tecl_id: i64,
-    ) -> Result<Option<VideoCourse>> {
-        self.client.get_video_course(subject_id, tecl_id).await
+        tecl_id: Option<i64>,
+        keyword: &str,
+    ) -> Result<Vec<Video>> {
+        let tecl_id = self.resolve_tecl_id(subject_id, tecl_id).await?;
+        self.client
+            .clone()
+            .search_videos(subject_id, tecl_id, keyword)
+            .await
+    }
+
+    fn video_courses_cache_key(subject: &Subject) -> String {
+        format!(
+            "{}{}_{}",
+            VIDEO_COURSES_CACHE_KEY_PREFIX, subject.subject_id, subject.tecl_id
+        )
+    }
+
+    fn fresh_cached_video_courses(&self, subject: &Subject) -> Result<Option<Vec<VideoCourse>>> {
+        let cached: Option<CachedVideoCourses> =
+            self.cache.get(&App::video_courses_cache_key(subject))?;
+        Ok(cached
+            .filter(|cached| {
+                utils::unix_timestamp().saturating_sub(cached.fetched_at)
+                    < VIDEO_COURSES_CACHE_TTL_SECS
+            })
+            .map(|cached| cached.courses))
+    }
+
+    // Searches the recordings of every subject. Hits are reported through `hits_handler`
+    // subject by subject as the listings come in, and returned all together in subject
+    // order at the end. A subject whose listing fails is skipped.
+    pub async fn search_videos<F: Fn(Vec<VideoSearchHit>)>(
+        &self,
+        keyword: &str,
+        hits_handler: F,
+    ) -> Result<Vec<VideoSearchHit>> {
+        let subjects = self.get_subjects_cached(false).await?;
+        let mut results = vec![vec![]; subjects.len()];
+        let semaphore = Arc::new(Semaphore::new(VIDEO_SEARCH_CONCURRENCY));
+        let mut tasks = JoinSet::new();
+        for (index, subject) in subjects.iter().enumerate() {
+            if let Some(courses) = self.fresh_cached_video_courses(subject)? {
+                let hits = video_search_hits(subject, &courses, keyword);
+                if !hits.is_empty() {
+                    hits_handler(hits.clone());
+                }
+                results[index] = hits;
+                continue;
+            }
+            let client = self.client.clone();
+            let semaphore = semaphore.clone();
+            let (subject_id, tecl_id) = (subject.subject_id, subject.tecl_id);
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                (index, client.get_video_courses(subject_id, tecl_id).await)
+            });
+        }
+
+        while let Some(res) = tasks.join_next().await {
+            let (index, courses) = res?;
+            let subject = &subjects[index];
+            let courses = match courses {
+                Ok(courses) => courses,
+                Err(e) => {
+                    tracing::warn!("failed to list videos of {}: {}", subject.subject_name, e);
+                    continue;
+                }
+            };
+            let hits = video_search_hits(subject, &courses, keyword);
+            self.cache.set(
+                App::video_courses_cache_key(subject),
+                CachedVideoCourses {
+                    fetched_at: utils::unix_timestamp(),
+                    courses,
+                },
+            )?;
+            if !hits.is_empty() {
+                hits_handler(hits.clone());
+            }
+            results[index] = hits;
+        }
+        Ok(results.into_iter().flatten().collect())
     }
 }
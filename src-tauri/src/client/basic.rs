@@ -1,22 +1,146 @@
-use super::{constants::BASE_URL, Client};
+use super::{constants::BASE_URL, transfer::TransferLimiter, Client};
 use ::bytes::Bytes;
-use reqwest::{cookie, multipart};
+use futures::stream;
+use regex::Regex;
+use reqwest::{cookie, multipart, Body, Proxy, StatusCode};
+use select::{document::Document, predicate::Name};
 use serde::de::DeserializeOwned;
-use std::{cmp::min, collections::HashSet, fs, io::Write, ops::Deref, path::Path, sync::Arc};
+use std::{
+    cmp::min,
+    collections::{HashMap, HashSet},
+    fs,
+    io::{Read, Write},
+    ops::Deref,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tauri::Url;
 use tokio::{sync::RwLock, task::JoinSet};
 
 use crate::{
-    client::constants::CHUNK_SIZE,
+    client::constants::{
+        CANVAS_CSRF_COOKIE, CANVAS_SESSION_COOKIE, DEFAULT_MAX_CONCURRENT_TRANSFERS,
+        DEFAULT_PROGRESS_INTERVAL_MS, PROFILE_PICTURES_FOLDER, PROFILE_PICTURE_MAX_SIZE,
+        SUBMISSION_UPLOAD_CHUNK_SIZE,
+    },
     error::{AppError, Result},
     model::{
-        Assignment, CalendarEvent, Colors, Course, DiscussionTopic, File, Folder, FoldersAndFiles,
-        FullDiscussion, ProgressPayload, RelationshipEdge, RelationshipNode, RelationshipNodeType,
-        RelationshipTopo, Submission, SubmissionUploadResult, SubmissionUploadSuccessResponse,
-        User, UserSubmissions,
+        Announcement, AnnouncementFileLink, AppointmentGroup, Assignment, AssignmentGroup, Avatar,
+        CalendarEvent, CanvasGroup, Collaboration, Colors, ContentMigration, Course,
+        DiscussionTopic, File, FileDownloadFailure, Folder, FolderDownloadReport, FoldersAndFiles,
+        FullDiscussion, LearningOutcome, MediaRecording, MediaTrack, NetworkTimeouts, OutcomeLink,
+        OutcomeRollup, OutcomeRollupsPage, OutcomeScore, ProgressPayload, RelationshipEdge,
+        RelationshipNode, RelationshipNodeType, RelationshipTopo, Submission, SubmissionComment,
+        SubmissionUploadResult, SubmissionUploadSuccessResponse, UsageRights, User,
+        UserSubmissions,
     },
-    utils,
+    utils::{self, ProgressThrottle},
 };
 
+// Resolves the string links of the rollup pages, a score gets the mastery points of its
+// outcome from the outcomes linked into the same page
+fn outcome_rollups(pages: Vec<OutcomeRollupsPage>) -> Vec<OutcomeRollup> {
+    let mastery_points: HashMap<i64, Option<f64>> = pages
+        .iter()
+        .flat_map(|page| &page.linked.outcomes)
+        .map(|outcome| (outcome.id, outcome.mastery_points))
+        .collect();
+    pages
+        .into_iter()
+        .flat_map(|page| page.rollups)
+        .map(|rollup| OutcomeRollup {
+            user_id: rollup.links.user.parse().unwrap_or_default(),
+            scores: rollup
+                .scores
+                .into_iter()
+                .map(|score| {
+                    let outcome_id = score.links.outcome.parse().unwrap_or_default();
+                    let mastery_points = mastery_points.get(&outcome_id).copied().flatten();
+                    let mastered = matches!(
+                        (score.score, mastery_points),
+                        (Some(score), Some(points)) if score >= points
+                    );
+                    OutcomeScore {
+                        outcome_id,
+                        title: score.title,
+                        score: score.score,
+                        count: score.count,
+                        mastery_points,
+                        mastered,
+                    }
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+// Canvas and its file store put the reason (quota exceeded, file type not allowed...)
+// in a "message" field, either at the top level or nested in an "errors" object
+fn find_error_message(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Object(map) => map
+            .get("message")
+            .and_then(|message| message.as_str())
+            .map(str::to_owned)
+            .or_else(|| map.values().find_map(find_error_message)),
+        serde_json::Value::Array(values) => values.iter().find_map(find_error_message),
+        _ => None,
+    }
+}
+
+fn submission_upload_error(status: StatusCode, body: &[u8]) -> AppError {
+    let message = serde_json::from_slice(body)
+        .ok()
+        .and_then(|value| find_error_message(&value))
+        .unwrap_or_else(|| match status {
+            StatusCode::PAYLOAD_TOO_LARGE => "file too large".to_owned(),
+            _ => format!("server responded with {}", status),
+        });
+    AppError::SubmissionUpload(message)
+}
+
+// Only the signature is trusted, the extension may lie
+fn profile_picture_content_type(header: &[u8]) -> Option<&'static str> {
+    if header.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if header.starts_with(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n']) {
+        Some("image/png")
+    } else {
+        None
+    }
+}
+
+// Files inserted with the rich content editor are links like
+// `<a class="instructure_file_link" href=".../courses/1/files/2?wrap=1">name</a>`
+fn extract_file_links(html: &str) -> Vec<AnnouncementFileLink> {
+    let file_id_re = Regex::new(r"/files/(\d+)").unwrap();
+    Document::from(html)
+        .find(Name("a"))
+        .filter_map(|node| {
+            let url = node.attr("href")?;
+            let file_id = file_id_re
+                .captures(url)
+                .and_then(|captures| captures[1].parse().ok());
+            let is_file_link = node
+                .attr("class")
+                .is_some_and(|class| class.contains("instructure_file_link"));
+            if file_id.is_none() && !is_file_link {
+                return None;
+            }
+            let title = node
+                .attr("title")
+                .map(str::to_owned)
+                .unwrap_or_else(|| node.text().trim().to_owned());
+            Some(AnnouncementFileLink {
+                title,
+                url: url.to_owned(),
+                file_id,
+            })
+        })
+        .collect()
+}
+
 // Apis here are for canvas
 impl Client {
     #[allow(dead_code)]
@@ -25,13 +149,188 @@ impl Client {
     }
 
     pub fn with_base_url<S: Into<String>>(base_url: S) -> Self {
+        Self::with_timeouts(base_url, NetworkTimeouts::default())
+    }
+
+    pub fn with_timeouts<S: Into<String>>(base_url: S, timeouts: NetworkTimeouts) -> Self {
         let jar = Arc::new(cookie::Jar::default());
-        let cli = reqwest::Client::builder()
-            .cookie_provider(jar.clone())
+        let cli = Self::http_client_builder(&jar, &timeouts, None)
             .build()
             .unwrap();
         let base_url = RwLock::new(base_url.into());
-        Self { cli, jar, base_url }
+        Self {
+            cli,
+            jar,
+            base_url,
+            video_relogin_lock: Default::default(),
+            video_session_generation: Default::default(),
+            student_view: false,
+            logged_out: Default::default(),
+            timeouts,
+            proxy_url: Default::default(),
+            progress_interval: Duration::from_millis(DEFAULT_PROGRESS_INTERVAL_MS),
+            transfers: TransferLimiter::new(DEFAULT_MAX_CONCURRENT_TRANSFERS),
+        }
+    }
+
+    // Every http client of this client is built from here, so they share cookies and proxy
+    pub(super) fn http_client_builder(
+        jar: &Arc<cookie::Jar>,
+        timeouts: &NetworkTimeouts,
+        proxy: Option<&Proxy>,
+    ) -> reqwest::ClientBuilder {
+        let builder = reqwest::Client::builder()
+            .cookie_provider(jar.clone())
+            .connect_timeout(Duration::from_secs(timeouts.connect_secs.max(1)));
+        match proxy {
+            Some(proxy) => builder.proxy(proxy.clone()),
+            None => builder,
+        }
+    }
+
+    pub(super) fn proxy(&self) -> Result<Option<Proxy>> {
+        if self.proxy_url.is_empty() {
+            return Ok(None);
+        }
+        let scheme = self.proxy_url.split("://").next().unwrap_or_default();
+        if !matches!(scheme, "http" | "https" | "socks5" | "socks5h") {
+            return Err(AppError::InvalidProxy(self.proxy_url.clone()));
+        }
+        let proxy = Proxy::all(&self.proxy_url)
+            .map_err(|_| AppError::InvalidProxy(self.proxy_url.clone()))?;
+        Ok(Some(proxy))
+    }
+
+    fn build_http_client(&self) -> Result<reqwest::Client> {
+        let proxy = self.proxy()?;
+        let cli = Self::http_client_builder(&self.jar, &self.timeouts, proxy.as_ref()).build()?;
+        Ok(cli)
+    }
+
+    // Routes every request through `proxy_url`, an empty url removes the proxy.
+    // The client is left unchanged when the url is invalid.
+    pub fn set_proxy(&mut self, proxy_url: &str) -> Result<()> {
+        let previous = std::mem::replace(&mut self.proxy_url, proxy_url.trim().to_owned());
+        match self.build_http_client() {
+            Ok(cli) => {
+                self.cli = cli;
+                Ok(())
+            }
+            Err(e) => {
+                self.proxy_url = previous;
+                Err(e)
+            }
+        }
+    }
+
+    // Fails with ProxyUnreachable when the proxy itself can't be reached, so a bad proxy
+    // isn't mistaken for the school network being down
+    pub async fn check_proxy(&self) -> Result<()> {
+        if self.proxy_url.is_empty() {
+            return Ok(());
+        }
+        let url = self.base_url.read().await.clone();
+        match self
+            .cli
+            .head(&url)
+            .timeout(self.request_timeout())
+            .send()
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) if e.is_connect() || e.is_timeout() => Err(AppError::ProxyUnreachable {
+                proxy: self.proxy_url.clone(),
+                reason: e.to_string(),
+            }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn with_progress_interval(mut self, interval_ms: u64) -> Self {
+        self.progress_interval = Duration::from_millis(interval_ms);
+        self
+    }
+
+    pub(super) fn progress_throttle(&self) -> ProgressThrottle {
+        ProgressThrottle::new(self.progress_interval)
+    }
+
+    pub(super) fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.timeouts.request_secs.max(1))
+    }
+
+    pub(super) fn download_timeout(&self) -> Duration {
+        Duration::from_secs(self.timeouts.download_secs.max(1))
+    }
+
+    // The test student of a course, which teachers can act as to preview the course
+    pub async fn get_student_view_student(&self, course_id: i64, token: &str) -> Result<User> {
+        let url = format!(
+            "{}/api/v1/courses/{}/student_view_student",
+            self.base_url.read().await,
+            course_id
+        );
+        self.get_json_with_token(&url, None::<&str>, token).await
+    }
+
+    // A client with the same timeouts and proxy but cookies of its own
+    fn temporary_client(&self, base_url: &str) -> Result<Client> {
+        let mut client = Client::with_timeouts(base_url, self.timeouts);
+        client.set_proxy(&self.proxy_url)?;
+        Ok(client)
+    }
+
+    // Enters the student view of the course like its button in the web ui, on a copy of the
+    // canvas website session so this client stays the teacher. The returned token is the
+    // session cookie of the student view. Needs `login_canvas_website` first.
+    pub async fn get_canvas_student_view_token(
+        &self,
+        course_id: i64,
+        token: &str,
+    ) -> Result<String> {
+        let base_url = self.base_url.read().await.clone();
+        let client = self.temporary_client(&base_url)?;
+        client.restore_cookies(&self.jar_cookies(&base_url), &base_url);
+        let csrf_token = client
+            .jar_cookie(&base_url, CANVAS_CSRF_COOKIE)
+            .ok_or(AppError::LoginError)?;
+        // the cookie is percent encoded, which a query string undoes the same way
+        let csrf_token = Url::parse(&format!(
+            "{}?{}={}",
+            base_url, CANVAS_CSRF_COOKIE, csrf_token
+        ))
+        .ok()
+        .and_then(|url| {
+            url.query_pairs()
+                .next()
+                .map(|(_, value)| value.into_owned())
+        })
+        .ok_or(AppError::LoginError)?;
+        let url = format!("{}/courses/{}/student_view", base_url, course_id);
+        client
+            .post_form_with_token(
+                &url,
+                None::<&str>,
+                &[("authenticity_token", csrf_token)],
+                token,
+            )
+            .await?
+            .error_for_status()?;
+        client
+            .jar_cookie(&base_url, CANVAS_SESSION_COOKIE)
+            .ok_or(AppError::LoginError)
+    }
+
+    // A separate client in the student view of the course, its canvas requests are made as the
+    // test student so teachers can try the course the way students see it
+    pub async fn student_view_client(&self, course_id: i64, token: &str) -> Result<Client> {
+        let student_view_token = self.get_canvas_student_view_token(course_id, token).await?;
+        let base_url = self.base_url.read().await.clone();
+        let mut client = self.temporary_client(&base_url)?;
+        let cookie = format!("{}={}", CANVAS_SESSION_COOKIE, student_view_token);
+        client.restore_cookies(&cookie, &base_url);
+        client.student_view = true;
+        Ok(client)
     }
 
     pub async fn set_base_url<S: Into<String>>(&self, base_url: S) -> bool {
@@ -60,6 +359,7 @@ impl Client {
             student_id,
             comment_id
         );
+        self.ensure_logged_in()?;
         self.cli
             .delete(url)
             .header("Authorization", format!("Bearer {}", token))
@@ -169,6 +469,7 @@ impl Client {
             assignment_id,
             override_id
         );
+        self.ensure_logged_in()?;
         self.cli
             .delete(url)
             .header("Authorization", format!("Bearer {}", token))
@@ -231,6 +532,7 @@ impl Client {
         save_path: &str,
         progress_handler: F,
     ) -> Result<()> {
+        let _permit = self.acquire_transfer().await;
         let mut response = self
             .get_request_with_token(&file.url, None::<&str>, token)
             .await?
@@ -240,20 +542,21 @@ impl Client {
             uuid: file.uuid.clone(),
             processed: 0,
             total: file.size,
+            ..Default::default()
         };
         let path = Path::new(save_path).join(&file.display_name);
-        let total = file.size;
         let mut file = fs::File::create(path.to_str().unwrap())?;
-        let mut last_chunk_no = 0;
+        let mut throttle = self.progress_throttle();
         while let Some(chunk) = response.chunk().await? {
             payload.processed += chunk.len() as u64;
-            let chunk_no = payload.processed / CHUNK_SIZE;
-            if chunk_no != last_chunk_no || payload.processed == total {
-                last_chunk_no = chunk_no;
-                progress_handler(payload.clone());
+            if let Some(payload) = throttle.update(payload.clone()) {
+                progress_handler(payload);
             }
             file.write_all(&chunk)?;
         }
+        if let Some(payload) = throttle.flush() {
+            progress_handler(payload);
+        }
 
         tracing::info!("File downloaded successfully!");
         Ok(())
@@ -306,6 +609,23 @@ impl Client {
         self.list_items(&url, token).await
     }
 
+    pub async fn list_announcements(
+        &self,
+        course_id: i64,
+        token: &str,
+    ) -> Result<Vec<Announcement>> {
+        let url = format!(
+            "{}/api/v1/courses/{}/discussion_topics?only_announcements=true",
+            self.base_url.read().await,
+            course_id
+        );
+        let mut announcements: Vec<Announcement> = self.list_items(&url, token).await?;
+        for announcement in &mut announcements {
+            announcement.file_links = extract_file_links(&announcement.message);
+        }
+        Ok(announcements)
+    }
+
     pub async fn get_full_discussion(
         &self,
         course_id: i64,
@@ -330,6 +650,160 @@ impl Client {
         self.list_items(&url, token).await
     }
 
+    // canvas requires `search_term` to have at least 2 characters
+    pub async fn search_canvas_files(
+        &self,
+        course_id: i64,
+        query: &str,
+        token: &str,
+    ) -> Result<Vec<File>> {
+        let url = format!(
+            "{}/api/v1/courses/{}/files",
+            self.base_url.read().await,
+            course_id
+        );
+        let mut all_files = vec![];
+        let mut page = 1;
+        loop {
+            let files: Vec<File> = self
+                .get_json_with_token(
+                    &url,
+                    Some(&vec![
+                        ("search_term", query.to_owned()),
+                        ("page", page.to_string()),
+                        ("per_page", "100".to_owned()),
+                    ]),
+                    token,
+                )
+                .await?;
+            if files.is_empty() {
+                break;
+            }
+            page += 1;
+            all_files.extend(files);
+        }
+        Ok(all_files)
+    }
+
+    pub async fn get_canvas_media_recordings(
+        &self,
+        course_id: i64,
+        token: &str,
+    ) -> Result<Vec<MediaRecording>> {
+        let url = format!(
+            "{}/api/v1/courses/{}/media_recordings",
+            self.base_url.read().await,
+            course_id
+        );
+        self.list_items(&url, token).await
+    }
+
+    pub async fn get_canvas_appointment_groups(
+        &self,
+        course_id: i64,
+        token: &str,
+    ) -> Result<Vec<AppointmentGroup>> {
+        let url = format!(
+            "{}/api/v1/appointment_groups?context_codes[]=course_{}",
+            self.base_url.read().await,
+            course_id
+        );
+        self.list_items(&url, token).await
+    }
+
+    pub async fn get_canvas_collaborations(
+        &self,
+        course_id: i64,
+        token: &str,
+    ) -> Result<Vec<Collaboration>> {
+        let url = format!(
+            "{}/api/v1/courses/{}/collaborations",
+            self.base_url.read().await,
+            course_id
+        );
+        self.list_items(&url, token).await
+    }
+
+    pub async fn get_canvas_groups(&self, course_id: i64, token: &str) -> Result<Vec<CanvasGroup>> {
+        let url = format!(
+            "{}/api/v1/courses/{}/groups",
+            self.base_url.read().await,
+            course_id
+        );
+        self.list_items(&url, token).await
+    }
+
+    // The same outcome may be linked into several groups, it is listed once
+    pub async fn get_canvas_learning_outcomes(
+        &self,
+        course_id: i64,
+        token: &str,
+    ) -> Result<Vec<LearningOutcome>> {
+        let url = format!(
+            "{}/api/v1/courses/{}/outcome_group_links?outcome_style=full",
+            self.base_url.read().await,
+            course_id
+        );
+        let links: Vec<OutcomeLink> = self.list_items(&url, token).await?;
+        let mut seen = HashSet::new();
+        let outcomes = links
+            .into_iter()
+            .map(|link| link.outcome)
+            .filter(|outcome| seen.insert(outcome.id))
+            .collect();
+        Ok(outcomes)
+    }
+
+    // The rollups come wrapped in an object, so they're paged here instead of by list_items
+    pub async fn get_canvas_outcome_rollups(
+        &self,
+        course_id: i64,
+        token: &str,
+    ) -> Result<Vec<OutcomeRollup>> {
+        let url = format!(
+            "{}/api/v1/courses/{}/outcome_rollups?include[]=outcomes",
+            self.base_url.read().await,
+            course_id
+        );
+        let mut pages = vec![];
+        for page in 1.. {
+            let query = [("page", page.to_string()), ("per_page", "100".to_owned())];
+            let rollups_page: OutcomeRollupsPage =
+                self.get_json_with_token(&url, Some(&query), token).await?;
+            if rollups_page.rollups.is_empty() {
+                break;
+            }
+            pages.push(rollups_page);
+        }
+        Ok(outcome_rollups(pages))
+    }
+
+    pub async fn get_canvas_content_migrations(
+        &self,
+        course_id: i64,
+        token: &str,
+    ) -> Result<Vec<ContentMigration>> {
+        let url = format!(
+            "{}/api/v1/courses/{}/content_migrations",
+            self.base_url.read().await,
+            course_id
+        );
+        self.list_items(&url, token).await
+    }
+
+    pub async fn get_canvas_media_track(
+        &self,
+        media_id: &str,
+        token: &str,
+    ) -> Result<Vec<MediaTrack>> {
+        let url = format!(
+            "{}/api/v1/media_objects/{}/media_tracks?include[]=content",
+            self.base_url.read().await,
+            media_id
+        );
+        self.get_json_with_token(&url, None::<&str>, token).await
+    }
+
     pub async fn list_course_images(&self, course_id: i64, token: &str) -> Result<Vec<File>> {
         let url = format!(
             "{}/api/v1/courses/{}/files?content_types[]=image",
@@ -381,6 +855,144 @@ impl Client {
         Ok(folder)
     }
 
+    pub async fn get_course_root_folder(&self, course_id: i64, token: &str) -> Result<Folder> {
+        let url = format!(
+            "{}/api/v1/courses/{}/folders/root",
+            self.base_url.read().await,
+            course_id
+        );
+        let folder = self.get_json_with_token(&url, None::<&str>, token).await?;
+        Ok(folder)
+    }
+
+    // Subfolders and files of a folder, None if the user isn't allowed to list it
+    async fn list_folder_contents(
+        &self,
+        folder: &Folder,
+        token: &str,
+    ) -> Result<Option<(Vec<Folder>, Vec<File>)>> {
+        if folder.locked {
+            return Ok(None);
+        }
+        let contents = async {
+            let folders = self.list_folder_folders(folder.id, token).await?;
+            let files = self.list_folder_files(folder.id, token).await?;
+            Ok::<_, AppError>((folders, files))
+        };
+        match contents.await.map_err(AppError::classify) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(AppError::Unauthorized) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Rebuild `folder` and all its subfolders under `local_dir`, empty ones included, then
+    // download the files all at once, the transfer limit keeps the connections in check.
+    // Locked files and folders are skipped, a failed file doesn't abort the others.
+    pub async fn download_folder<F, G>(
+        self: Arc<Self>,
+        folder: Folder,
+        local_dir: &str,
+        token: &str,
+        progress_handler: F,
+        summary_handler: G,
+    ) -> Result<FolderDownloadReport>
+    where
+        F: Fn(ProgressPayload) + Send + Sync + 'static,
+        G: Fn(ProgressPayload),
+    {
+        let local_dir = Path::new(local_dir);
+        let relative = |path: &Path| {
+            path.strip_prefix(local_dir)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .into_owned()
+        };
+        let folder_id = folder.id;
+        let mut report = FolderDownloadReport::default();
+        let mut downloads = vec![];
+        let mut pending = vec![(folder, local_dir.to_path_buf())];
+        while let Some((folder, dir)) = pending.pop() {
+            let Some((folders, files)) = self.list_folder_contents(&folder, token).await? else {
+                report.locked.push(relative(&dir));
+                continue;
+            };
+            fs::create_dir_all(&dir)?;
+            // canvas allows a file and a folder with the same name, the local directory doesn't
+            let mut used_names = HashSet::new();
+            for subfolder in folders {
+                let name = utils::sanitize_filename(&subfolder.name);
+                let name = utils::unique_name(&name, &mut used_names);
+                pending.push((subfolder, dir.join(name)));
+            }
+            for mut file in files {
+                let name = utils::sanitize_filename(&file.display_name);
+                let name = utils::unique_name(&name, &mut used_names);
+                if file.locked || file.url.is_empty() {
+                    report.locked.push(relative(&dir.join(name)));
+                    continue;
+                }
+                file.display_name = name;
+                downloads.push((file, dir.clone()));
+            }
+        }
+
+        let mut summary = ProgressPayload {
+            uuid: folder_id.to_string(),
+            processed: 0,
+            total: downloads.len() as u64,
+            ..Default::default()
+        };
+        summary_handler(summary.clone());
+
+        let progress_handler = Arc::new(progress_handler);
+        let mut tasks = JoinSet::new();
+        for (file, dir) in downloads {
+            let self_cloned = self.clone();
+            let token = token.to_owned();
+            let progress_handler = progress_handler.clone();
+            tasks.spawn(async move {
+                let result = self_cloned
+                    .download_file(&file, &token, &dir.to_string_lossy(), |progress| {
+                        progress_handler(progress)
+                    })
+                    .await;
+                (dir.join(&file.display_name), result)
+            });
+        }
+
+        while let Some(res) = tasks.join_next().await {
+            let (path, result) = res?;
+            match result {
+                Ok(()) => report.succeeded.push(relative(&path)),
+                Err(e) => {
+                    tracing::error!("failed to download file {:?}: {}", path, e);
+                    report.failed.push(FileDownloadFailure {
+                        path: relative(&path),
+                        error: e.to_string(),
+                    });
+                }
+            }
+            summary.processed += 1;
+            summary_handler(summary.clone());
+        }
+        Ok(report)
+    }
+
+    pub async fn get_canvas_file_usage_rights(
+        &self,
+        file_id: i64,
+        token: &str,
+    ) -> Result<UsageRights> {
+        let url = format!(
+            "{}/api/v1/files/{}/usage_rights",
+            self.base_url.read().await,
+            file_id
+        );
+        let usage_rights = self.get_json_with_token(&url, None::<&str>, token).await?;
+        Ok(usage_rights)
+    }
+
     async fn get_folders_and_files(&self, course_id: i64, token: &str) -> Result<FoldersAndFiles> {
         let folders = self.list_course_folders(course_id, token).await?;
         let files = self.list_course_files(course_id, token).await?;
@@ -435,9 +1047,11 @@ impl Client {
         Ok(colors)
     }
 
+    // `event_type` is either "assignment" or "event", canvas lists them separately
     pub async fn list_calendar_events_inner(
         &self,
         token: &str,
+        event_type: &str,
         context_codes: &[String],
         start_date: &str,
         end_date: &str,
@@ -448,13 +1062,16 @@ impl Client {
             .reduce(|c1, c2| format!("{}&{}", c1, c2))
             .unwrap_or_default();
         let url = format!(
-            "{}/api/v1/calendar_events?type=assignment&{}&start_date={}&end_date={}",
+            "{}/api/v1/calendar_events?type={}&{}&start_date={}&end_date={}",
             self.base_url.read().await,
+            event_type,
             context_codes,
             start_date,
             end_date
         );
-        self.list_items(&url, token).await
+        let mut events: Vec<CalendarEvent> = self.list_items(&url, token).await?;
+        events.iter_mut().for_each(CalendarEvent::classify);
+        Ok(events)
     }
 
     pub async fn list_calendar_events(
@@ -480,13 +1097,74 @@ impl Client {
             end = min(start + BATCH_SIZE, n_codes);
             let context_codes_batch = &context_codes[start..end];
             let events = self
-                .list_calendar_events_inner(token, context_codes_batch, start_date, end_date)
+                .list_calendar_events_inner(
+                    token,
+                    "assignment",
+                    context_codes_batch,
+                    start_date,
+                    end_date,
+                )
                 .await?;
             all_events.extend(events);
         }
         Ok(all_events)
     }
 
+    // Deadlines, quizzes and custom events of a course between `start_date` and `end_date`
+    pub async fn get_calendar_events(
+        &self,
+        course_id: i64,
+        start_date: &str,
+        end_date: &str,
+        token: &str,
+    ) -> Result<Vec<CalendarEvent>> {
+        let context_codes = [format!("course_{}", course_id)];
+        let mut events = vec![];
+        for event_type in ["assignment", "event"] {
+            let typed_events = self
+                .list_calendar_events_inner(token, event_type, &context_codes, start_date, end_date)
+                .await?;
+            events.extend(typed_events);
+        }
+        events.sort_by(|e1, e2| e1.start_at.cmp(&e2.start_at));
+        Ok(events)
+    }
+
+    // Every event and dated assignment of the course, whatever their dates
+    pub async fn list_all_course_calendar_events(
+        &self,
+        course_id: i64,
+        token: &str,
+    ) -> Result<Vec<CalendarEvent>> {
+        let mut events = vec![];
+        for event_type in ["assignment", "event"] {
+            let url = format!(
+                "{}/api/v1/calendar_events?type={}&context_codes[]=course_{}&all_events=true",
+                self.base_url.read().await,
+                event_type,
+                course_id
+            );
+            let mut typed_events: Vec<CalendarEvent> = self.list_items(&url, token).await?;
+            typed_events.iter_mut().for_each(CalendarEvent::classify);
+            events.extend(typed_events);
+        }
+        events.sort_by(|e1, e2| e1.start_at.cmp(&e2.start_at));
+        Ok(events)
+    }
+
+    pub async fn export_course_calendar_as_ics(
+        &self,
+        course_id: i64,
+        path: &str,
+        token: &str,
+    ) -> Result<()> {
+        let events = self
+            .list_all_course_calendar_events(course_id, token)
+            .await?;
+        fs::write(path, utils::export_ics(&events))?;
+        Ok(())
+    }
+
     pub async fn list_course_users(&self, course_id: i64, token: &str) -> Result<Vec<User>> {
         let url = format!(
             "{}/api/v1/courses/{}/users",
@@ -631,14 +1309,26 @@ impl Client {
         Ok(me)
     }
 
-    async fn upload_submission_file_with(
+    async fn upload_submission_file_with<F: Fn(u64) + Send + Sync + 'static>(
         &self,
         params: &SubmissionUploadSuccessResponse,
         file_path: &str,
+        on_uploaded: F,
     ) -> Result<File> {
         let upload_params = &params.upload_params;
-        let file_fs = fs::read(file_path)?;
-        let file = multipart::Part::bytes(file_fs).file_name("filename.filetype");
+        let data = Bytes::from(fs::read(file_path)?);
+        let file_size = data.len() as u64;
+        // Feed the body chunk by chunk so the progress follows what's actually been sent
+        let chunks = (0..data.len())
+            .step_by(SUBMISSION_UPLOAD_CHUNK_SIZE)
+            .map(move |start| {
+                let end = min(start + SUBMISSION_UPLOAD_CHUNK_SIZE, data.len());
+                on_uploaded((end - start) as u64);
+                Ok::<_, std::io::Error>(data.slice(start..end))
+            });
+        let body = Body::wrap_stream(stream::iter(chunks));
+        let file =
+            multipart::Part::stream_with_length(body, file_size).file_name("filename.filetype");
         let form = reqwest::multipart::Form::new()
             .text("x-amz-credential", upload_params.x_amz_credential.clone())
             .text("x-amz-algorithm", upload_params.x_amz_algorithm.clone())
@@ -658,12 +1348,15 @@ impl Client {
         let resp = self
             .cli
             .post(&params.upload_url)
+            .timeout(self.download_timeout())
             .multipart(form)
             .send()
-            .await?
-            .error_for_status()?;
-
+            .await?;
+        let status = resp.status();
         let bytes = resp.bytes().await?;
+        if !status.is_success() {
+            return Err(submission_upload_error(status, &bytes));
+        }
         let file = utils::parse_json(&bytes)?;
         Ok(file)
     }
@@ -692,7 +1385,11 @@ impl Client {
         let resp = self
             .post_form_with_token(&url, None::<&str>, &form, token)
             .await?;
+        let status = resp.status();
         let bytes = resp.bytes().await?;
+        if !status.is_success() {
+            return Err(submission_upload_error(status, &bytes));
+        }
         let result = match utils::parse_json::<SubmissionUploadResult>(&bytes)? {
             SubmissionUploadResult::Success(success_response) => success_response,
             SubmissionUploadResult::Error(error_response) => {
@@ -702,19 +1399,52 @@ impl Client {
         Ok(result)
     }
 
-    pub async fn submit_assignment(
+    pub async fn submit_assignment<F: Fn(ProgressPayload) + Send + Sync + 'static>(
         &self,
         course_id: i64,
         assignment_id: i64,
         file_paths: &[String],
         comment: Option<&str>,
         token: &str,
+        progress_handler: F,
     ) -> Result<()> {
+        // One progress covering all the files, they make up a single submission
+        let mut total = 0;
+        for file_path in file_paths {
+            total += fs::metadata(file_path)?.len();
+        }
+        let payload = Arc::new(Mutex::new(ProgressPayload {
+            uuid: format!("{}-{}", course_id, assignment_id),
+            processed: 0,
+            total,
+            ..Default::default()
+        }));
+        let progress_handler = Arc::new(progress_handler);
+
         let mut file_ids = vec![];
         for file_path in file_paths {
-            let file_name = file_path.split('/').last().unwrap();
+            let file_name = Path::new(file_path)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or(file_path);
+            let params = self
+                .prepare_upload_submission_file(
+                    course_id,
+                    assignment_id,
+                    file_path,
+                    file_name,
+                    token,
+                )
+                .await?;
+            let payload = payload.clone();
+            let progress_handler = progress_handler.clone();
             let file = self
-                .upload_submission_file(course_id, assignment_id, file_path, file_name, token)
+                .upload_submission_file_with(&params, file_path, move |uploaded| {
+                    if let Ok(mut payload) = payload.lock() {
+                        payload.processed += uploaded;
+                        progress_handler(payload.clone());
+                    }
+                })
                 .await?;
             file_ids.push(file.id);
         }
@@ -732,8 +1462,14 @@ impl Client {
         if let Some(comment) = comment {
             form.push(("comment[text_comment]", comment.to_owned()));
         }
-        self.post_form_with_token(&url, None::<&str>, &form, token)
+        let resp = self
+            .post_form_with_token(&url, None::<&str>, &form, token)
             .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            let bytes = resp.bytes().await?;
+            return Err(submission_upload_error(status, &bytes));
+        }
         Ok(())
     }
 
@@ -751,10 +1487,80 @@ impl Client {
             .prepare_upload_submission_file(course_id, assignment_id, file_path, file_name, token)
             .await?;
         // Step 2: Upload the file data to the URL given in the previous response
-        let file = self.upload_submission_file_with(&params, file_path).await?;
+        let file = self
+            .upload_submission_file_with(&params, file_path, |_| {})
+            .await?;
         Ok(file)
     }
 
+    // Same flow as a submission upload, but into the "profile pictures" folder of the user,
+    // then the uploaded picture is picked among the avatars
+    pub async fn upload_canvas_profile_picture(&self, image_path: &str, token: &str) -> Result<()> {
+        let size = fs::metadata(image_path)?.len();
+        if size > PROFILE_PICTURE_MAX_SIZE {
+            return Err(AppError::FileTooLarge {
+                size,
+                limit: PROFILE_PICTURE_MAX_SIZE,
+            });
+        }
+        let mut header = [0; 8];
+        let read = fs::File::open(image_path)?.read(&mut header)?;
+        let Some(content_type) = profile_picture_content_type(&header[..read]) else {
+            return Err(AppError::UnsupportedFormat(image_path.to_owned()));
+        };
+        let file_name = Path::new(image_path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(image_path);
+
+        // Step 1: Telling Canvas about the file upload and getting a token
+        let url = format!("{}/api/v1/users/self/files", self.base_url.read().await);
+        let form = [
+            ("name", file_name.to_owned()),
+            ("size", size.to_string()),
+            ("content_type", content_type.to_owned()),
+            ("parent_folder_path", PROFILE_PICTURES_FOLDER.to_owned()),
+        ];
+        let resp = self
+            .post_form_with_token(&url, None::<&str>, &form, token)
+            .await?;
+        let status = resp.status();
+        let bytes = resp.bytes().await?;
+        if !status.is_success() {
+            return Err(submission_upload_error(status, &bytes));
+        }
+        let params = match utils::parse_json::<SubmissionUploadResult>(&bytes)? {
+            SubmissionUploadResult::Success(success_response) => success_response,
+            SubmissionUploadResult::Error(error_response) => {
+                return Err(AppError::SubmissionUpload(error_response.message))
+            }
+        };
+        // Step 2: Upload the file data to the URL given in the previous response
+        let file = self
+            .upload_submission_file_with(&params, image_path, |_| {})
+            .await?;
+
+        // Step 3: Use the uploaded picture as avatar
+        let url = format!("{}/api/v1/users/self/avatars", self.base_url.read().await);
+        let avatars: Vec<Avatar> = self.get_json_with_token(&url, None::<&str>, token).await?;
+        let Some(avatar) = avatars
+            .into_iter()
+            .find(|avatar| avatar.type_field == "attachment" && avatar.id == Some(file.id))
+        else {
+            return Err(AppError::NotFound);
+        };
+        let url = format!("{}/api/v1/users/self", self.base_url.read().await);
+        self.put_form_with_token(
+            &url,
+            None::<&str>,
+            &[("user[avatar][token]", avatar.token)],
+            token,
+        )
+        .await?
+        .error_for_status()?;
+        Ok(())
+    }
+
     #[allow(dead_code)]
     pub async fn list_ta_courses(&self, token: &str) -> Result<Vec<Course>> {
         let url = format!(
@@ -788,6 +1594,19 @@ impl Client {
         Ok(submission)
     }
 
+    // Feedback comments are only returned along with the submission they belong to
+    pub async fn get_canvas_submission_comments(
+        &self,
+        course_id: i64,
+        assignment_id: i64,
+        token: &str,
+    ) -> Result<Vec<SubmissionComment>> {
+        let submission = self
+            .get_my_single_submission(course_id, assignment_id, token)
+            .await?;
+        Ok(submission.submission_comments)
+    }
+
     pub async fn list_course_assignments(
         &self,
         course_id: i64,
@@ -800,6 +1619,33 @@ impl Client {
         self.list_items(&url, token).await
     }
 
+    pub async fn list_assignment_groups(
+        &self,
+        course_id: i64,
+        token: &str,
+    ) -> Result<Vec<AssignmentGroup>> {
+        let url = format!(
+            "{}/api/v1/courses/{}/assignment_groups",
+            self.base_url.read().await,
+            course_id
+        );
+        self.list_items(&url, token).await
+    }
+
+    // Submissions of the current user to every assignment of the course
+    pub async fn list_my_submissions(
+        &self,
+        course_id: i64,
+        token: &str,
+    ) -> Result<Vec<Submission>> {
+        let url = format!(
+            "{}/api/v1/courses/{}/students/submissions?include[]=submission_comments",
+            self.base_url.read().await,
+            course_id
+        );
+        self.list_items(&url, token).await
+    }
+
     pub async fn list_current_term_courses(&self, token: &str) -> Result<Vec<Course>> {
         let url = format!(
             "{}/api/v1/courses?include[]=teachers&include[]=term&enrollment_state=active",
@@ -862,6 +1708,7 @@ impl Client {
 
 #[cfg(test)]
 mod test {
+    use super::{extract_file_links, outcome_rollups, profile_picture_content_type};
     use crate::{
         client::Client,
         error::Result,
@@ -947,7 +1794,9 @@ mod test {
             for assignment in assignments {
                 assert_eq!(assignment.course_id, course.id);
                 assert!(assignment.id > 0);
-                assert!(check_rfc3339_time_format(&assignment.due_at));
+                // due_at is parsed while deserializing, its rfc3339 form must still round trip
+                let due_at = assignment.due_at.map(|due_at| due_at.to_rfc3339());
+                assert!(check_rfc3339_time_format(&due_at));
                 assert!(check_rfc3339_time_format(&assignment.lock_at));
                 for assignment_override in assignment.overrides {
                     assert!(check_rfc3339_time_format(&assignment_override.unlock_at));
@@ -1028,4 +1877,58 @@ mod test {
         cli.get_colors(&token).await?;
         Ok(())
     }
+
+    #[test]
+    fn test_profile_picture_content_type() {
+        assert_eq!(
+            Some("image/jpeg"),
+            profile_picture_content_type(&[0xFF, 0xD8, 0xFF, 0xE0, 0, 0x10])
+        );
+        assert_eq!(
+            Some("image/png"),
+            profile_picture_content_type(b"\x89PNG\r\n\x1a\n")
+        );
+        assert_eq!(None, profile_picture_content_type(b"GIF89a"));
+        assert_eq!(None, profile_picture_content_type(&[0xFF, 0xD8]));
+    }
+
+    #[test]
+    fn test_outcome_rollups() -> Result<()> {
+        let page = r#"{
+            "rollups": [{
+                "scores": [
+                    {"score": 3.0, "title": "Recursion", "count": 2, "links": {"outcome": "7"}},
+                    {"score": 2.5, "title": "Sorting", "count": 1, "links": {"outcome": "8"}}
+                ],
+                "links": {"user": "42", "section": "1"}
+            }],
+            "linked": {"outcomes": [
+                {"id": 7, "title": "Recursion", "mastery_points": 3.0},
+                {"id": 8, "title": "Sorting", "mastery_points": 3.0}
+            ]},
+            "meta": {"pagination": {"page": 1}}
+        }"#;
+        let rollups = outcome_rollups(vec![serde_json::from_str(page)?]);
+        assert_eq!(1, rollups.len());
+        assert_eq!(42, rollups[0].user_id);
+        let scores = &rollups[0].scores;
+        assert_eq!((7, true), (scores[0].outcome_id, scores[0].mastered));
+        assert_eq!((8, false), (scores[1].outcome_id, scores[1].mastered));
+        assert_eq!(Some(3.0), scores[1].mastery_points);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_file_links() {
+        let html = r#"<p>课件见 <a class="instructure_file_link instructure_scribd_file" title="lecture1.pdf" href="https://oc.sjtu.edu.cn/courses/1/files/123?wrap=1" data-api-returntype="File">lecture1.pdf</a>，
+            作业见 <a href="https://oc.sjtu.edu.cn/courses/1/assignments/4">作业</a></p>"#;
+        let links = extract_file_links(html);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].title, "lecture1.pdf");
+        assert_eq!(links[0].file_id, Some(123));
+        assert_eq!(
+            links[0].url,
+            "https://oc.sjtu.edu.cn/courses/1/files/123?wrap=1"
+        );
+    }
 }
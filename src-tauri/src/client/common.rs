@@ -1,29 +1,50 @@
 use reqwest::{
     header::{HeaderValue, ACCEPT, CONTENT_TYPE},
-    Body, Response,
+    Body, RequestBuilder, Response,
 };
 use serde::{de::DeserializeOwned, Serialize};
+use std::sync::atomic::Ordering;
 
 use super::Client;
-use crate::{error::Result, utils};
+use crate::{
+    error::{AppError, Result},
+    utils,
+};
 
 impl Client {
-    pub async fn get_request_with_token<T: Serialize + ?Sized>(
+    // A student view client is already authenticated by its session cookie, the token would
+    // make canvas answer as the teacher again
+    fn authorize(&self, request: RequestBuilder, token: &str) -> RequestBuilder {
+        if self.student_view {
+            request
+        } else {
+            request.header("Authorization", format!("Bearer {}", token))
+        }
+    }
+
+    fn get_with_token<T: Serialize + ?Sized>(
         &self,
         url: &str,
         query: Option<&T>,
         token: &str,
-    ) -> Result<Response> {
-        let mut req = self
-            .cli
-            .get(url)
-            .header("Authorization", format!("Bearer {}", token));
+    ) -> Result<RequestBuilder> {
+        self.ensure_logged_in()?;
+        let mut req = self.authorize(self.cli.get(url), token);
 
         if let Some(query) = query {
             req = req.query(query)
         }
+        Ok(req)
+    }
 
-        let res = req.send().await?;
+    // No request timeout, the response may be a whole file streamed to disk
+    pub async fn get_request_with_token<T: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        query: Option<&T>,
+        token: &str,
+    ) -> Result<Response> {
+        let res = self.get_with_token(url, query, token)?.send().await?;
         Ok(res)
     }
 
@@ -34,7 +55,9 @@ impl Client {
         token: &str,
     ) -> Result<D> {
         let response = self
-            .get_request_with_token(url, query, token)
+            .get_with_token(url, query, token)?
+            .timeout(self.request_timeout())
+            .send()
             .await?
             .error_for_status()?;
         let json = utils::parse_json(&response.bytes().await?)?;
@@ -47,7 +70,12 @@ impl Client {
         query: Option<&Q>,
         form: &T,
     ) -> Result<Response> {
-        let mut request = self.cli.post(url).form(form);
+        self.ensure_logged_in()?;
+        let mut request = self
+            .cli
+            .post(url)
+            .timeout(self.request_timeout())
+            .form(form);
         if let Some(query) = query {
             request = request.query(query);
         }
@@ -62,10 +90,10 @@ impl Client {
         form: &T,
         token: &str,
     ) -> Result<Response> {
+        self.ensure_logged_in()?;
         let mut request = self
-            .cli
-            .post(url)
-            .header("Authorization".to_owned(), format!("Bearer {}", token))
+            .authorize(self.cli.post(url), token)
+            .timeout(self.request_timeout())
             .form(form);
         if let Some(query) = query {
             request = request.query(query);
@@ -81,10 +109,10 @@ impl Client {
         form: &T,
         token: &str,
     ) -> Result<Response> {
+        self.ensure_logged_in()?;
         let mut request = self
-            .cli
-            .put(url)
-            .header("Authorization".to_owned(), format!("Bearer {}", token))
+            .authorize(self.cli.put(url), token)
+            .timeout(self.request_timeout())
             .form(form);
         if let Some(query) = query {
             request = request.query(query);
@@ -98,9 +126,11 @@ impl Client {
         url: &str,
         body: B,
     ) -> Result<D> {
+        self.ensure_logged_in()?;
         let req = self
             .cli
             .post(url)
+            .timeout(self.request_timeout())
             .body(body)
             .header(CONTENT_TYPE, "application/json");
         let resp = req.send().await?.error_for_status()?;
@@ -116,7 +146,8 @@ impl Client {
         url: &str,
         query: Option<&T>,
     ) -> Result<Response> {
-        let mut req = self.cli.get(url);
+        self.ensure_logged_in()?;
+        let mut req = self.cli.get(url).timeout(self.request_timeout());
 
         if let Some(query) = query {
             req = req.query(query);
@@ -131,9 +162,14 @@ impl Client {
         url: &str,
         query: Option<&T>,
     ) -> Result<D> {
+        self.ensure_logged_in()?;
         let mut headers = reqwest::header::HeaderMap::new();
         headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
-        let mut req = self.cli.get(url).headers(headers);
+        let mut req = self
+            .cli
+            .get(url)
+            .timeout(self.request_timeout())
+            .headers(headers);
 
         if let Some(query) = query {
             req = req.query(query);
@@ -143,4 +179,23 @@ impl Client {
         let json = utils::parse_json(&response.bytes().await?)?;
         Ok(json)
     }
+
+    // Pages of the login flow itself, the only requests that go out while logged out
+    pub(super) async fn get_login_page(&self, url: &str) -> Result<Response> {
+        let res = self
+            .cli
+            .get(url)
+            .timeout(self.request_timeout())
+            .send()
+            .await?;
+        Ok(res)
+    }
+
+    // Stops requests from going out with cookies the server may still accept after a logout
+    pub(super) fn ensure_logged_in(&self) -> Result<()> {
+        if self.logged_out.load(Ordering::SeqCst) {
+            return Err(AppError::LoginError);
+        }
+        Ok(())
+    }
 }
@@ -2,14 +2,24 @@ pub const BASE_URL: &str = "https://oc.sjtu.edu.cn";
 pub const JI_BASE_URL: &str = "https://jicanvas.com";
 pub const VIDEO_BASE_URL: &str = "https://courses.sjtu.edu.cn/app";
 pub const CANVAS_LOGIN_URL: &str = "https://oc.sjtu.edu.cn/login/openid_connect";
+pub const CANVAS_SESSION_COOKIE: &str = "_normandy_session";
+pub const CANVAS_CSRF_COOKIE: &str = "_csrf_token";
 pub const VIDEO_LOGIN_URL: &str =
     "https://courses.sjtu.edu.cn/app/oauth/2.0/login?login_type=outer";
 pub const VIDEO_OAUTH_KEY_URL: &str = "https://courses.sjtu.edu.cn/app/vodvideo/vodVideoPlay.d2j?ssoCheckToken=ssoCheckToken&refreshToken=&accessToken=&userId=&";
+pub const VIDEO_LTI_LAUNCH_URL: &str = "https://courses.sjtu.edu.cn/lti/launch";
+// video links are only served to requests coming from the platform
+pub const VIDEO_REFERER: &str = "https://courses.sjtu.edu.cn";
 pub const VIDEO_INFO_URL: &str =
     "https://courses.sjtu.edu.cn/app/system/resource/vodVideo/getvideoinfos";
+pub const VIDEO_SUBTITLE_URL: &str =
+    "https://courses.sjtu.edu.cn/app/system/resource/vodVideo/getvideosubtitle";
 pub const AUTH_URL: &str = "https://jaccount.sjtu.edu.cn";
 pub const MY_SJTU_URL: &str = "https://my.sjtu.edu.cn/ui/appmyinfo";
 pub const EXPRESS_LOGIN_URL: &str = "https://jaccount.sjtu.edu.cn/jaccount/expresslogin";
+pub const QR_LOGIN_WEBSOCKET_URL: &str = "wss://jaccount.sjtu.edu.cn/jaccount/sub";
+pub const QR_CODE_BASE_URL: &str = "https://jaccount.sjtu.edu.cn/jaccount/confirmscancode";
+pub const UPDATE_QR_CODE_MESSAGE: &str = r#"{ "type": "UPDATE_QR_CODE" }"#;
 pub const OAUTH_PATH: &str =
     "aHR0cHM6Ly9jb3Vyc2VzLnNqdHUuZWR1LmNuL2FwcC92b2R2aWRlby92b2RWaWRlb1BsYXkuZDJq";
 pub const OAUTH_RANDOM: &str = "oauth_ABCDE=ABCDEFGH&oauth_VWXYZ=STUVWXYZ";
@@ -17,8 +27,36 @@ pub const OAUTH_RANDOM_P1: &str = "oauth_ABCDE";
 pub const OAUTH_RANDOM_P2: &str = "oauth_VWXYZ";
 pub const OAUTH_RANDOM_P1_VAL: &str = "ABCDEFGH";
 pub const OAUTH_RANDOM_P2_VAL: &str = "STUVWXYZ";
-pub const CHUNK_SIZE: u64 = 16 * 1024 * 1024;
+pub const DEFAULT_PROGRESS_INTERVAL_MS: u64 = 200;
+pub const DEFAULT_MAX_CONCURRENT_TRANSFERS: usize = 8;
 pub const VIDEO_CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+// real video data practically never has a whole block of zeros
+pub const VIDEO_REPAIR_BLOCK_SIZE: u64 = 1024 * 1024;
+pub const VIDEO_INFO_RETRY_BASE_DELAY_MS: u64 = 500;
+pub const GET_UUID_MAX_ATTEMPTS: u32 = 3;
+pub const GET_UUID_RETRY_BASE_DELAY_MS: u64 = 500;
+// how much of a login page gets logged when the expected content isn't found in it
+pub const PAGE_LOG_PREFIX_CHARS: usize = 512;
+// the meta (or script variable) of the video page holding the oauth consumer key
+pub const OAUTH_KEY_NAME: &str = "xForSecName";
+pub const VIDEO_RANGE_MAX_RETRIES: u32 = 3;
+pub const VIDEO_PROGRESS_CHANNEL_CAPACITY: usize = 1024;
+pub const VIDEO_COVER_CONCURRENCY: usize = 5;
+pub const VIDEO_RELOGIN_MAX_ATTEMPTS: u32 = 2;
+// headroom kept free besides the video itself
+pub const VIDEO_DISK_SPACE_MARGIN: u64 = 64 * 1024 * 1024;
+pub const TRANSCRIPT_PAGE_SIZE: i64 = 500;
+pub const CANVAS_VIDEO_PAGE_SIZE: i64 = 100;
+pub const VIDEO_PAGE_SIZE: i64 = 100;
+pub const RECOMMENDED_VIDEOS_LIMIT: usize = 3;
+// pages of a paged video api fetched at the same time
+pub const VIDEO_PAGE_CONCURRENCY: usize = 4;
+pub const VIDEO_PAGE_MAX_ATTEMPTS: u32 = 3;
+pub const VIDEO_PAGE_RETRY_BASE_DELAY_MS: u64 = 500;
+// length of the cues made up for transcripts without timing
+pub const SUBTITLE_CUE_MS: u64 = 5000;
+// the platform only transcribes mandarin, so there's a single language per recording
+pub const VIDEO_TRANSCRIPT_LANGUAGE: &str = "zh";
 
 pub const JBOX_LOGIN_URL: &str =
     "https://pan.sjtu.edu.cn/user/v1/sign-in/sso-login-redirect/xpw8ou8y";
@@ -27,3 +65,7 @@ pub const JBOX_USER_SPACE_URL: &str = "https://pan.sjtu.edu.cn/user/v1/space/1/p
 pub const JBOX_BASE_URL: &str = "https://pan.sjtu.edu.cn";
 // 4M
 pub const JBOX_UPLOAD_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+pub const SUBMISSION_UPLOAD_CHUNK_SIZE: usize = 256 * 1024;
+// 5M
+pub const PROFILE_PICTURE_MAX_SIZE: u64 = 5 * 1024 * 1024;
+pub const PROFILE_PICTURES_FOLDER: &str = "profile pictures";
@@ -0,0 +1,468 @@
+use std::{collections::HashMap, fs::File, io::Write};
+
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use reqwest::header::{CONTENT_TYPE, REFERER};
+use tauri::Url;
+
+use super::Client;
+use crate::{
+    error::{AppError, Result},
+    model::{ProgressPayload, VideoClipRange},
+};
+
+type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
+
+const HLS_CONTENT_TYPES: [&str; 3] = [
+    "application/vnd.apple.mpegurl",
+    "application/x-mpegurl",
+    "audio/mpegurl",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HlsKey {
+    pub uri: String,
+    pub iv: Option<[u8; 16]>,
+}
+
+impl HlsKey {
+    // RFC 8216: without an explicit IV, the media sequence number is used as IV
+    pub fn iv_for(&self, sequence: u64) -> [u8; 16] {
+        self.iv.unwrap_or_else(|| {
+            let mut iv = [0u8; 16];
+            iv[8..].copy_from_slice(&sequence.to_be_bytes());
+            iv
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HlsSegment {
+    pub uri: String,
+    pub duration: f64,
+    pub sequence: u64,
+    pub key: Option<HlsKey>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HlsVariant {
+    pub uri: String,
+    pub bandwidth: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HlsPlaylist {
+    pub variants: Vec<HlsVariant>,
+    pub segments: Vec<HlsSegment>,
+}
+
+fn hls_error<E: ToString>(e: E) -> AppError {
+    AppError::HlsError(e.to_string())
+}
+
+// split `KEY=VALUE,KEY="VALUE,WITH,COMMA"` into a map
+fn parse_attributes(attributes: &str) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+    let mut parts = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in attributes.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => parts.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    parts.push(current);
+    for part in parts {
+        if let Some((key, value)) = part.split_once('=') {
+            result.insert(key.trim().to_owned(), value.trim().to_owned());
+        }
+    }
+    result
+}
+
+fn parse_iv(iv: &str) -> Result<[u8; 16]> {
+    let hex = iv.trim_start_matches("0x").trim_start_matches("0X");
+    if hex.len() != 32 {
+        return Err(hls_error(format!("invalid IV: {}", iv)));
+    }
+    let mut bytes = [0u8; 16];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(hls_error)?;
+    }
+    Ok(bytes)
+}
+
+fn parse_key(attributes: &str, base_url: &Url) -> Result<Option<HlsKey>> {
+    let attributes = parse_attributes(attributes);
+    let method = attributes
+        .get("METHOD")
+        .map(String::as_str)
+        .unwrap_or("NONE");
+    match method {
+        "NONE" => Ok(None),
+        "AES-128" => {
+            let uri = attributes
+                .get("URI")
+                .ok_or_else(|| hls_error("AES-128 key without URI"))?;
+            let iv = match attributes.get("IV") {
+                Some(iv) => Some(parse_iv(iv)?),
+                None => None,
+            };
+            Ok(Some(HlsKey {
+                uri: base_url.join(uri).map_err(hls_error)?.to_string(),
+                iv,
+            }))
+        }
+        _ => Err(hls_error(format!(
+            "unsupported encryption method {}",
+            method
+        ))),
+    }
+}
+
+pub fn parse_playlist(base_url: &Url, content: &str) -> Result<HlsPlaylist> {
+    let mut lines = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    if lines.next() != Some("#EXTM3U") {
+        return Err(hls_error("missing #EXTM3U header"));
+    }
+
+    let mut playlist = HlsPlaylist::default();
+    let mut sequence = 0;
+    let mut key = None;
+    let mut duration = None;
+    let mut bandwidth = None;
+    for line in lines {
+        if let Some(value) = line.strip_prefix("#EXT-X-MEDIA-SEQUENCE:") {
+            sequence = value.parse().map_err(hls_error)?;
+        } else if let Some(value) = line.strip_prefix("#EXT-X-KEY:") {
+            key = parse_key(value, base_url)?;
+        } else if let Some(value) = line.strip_prefix("#EXTINF:") {
+            let value = value.split(',').next().unwrap_or_default();
+            duration = Some(value.trim().parse::<f64>().map_err(hls_error)?);
+        } else if let Some(value) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            let attributes = parse_attributes(value);
+            let value = attributes.get("BANDWIDTH").cloned().unwrap_or_default();
+            bandwidth = Some(value.parse().unwrap_or_default());
+        } else if !line.starts_with('#') {
+            let uri = base_url.join(line).map_err(hls_error)?.to_string();
+            if let Some(bandwidth) = bandwidth.take() {
+                playlist.variants.push(HlsVariant { uri, bandwidth });
+            } else {
+                playlist.segments.push(HlsSegment {
+                    uri,
+                    duration: duration.take().unwrap_or_default(),
+                    sequence,
+                    key: key.clone(),
+                });
+                sequence += 1;
+            }
+        }
+    }
+    Ok(playlist)
+}
+
+// Segments overlapping `[start, end]` seconds, with the time range they actually cover.
+// Segments can't be cut, so the clip may start a bit earlier and end a bit later.
+pub fn select_clip_segments(
+    segments: &[HlsSegment],
+    start: f64,
+    end: f64,
+) -> (&[HlsSegment], VideoClipRange) {
+    let mut offset = 0.0;
+    let mut first = None;
+    let mut last = None;
+    let mut range = VideoClipRange::default();
+    for (index, segment) in segments.iter().enumerate() {
+        let segment_end = offset + segment.duration;
+        if offset < end && segment_end > start {
+            if first.is_none() {
+                first = Some(index);
+                range.start = offset;
+            }
+            last = Some(index);
+            range.end = segment_end;
+        }
+        offset = segment_end;
+    }
+    match (first, last) {
+        (Some(first), Some(last)) => (&segments[first..=last], range),
+        _ => (&segments[..0], range),
+    }
+}
+
+pub fn decrypt_segment(key: &[u8], iv: &[u8; 16], data: &[u8]) -> Result<Vec<u8>> {
+    let mut buf = data.to_vec();
+    let decryptor = Aes128CbcDec::new_from_slices(key, iv).map_err(hls_error)?;
+    let len = decryptor
+        .decrypt_padded_mut::<Pkcs7>(&mut buf)
+        .map_err(hls_error)?
+        .len();
+    buf.truncate(len);
+    Ok(buf)
+}
+
+// Apis here are for HLS(m3u8) video streams
+impl Client {
+    pub async fn is_hls_stream(&self, url: &str) -> Result<bool> {
+        if let Ok(parsed) = Url::parse(url) {
+            if parsed.path().ends_with(".m3u8") {
+                return Ok(true);
+            }
+        }
+        let response = self.download_video_partial(url, 0, 0).await?;
+        let content_type = match response.headers().get(CONTENT_TYPE) {
+            Some(content_type) => content_type.to_str()?.to_lowercase(),
+            None => return Ok(false),
+        };
+        Ok(HLS_CONTENT_TYPES
+            .iter()
+            .any(|tp| content_type.starts_with(tp)))
+    }
+
+    async fn get_hls_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        self.ensure_logged_in()?;
+        let _permit = self.acquire_transfer().await;
+        let response = self
+            .cli
+            .get(url)
+            .timeout(self.download_timeout())
+            .header(REFERER, "https://courses.sjtu.edu.cn")
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    pub async fn get_hls_media_playlist(&self, url: &str) -> Result<HlsPlaylist> {
+        let base_url = Url::parse(url).map_err(hls_error)?;
+        let bytes = self.get_hls_bytes(url).await?;
+        let playlist = parse_playlist(&base_url, &String::from_utf8_lossy(&bytes))?;
+        // master playlist, pick the variant with the highest bandwidth
+        let Some(variant) = playlist.variants.iter().max_by_key(|v| v.bandwidth) else {
+            return Ok(playlist);
+        };
+        let base_url = Url::parse(&variant.uri).map_err(hls_error)?;
+        let bytes = self.get_hls_bytes(&variant.uri).await?;
+        parse_playlist(&base_url, &String::from_utf8_lossy(&bytes))
+    }
+
+    // Download all segments in order and concat them into `save_path`.
+    // Progress is reported in number of segments.
+    pub async fn download_hls_video<F: Fn(ProgressPayload)>(
+        &self,
+        video_id: i64,
+        url: &str,
+        save_path: &str,
+        progress_handler: F,
+    ) -> Result<()> {
+        let playlist = self.get_hls_media_playlist(url).await?;
+        if playlist.segments.is_empty() {
+            tracing::warn!("hls playlist {} has no segment", url);
+            return Err(AppError::VideoDownloadError {
+                save_path: save_path.to_owned(),
+                bytes_written: 0,
+                expected_bytes: 0,
+            });
+        }
+        self.download_hls_segments(video_id, &playlist.segments, save_path, progress_handler)
+            .await?;
+        tracing::info!("Successfully downloaded hls video to {}", save_path);
+        Ok(())
+    }
+
+    // Download only the segments overlapping `[start, end]` seconds, returns the range covered
+    pub async fn download_hls_clip<F: Fn(ProgressPayload)>(
+        &self,
+        video_id: i64,
+        url: &str,
+        save_path: &str,
+        start: f64,
+        end: f64,
+        progress_handler: F,
+    ) -> Result<VideoClipRange> {
+        let playlist = self.get_hls_media_playlist(url).await?;
+        let (segments, range) = select_clip_segments(&playlist.segments, start, end);
+        if segments.is_empty() {
+            tracing::warn!("no segment of {} within {}s-{}s", url, start, end);
+            return Err(AppError::InvalidClipRange(start, end));
+        }
+        self.download_hls_segments(video_id, segments, save_path, progress_handler)
+            .await?;
+        tracing::info!(
+            "Successfully downloaded {}s-{}s of hls video to {}",
+            range.start,
+            range.end,
+            save_path
+        );
+        Ok(range)
+    }
+
+    async fn download_hls_segments<F: Fn(ProgressPayload)>(
+        &self,
+        video_id: i64,
+        segments: &[HlsSegment],
+        save_path: &str,
+        progress_handler: F,
+    ) -> Result<()> {
+        let mut payload = ProgressPayload {
+            uuid: video_id.to_string(),
+            processed: 0,
+            total: segments.len() as u64,
+            ..Default::default()
+        };
+        progress_handler(payload.clone());
+
+        let mut keys: HashMap<String, Vec<u8>> = HashMap::new();
+        let mut file = File::create(save_path)?;
+        for segment in segments {
+            let bytes = self.get_hls_bytes(&segment.uri).await?;
+            let data = match &segment.key {
+                Some(key) => {
+                    if !keys.contains_key(&key.uri) {
+                        let key_bytes = self.get_hls_bytes(&key.uri).await?;
+                        keys.insert(key.uri.clone(), key_bytes);
+                    }
+                    decrypt_segment(&keys[&key.uri], &key.iv_for(segment.sequence), &bytes)?
+                }
+                None => bytes,
+            };
+            file.write_all(&data)?;
+            payload.processed += 1;
+            progress_handler(payload.clone());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aes::cipher::BlockEncryptMut;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_media_playlist() -> Result<()> {
+        let base_url = Url::parse("https://example.com/vod/index.m3u8").unwrap();
+        let content = r#"#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-MEDIA-SEQUENCE:7
+#EXT-X-KEY:METHOD=AES-128,URI="key.bin"
+#EXTINF:10.0,
+seg0.ts
+#EXTINF:4.5,
+https://cdn.example.com/seg1.ts
+#EXT-X-KEY:METHOD=NONE
+#EXTINF:3,
+seg2.ts
+#EXT-X-ENDLIST
+"#;
+        let playlist = parse_playlist(&base_url, content)?;
+        assert!(playlist.variants.is_empty());
+        assert_eq!(3, playlist.segments.len());
+
+        let first = &playlist.segments[0];
+        assert_eq!("https://example.com/vod/seg0.ts", first.uri);
+        assert_eq!(10.0, first.duration);
+        assert_eq!(7, first.sequence);
+        let key = first.key.as_ref().unwrap();
+        assert_eq!("https://example.com/vod/key.bin", key.uri);
+        let mut expected_iv = [0u8; 16];
+        expected_iv[15] = 7;
+        assert_eq!(expected_iv, key.iv_for(first.sequence));
+
+        assert_eq!("https://cdn.example.com/seg1.ts", playlist.segments[1].uri);
+        assert_eq!(8, playlist.segments[1].sequence);
+        assert!(playlist.segments[2].key.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_master_playlist() -> Result<()> {
+        let base_url = Url::parse("https://example.com/vod/master.m3u8").unwrap();
+        let content = r#"#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360,CODECS="avc1.4d401e,mp4a.40.2"
+low/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2800000,RESOLUTION=1280x720
+high/index.m3u8
+"#;
+        let playlist = parse_playlist(&base_url, content)?;
+        assert!(playlist.segments.is_empty());
+        assert_eq!(2, playlist.variants.len());
+        assert_eq!(800000, playlist.variants[0].bandwidth);
+        assert_eq!(
+            "https://example.com/vod/high/index.m3u8",
+            playlist.variants[1].uri
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_playlist_with_explicit_iv() -> Result<()> {
+        let base_url = Url::parse("https://example.com/index.m3u8").unwrap();
+        let content = "#EXTM3U\n#EXT-X-KEY:METHOD=AES-128,URI=\"k\",IV=0x000102030405060708090a0b0c0d0e0f\n#EXTINF:1,\na.ts\n";
+        let playlist = parse_playlist(&base_url, content)?;
+        let key = playlist.segments[0].key.as_ref().unwrap();
+        let expected: Vec<u8> = (0..16).collect();
+        assert_eq!(expected, key.iv_for(100).to_vec());
+
+        assert!(parse_playlist(&base_url, "not a playlist").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_clip_segments() -> Result<()> {
+        let base_url = Url::parse("https://example.com/index.m3u8").unwrap();
+        let content = "#EXTM3U\n#EXTINF:10,\na.ts\n#EXTINF:10,\nb.ts\n#EXTINF:10,\nc.ts\n#EXTINF:5.5,\nd.ts\n#EXT-X-ENDLIST\n";
+        let playlist = parse_playlist(&base_url, content)?;
+
+        let (segments, range) = select_clip_segments(&playlist.segments, 12.0, 25.0);
+        assert_eq!(2, segments.len());
+        assert_eq!("https://example.com/b.ts", segments[0].uri);
+        assert_eq!("https://example.com/c.ts", segments[1].uri);
+        assert_eq!(
+            VideoClipRange {
+                start: 10.0,
+                end: 30.0
+            },
+            range
+        );
+
+        // boundaries touching a segment don't pull it in
+        let (segments, range) = select_clip_segments(&playlist.segments, 10.0, 20.0);
+        assert_eq!(1, segments.len());
+        assert_eq!(
+            VideoClipRange {
+                start: 10.0,
+                end: 20.0
+            },
+            range
+        );
+
+        let (segments, range) = select_clip_segments(&playlist.segments, 30.0, 100.0);
+        assert_eq!("https://example.com/d.ts", segments[0].uri);
+        assert_eq!(35.5, range.end);
+
+        let (segments, _) = select_clip_segments(&playlist.segments, 40.0, 50.0);
+        assert!(segments.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decrypt_segment() -> Result<()> {
+        let key = [7u8; 16];
+        let iv = [3u8; 16];
+        let plain = b"transport stream payload".to_vec();
+        let mut buf = plain.clone();
+        buf.resize(32, 0);
+        let encrypted = cbc::Encryptor::<aes::Aes128>::new(&key.into(), &iv.into())
+            .encrypt_padded_mut::<Pkcs7>(&mut buf, plain.len())
+            .unwrap()
+            .to_vec();
+        assert_eq!(plain, decrypt_segment(&key, &iv, &encrypted)?);
+        Ok(())
+    }
+}
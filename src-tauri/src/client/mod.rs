@@ -1,15 +1,44 @@
 use reqwest::cookie::Jar;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::{Mutex, RwLock};
+
+use crate::model::NetworkTimeouts;
 
 pub mod basic;
 mod common;
 pub mod constants;
+mod hls;
 pub mod jbox;
+mod qr_login;
+mod session;
+mod subtitle;
+mod transfer;
 pub mod video;
 
+pub use qr_login::QrLoginSocket;
+use transfer::TransferLimiter;
+
 pub struct Client {
     cli: reqwest::Client,
     jar: Arc<Jar>,
     base_url: RwLock<String>,
+    // only one download task re-logins the video website when the session expires
+    video_relogin_lock: Mutex<()>,
+    video_session_generation: AtomicU64,
+    // canvas requests go with the student view session cookie instead of the token
+    student_view: bool,
+    // set by `logout`, cookie based requests fail with LoginError until the next login
+    logged_out: AtomicBool,
+    timeouts: NetworkTimeouts,
+    // the proxy url every request goes through, empty for none
+    proxy_url: String,
+    progress_interval: Duration,
+    // shared by every download, see `TransferLimiter`
+    transfers: TransferLimiter,
 }
@@ -0,0 +1,78 @@
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use super::{
+    constants::{QR_CODE_BASE_URL, QR_LOGIN_WEBSOCKET_URL, UPDATE_QR_CODE_MESSAGE},
+    Client,
+};
+use crate::{
+    error::{AppError, Result},
+    model::QrSession,
+};
+
+// jaccount pushes QR code updates and the login confirmation through this channel
+pub type QrLoginSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Debug, Deserialize)]
+struct QrLoginMessage {
+    #[serde(rename = "type")]
+    type_field: String,
+    payload: Option<QrCodePayload>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QrCodePayload {
+    sig: String,
+    ts: i64,
+}
+
+// Text messages of the channel, None once it's closed
+async fn next_message(socket: &mut QrLoginSocket) -> Result<Option<QrLoginMessage>> {
+    while let Some(message) = socket.next().await {
+        if let Message::Text(text) = message? {
+            match serde_json::from_str(&text) {
+                Ok(message) => return Ok(Some(message)),
+                Err(e) => tracing::warn!("unknown qr login message {}: {}", text, e),
+            }
+        }
+    }
+    Ok(None)
+}
+
+impl Client {
+    // Subscribe to a fresh login uuid and wait for jaccount to sign its first QR code
+    pub async fn start_qr_login(&self) -> Result<(QrSession, QrLoginSocket)> {
+        let uuid = self.get_uuid().await?;
+        let url = format!("{}/{}", QR_LOGIN_WEBSOCKET_URL, uuid);
+        let (mut socket, _) = connect_async(url).await?;
+        socket
+            .send(Message::Text(UPDATE_QR_CODE_MESSAGE.to_owned()))
+            .await?;
+        while let Some(message) = next_message(&mut socket).await? {
+            if !message.type_field.eq_ignore_ascii_case("UPDATE_QR_CODE") {
+                continue;
+            }
+            if let Some(payload) = message.payload {
+                let qr_url = format!(
+                    "{}?uuid={}&ts={}&sig={}",
+                    QR_CODE_BASE_URL, uuid, payload.ts, payload.sig
+                );
+                return Ok((QrSession { uuid, qr_url }, socket));
+            }
+        }
+        Err(AppError::LoginError)
+    }
+
+    // Resolves with true once the QR code is confirmed on the phone, or false if jaccount
+    // closes the channel. Cancel safe, so it can be polled with a timeout.
+    pub async fn wait_qr_login(socket: &mut QrLoginSocket) -> Result<bool> {
+        while let Some(message) = next_message(socket).await? {
+            if message.type_field.eq_ignore_ascii_case("LOGIN") {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
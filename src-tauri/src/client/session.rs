@@ -0,0 +1,202 @@
+use std::{
+    fs,
+    io::{ErrorKind, Write},
+    sync::atomic::Ordering,
+};
+
+use reqwest::cookie::CookieStore;
+use tauri::Url;
+
+use super::{
+    constants::{AUTH_URL, VIDEO_BASE_URL, VIDEO_LOGIN_URL},
+    Client,
+};
+use crate::{error::Result, model::SavedSession, utils};
+
+impl Client {
+    pub(super) fn jar_cookies(&self, url: &str) -> String {
+        self.jar
+            .cookies(&Url::parse(url).unwrap())
+            .and_then(|value| value.to_str().ok().map(str::to_owned))
+            .unwrap_or_default()
+    }
+
+    // The raw value of a cookie sent to `url`
+    pub(super) fn jar_cookie(&self, url: &str, name: &str) -> Option<String> {
+        self.jar_cookies(url)
+            .split(';')
+            .filter_map(|cookie| cookie.trim().split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.to_owned())
+    }
+
+    // A `Cookie` header holds several cookies, but `add_cookie_str` only takes one at a time
+    pub(super) fn restore_cookies(&self, cookies: &str, url: &str) {
+        let url = Url::parse(url).unwrap();
+        for cookie in cookies.split(';').map(str::trim) {
+            if !cookie.is_empty() {
+                self.jar.add_cookie_str(cookie, &url);
+            }
+        }
+    }
+
+    // The jar can't remove cookies, but storing an already expired one replaces it. Only one
+    // with the same domain and path is replaced and the jar doesn't tell them, so every domain
+    // and path a cookie sent to `url` may have been set for is covered.
+    fn expire_cookies(&self, url: &str) {
+        let cookies = self.jar_cookies(url);
+        let url = Url::parse(url).unwrap();
+        // host-only, or set for a parent domain like `Domain=sjtu.edu.cn`
+        let labels: Vec<_> = url.host_str().unwrap_or_default().split('.').collect();
+        let mut domains = vec![String::new()];
+        for i in 0..labels.len().saturating_sub(1) {
+            domains.push(format!("; Domain={}", labels[i..].join(".")));
+        }
+        let mut paths = vec!["/".to_owned()];
+        for segment in url.path().split('/').filter(|segment| !segment.is_empty()) {
+            let parent = paths.last().unwrap().trim_end_matches('/').to_owned();
+            paths.push(format!("{}/{}", parent, segment));
+        }
+        let names = cookies
+            .split(';')
+            .filter_map(|cookie| cookie.split_once('='))
+            .map(|(name, _)| name.trim());
+        for name in names {
+            for domain in &domains {
+                for path in &paths {
+                    let cookie = format!("{}=; Max-Age=0; Path={}{}", name, path, domain);
+                    self.jar.add_cookie_str(&cookie, &url);
+                }
+            }
+        }
+    }
+
+    pub(super) fn mark_logged_in(&self) {
+        self.logged_out.store(false, Ordering::SeqCst);
+    }
+
+    // Forget the jaccount, video website and canvas cookies along with the saved session,
+    // so a different jaccount can log in without restarting
+    pub async fn logout(&self, session_path: &str) -> Result<()> {
+        self.logged_out.store(true, Ordering::SeqCst);
+        let base_url = self.base_url.read().await.clone();
+        for url in [AUTH_URL, VIDEO_BASE_URL, base_url.as_str()] {
+            self.expire_cookies(url);
+        }
+        match fs::remove_file(session_path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    // Persist the jaccount and video website cookies so the next launch can skip the login.
+    // The file holds credentials, so it's only readable by the current user.
+    pub fn save_session(&self, path: &str) -> Result<()> {
+        let session = SavedSession {
+            auth_cookies: self.jar_cookies(AUTH_URL),
+            video_cookies: self.jar_cookies(VIDEO_BASE_URL),
+            saved_at: utils::unix_timestamp(),
+        };
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(path)?;
+        // the mode only applies to a new file, one left by an older version may be readable
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(&serde_json::to_vec(&session)?)?;
+        Ok(())
+    }
+
+    // Returns whether a saved session was found and is still accepted by the server.
+    // A stale session file is removed so the normal login flow takes over.
+    pub async fn load_session(&self, path: &str) -> Result<bool> {
+        let Ok(content) = fs::read(path) else {
+            return Ok(false);
+        };
+        let Ok(session) = utils::parse_json::<SavedSession>(&content) else {
+            fs::remove_file(path)?;
+            return Ok(false);
+        };
+        self.restore_cookies(&session.auth_cookies, AUTH_URL);
+        self.restore_cookies(&session.video_cookies, VIDEO_BASE_URL);
+        if self.check_session_valid().await? {
+            tracing::info!("Restored session saved at {}", session.saved_at);
+            self.mark_logged_in();
+            return Ok(true);
+        }
+        tracing::info!("Saved session expired, remove {}", path);
+        fs::remove_file(path)?;
+        Ok(false)
+    }
+
+    // Probes whether the current cookies are still logged in, an expired session is
+    // redirected to the jaccount login page
+    pub async fn check_session_valid(&self) -> Result<bool> {
+        let response = self.get_login_page(VIDEO_LOGIN_URL).await?;
+        Ok(response.url().domain() != Some("jaccount.sjtu.edu.cn"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::error::AppError;
+
+    #[tokio::test]
+    async fn test_logout() -> Result<()> {
+        let cli = Client::new();
+        let video_url = Url::parse(VIDEO_BASE_URL).unwrap();
+        cli.jar.add_cookie_str("JSESSIONID=a", &video_url);
+        cli.jar
+            .add_cookie_str("JAAuthCookie=b; Domain=sjtu.edu.cn; Path=/app", &video_url);
+        let session_path = env::temp_dir().join("test_logout_session.json");
+        cli.logout(session_path.to_str().unwrap()).await?;
+        assert_eq!("", cli.jar_cookies(VIDEO_BASE_URL));
+
+        // nothing goes out with the old cookies
+        assert!(matches!(
+            cli.get_request(VIDEO_BASE_URL, None::<&str>).await,
+            Err(AppError::LoginError)
+        ));
+        assert!(matches!(
+            cli.get_json_with_cookie::<_, serde_json::Value>(VIDEO_BASE_URL, None::<&str>)
+                .await,
+            Err(AppError::LoginError)
+        ));
+        assert!(matches!(
+            cli.post_form(VIDEO_BASE_URL, None::<&str>, &[("id", "1")])
+                .await,
+            Err(AppError::LoginError)
+        ));
+        assert!(matches!(
+            cli.download_video_partial(VIDEO_BASE_URL, 0, 0).await,
+            Err(AppError::LoginError)
+        ));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_session_permissions() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = env::temp_dir().join("test_save_session_permissions.json");
+        fs::write(&path, "")?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644))?;
+        Client::new().save_session(path.to_str().unwrap())?;
+        let mode = fs::metadata(&path)?.permissions().mode();
+        fs::remove_file(&path)?;
+        assert_eq!(0o600, mode & 0o777);
+        Ok(())
+    }
+}
@@ -0,0 +1,328 @@
+use std::{fs, path::Path};
+
+use reqwest::StatusCode;
+
+use super::{
+    constants::{
+        SUBTITLE_CUE_MS, TRANSCRIPT_PAGE_SIZE, VIDEO_SUBTITLE_URL, VIDEO_TRANSCRIPT_LANGUAGE,
+    },
+    Client,
+};
+use crate::{
+    error::{AppError, Result},
+    model::{ItemPage, TranscriptSegment},
+};
+
+// Parses "01:02:03,456" (srt), "01:02:03.456" or "02:03.456" (vtt) into milliseconds
+fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let (clock, millis) = timestamp.trim().split_once(|c| c == '.' || c == ',')?;
+    let millis: u64 = millis.parse().ok()?;
+    let parts = clock
+        .split(':')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let seconds = match parts[..] {
+        [h, m, s] => h * 3600 + m * 60 + s,
+        [m, s] => m * 60 + s,
+        _ => return None,
+    };
+    Some(seconds * 1000 + millis)
+}
+
+// Parses both srt and vtt, they only differ in headers, cue numbers and the millisecond separator
+pub fn parse_subtitles(content: &str) -> Vec<TranscriptSegment> {
+    let mut cues = vec![];
+    let content = content.replace("\r\n", "\n");
+    for block in content.split("\n\n") {
+        let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+        let Some(timing) = lines.next() else {
+            continue;
+        };
+        let Some((start, end)) = timing.split_once("-->") else {
+            continue;
+        };
+        // vtt may append cue settings after the end timestamp
+        let end = end.split_whitespace().next().unwrap_or_default();
+        let (Some(start_ms), Some(end_ms)) = (parse_timestamp(start), parse_timestamp(end)) else {
+            continue;
+        };
+        let text = lines.collect::<Vec<_>>().join("\n");
+        if text.is_empty() {
+            continue;
+        }
+        cues.push(TranscriptSegment {
+            start_ms,
+            end_ms,
+            text,
+        });
+    }
+    cues
+}
+
+fn format_timestamp(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+pub fn format_srt(cues: &[TranscriptSegment]) -> String {
+    cues.iter()
+        .enumerate()
+        .map(|(index, cue)| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                index + 1,
+                format_timestamp(cue.start_ms, ','),
+                format_timestamp(cue.end_ms, ','),
+                cue.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn format_vtt(cues: &[TranscriptSegment]) -> String {
+    let mut content = "WEBVTT\n".to_owned();
+    for cue in cues {
+        content += &format!(
+            "\n{} --> {}\n{}\n",
+            format_timestamp(cue.start_ms, '.'),
+            format_timestamp(cue.end_ms, '.'),
+            cue.text
+        );
+    }
+    content
+}
+
+// Untimed transcripts get one line every SUBTITLE_CUE_MS, and cues overlapping each other
+// (several speakers at once) are merged so players don't stack them.
+pub fn normalize_cues(cues: Vec<TranscriptSegment>) -> Vec<TranscriptSegment> {
+    if cues.iter().all(|cue| cue.end_ms == 0) {
+        return cues
+            .into_iter()
+            .enumerate()
+            .map(|(index, cue)| TranscriptSegment {
+                start_ms: index as u64 * SUBTITLE_CUE_MS,
+                end_ms: (index as u64 + 1) * SUBTITLE_CUE_MS,
+                text: cue.text,
+            })
+            .collect();
+    }
+    let mut normalized: Vec<TranscriptSegment> = vec![];
+    for cue in cues {
+        match normalized.last_mut() {
+            Some(last) if cue.start_ms < last.end_ms => {
+                last.end_ms = last.end_ms.max(cue.end_ms);
+                last.text = format!("{}\n{}", last.text, cue.text);
+            }
+            _ => normalized.push(cue),
+        }
+    }
+    normalized
+}
+
+impl Client {
+    // Write the transcript of a video next to it as `.srt` and/or `.vtt`,
+    // nothing is written for videos without transcript
+    pub async fn save_video_subtitles(
+        &self,
+        video_id: i64,
+        save_path: &str,
+        srt: bool,
+        vtt: bool,
+    ) -> Result<()> {
+        let cues = normalize_cues(self.get_video_transcript(video_id).await?);
+        if cues.is_empty() {
+            tracing::info!("video {} has no transcript", video_id);
+            return Ok(());
+        }
+        let save_path = Path::new(save_path);
+        if srt {
+            fs::write(save_path.with_extension("srt"), format_srt(&cues))?;
+        }
+        if vtt {
+            fs::write(save_path.with_extension("vtt"), format_vtt(&cues))?;
+        }
+        Ok(())
+    }
+
+    // Saves the transcript of an already downloaded video as `{video_path_without_ext}.{lang}.srt`,
+    // the sidecar naming media players like Plex pick up. The recording is looked up through
+    // the `.info.json` metadata written at download time.
+    pub async fn copy_video_subtitles_to_sidecar(&self, video_path: &str) -> Result<()> {
+        let metadata = Client::read_video_metadata(video_path)
+            .ok_or_else(|| AppError::VideoMetadataNotFound(video_path.to_owned()))?;
+        let cues = normalize_cues(self.get_video_transcript(metadata.video_id).await?);
+        if cues.is_empty() {
+            tracing::info!("video {} has no transcript", metadata.video_id);
+            return Ok(());
+        }
+        let subtitle_path =
+            Path::new(video_path).with_extension(format!("{}.srt", VIDEO_TRANSCRIPT_LANGUAGE));
+        fs::write(subtitle_path, format_srt(&cues))?;
+        Ok(())
+    }
+
+    // Returns an empty list instead of an error for videos without subtitles. Long
+    // transcripts come in pages, plain arrays and srt/vtt files in one go.
+    pub async fn get_video_transcript(&self, video_id: i64) -> Result<Vec<TranscriptSegment>> {
+        self.ensure_logged_in()?;
+        let mut segments = vec![];
+        let mut page_index = 1;
+        loop {
+            let response = self
+                .cli
+                .get(VIDEO_SUBTITLE_URL)
+                .query(&[
+                    ("id", video_id),
+                    ("pageSize", TRANSCRIPT_PAGE_SIZE),
+                    ("pageIndex", page_index),
+                ])
+                .send()
+                .await?;
+            if response.status() == StatusCode::NOT_FOUND {
+                return Ok(segments);
+            }
+            let body = response.error_for_status()?.text().await?;
+            let body = body.trim();
+            if body.starts_with('[') {
+                return Ok(serde_json::from_str(body).unwrap_or_default());
+            }
+            if !body.starts_with('{') {
+                return Ok(parse_subtitles(body));
+            }
+            let Ok(page) = serde_json::from_str::<ItemPage<TranscriptSegment>>(body) else {
+                tracing::warn!("unknown transcript page of video {}", video_id);
+                return Ok(segments);
+            };
+            segments.extend(page.list);
+            if page.page.page_count == 0 || page.page.page_next == page_index {
+                return Ok(segments);
+            }
+            page_index += 1;
+        }
+    }
+
+    // Canvas (LTI) videos are identified by a string id, the transcript is keyed by
+    // the id of the recording behind it
+    pub async fn get_canvas_video_transcript(
+        &self,
+        video_id: &str,
+    ) -> Result<Vec<TranscriptSegment>> {
+        let info = self.get_canvas_video_info(video_id).await?;
+        self.get_video_transcript(info.id).await
+    }
+
+    // The platform doesn't list subtitle languages, it only transcribes mandarin, so a
+    // recording has its transcript language or nothing at all
+    pub async fn get_video_platform_subtitle_languages(
+        &self,
+        video_id: i64,
+    ) -> Result<Vec<String>> {
+        let transcript = self.get_video_transcript(video_id).await?;
+        if transcript.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(vec![VIDEO_TRANSCRIPT_LANGUAGE.to_owned()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(Some(3_723_456), parse_timestamp("01:02:03,456"));
+        assert_eq!(Some(3_723_456), parse_timestamp("01:02:03.456"));
+        assert_eq!(Some(123_456), parse_timestamp("02:03.456"));
+        assert_eq!(None, parse_timestamp("02:03"));
+    }
+
+    #[test]
+    fn test_parse_srt() {
+        let content = "1\r\n00:00:01,000 --> 00:00:02,500\r\n同学们好\r\n\r\n2\r\n00:00:03,000 --> 00:00:04,000\r\n今天讲\r\n第二章\r\n";
+        let cues = parse_subtitles(content);
+        assert_eq!(
+            vec![
+                TranscriptSegment {
+                    start_ms: 1000,
+                    end_ms: 2500,
+                    text: "同学们好".to_owned(),
+                },
+                TranscriptSegment {
+                    start_ms: 3000,
+                    end_ms: 4000,
+                    text: "今天讲\n第二章".to_owned(),
+                },
+            ],
+            cues
+        );
+    }
+
+    #[test]
+    fn test_parse_vtt() {
+        let content =
+            "WEBVTT\n\nNOTE generated\n\nintro\n00:01.000 --> 00:02.000 align:start\nhello\n";
+        let cues = parse_subtitles(content);
+        assert_eq!(
+            vec![TranscriptSegment {
+                start_ms: 1000,
+                end_ms: 2000,
+                text: "hello".to_owned(),
+            }],
+            cues
+        );
+        assert!(parse_subtitles("WEBVTT\n").is_empty());
+    }
+
+    #[test]
+    fn test_format_subtitles() {
+        let cues = vec![
+            TranscriptSegment {
+                start_ms: 1000,
+                end_ms: 3_723_456,
+                text: "同学们好".to_owned(),
+            },
+            TranscriptSegment {
+                start_ms: 3_800_000,
+                end_ms: 3_801_000,
+                text: "下课".to_owned(),
+            },
+        ];
+        let srt = format_srt(&cues);
+        assert_eq!(
+            "1\n00:00:01,000 --> 01:02:03,456\n同学们好\n\n2\n01:03:20,000 --> 01:03:21,000\n下课\n",
+            srt
+        );
+        // what we write can be read back
+        assert_eq!(cues, parse_subtitles(&srt));
+        assert_eq!(cues, parse_subtitles(&format_vtt(&cues)));
+    }
+
+    #[test]
+    fn test_normalize_cues() {
+        let cue = |start_ms, end_ms, text: &str| TranscriptSegment {
+            start_ms,
+            end_ms,
+            text: text.to_owned(),
+        };
+        assert_eq!(
+            vec![cue(0, 5000, "a"), cue(5000, 10000, "b")],
+            normalize_cues(vec![cue(0, 0, "a"), cue(0, 0, "b")])
+        );
+        assert_eq!(
+            vec![cue(0, 3000, "老师：a\n学生：b"), cue(4000, 5000, "c")],
+            normalize_cues(vec![
+                cue(0, 2000, "老师：a"),
+                cue(1000, 3000, "学生：b"),
+                cue(4000, 5000, "c"),
+            ])
+        );
+    }
+}
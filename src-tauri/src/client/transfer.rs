@@ -0,0 +1,90 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use super::Client;
+use crate::model::TransferStats;
+
+// Caps the http transfers of all downloads together, however many batches are running.
+// tokio's semaphore is fair, waiting transfers get a permit in the order they asked.
+pub(super) struct TransferLimiter {
+    semaphore: Semaphore,
+    limit: usize,
+    queued: AtomicUsize,
+}
+
+// Counts a transfer as queued until it gets its permit or gives up waiting
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl TransferLimiter {
+    pub fn new(limit: usize) -> Self {
+        let limit = limit.max(1);
+        Self {
+            semaphore: Semaphore::new(limit),
+            limit,
+            queued: Default::default(),
+        }
+    }
+
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        let _guard = QueuedGuard(&self.queued);
+        // the semaphore is never closed
+        self.semaphore.acquire().await.unwrap()
+    }
+
+    pub fn stats(&self) -> TransferStats {
+        TransferStats {
+            limit: self.limit,
+            in_flight: self.limit - self.semaphore.available_permits(),
+            queued: self.queued.load(Ordering::SeqCst),
+        }
+    }
+}
+
+impl Client {
+    pub fn with_transfer_limit(mut self, limit: usize) -> Self {
+        self.transfers = TransferLimiter::new(limit);
+        self
+    }
+
+    // Held while a download request is sent and its body read
+    pub(super) async fn acquire_transfer(&self) -> SemaphorePermit<'_> {
+        self.transfers.acquire().await
+    }
+
+    pub fn transfer_stats(&self) -> TransferStats {
+        self.transfers.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_transfer_limiter() {
+        let limiter = TransferLimiter::new(2);
+        let first = limiter.acquire().await;
+        let _second = limiter.acquire().await;
+        let stats = limiter.stats();
+        assert_eq!((stats.in_flight, stats.queued), (2, 0));
+
+        // a third transfer waits for a permit
+        let third = limiter.acquire();
+        tokio::pin!(third);
+        assert!(futures::poll!(third.as_mut()).is_pending());
+        assert_eq!(limiter.stats().queued, 1);
+
+        drop(first);
+        let _third = third.await;
+        let stats = limiter.stats();
+        assert_eq!((stats.in_flight, stats.queued), (2, 0));
+    }
+}
@@ -1,17 +1,23 @@
 use std::{
     collections::HashMap,
-    fs::File,
-    io::Write,
-    sync::Arc,
-    time::{SystemTime, UNIX_EPOCH},
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::Path,
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+use async_stream::stream;
 use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::NaiveDate;
+use futures::{future, Stream, TryStreamExt};
 use md5::{Digest, Md5};
 use regex::Regex;
 use reqwest::{
     cookie::CookieStore,
-    header::{HeaderValue, ACCEPT, CONTENT_RANGE, RANGE, REFERER},
+    header::{
+        HeaderValue, ACCEPT, ACCEPT_RANGES, CONTENT_RANGE, CONTENT_TYPE, LOCATION, RANGE, REFERER,
+    },
     redirect::Policy,
     Response, StatusCode,
 };
@@ -22,28 +28,450 @@ use select::{
 };
 use serde::{de::DeserializeOwned, Serialize};
 use tauri::Url;
-use tokio::{sync::Mutex, task::JoinSet};
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        Mutex, Semaphore,
+    },
+    task::JoinSet,
+};
 
 use super::{
     constants::{
-        AUTH_URL, CANVAS_LOGIN_URL, EXPRESS_LOGIN_URL, MY_SJTU_URL, VIDEO_BASE_URL,
-        VIDEO_LOGIN_URL, VIDEO_OAUTH_KEY_URL,
+        AUTH_URL, CANVAS_LOGIN_URL, CANVAS_VIDEO_PAGE_SIZE, EXPRESS_LOGIN_URL, MY_SJTU_URL,
+        RECOMMENDED_VIDEOS_LIMIT, VIDEO_BASE_URL, VIDEO_LOGIN_URL, VIDEO_LTI_LAUNCH_URL,
+        VIDEO_OAUTH_KEY_URL, VIDEO_REFERER,
     },
     Client,
 };
 use crate::{
     client::constants::{
-        OAUTH_PATH, OAUTH_RANDOM, OAUTH_RANDOM_P1, OAUTH_RANDOM_P1_VAL, OAUTH_RANDOM_P2,
-        OAUTH_RANDOM_P2_VAL, VIDEO_CHUNK_SIZE, VIDEO_INFO_URL,
+        GET_UUID_MAX_ATTEMPTS, GET_UUID_RETRY_BASE_DELAY_MS, OAUTH_KEY_NAME, OAUTH_PATH,
+        OAUTH_RANDOM, OAUTH_RANDOM_P1, OAUTH_RANDOM_P1_VAL, OAUTH_RANDOM_P2, OAUTH_RANDOM_P2_VAL,
+        PAGE_LOG_PREFIX_CHARS, VIDEO_CHUNK_SIZE, VIDEO_COVER_CONCURRENCY, VIDEO_DISK_SPACE_MARGIN,
+        VIDEO_INFO_RETRY_BASE_DELAY_MS, VIDEO_INFO_URL, VIDEO_PAGE_CONCURRENCY,
+        VIDEO_PAGE_MAX_ATTEMPTS, VIDEO_PAGE_RETRY_BASE_DELAY_MS, VIDEO_PAGE_SIZE,
+        VIDEO_PROGRESS_CHANNEL_CAPACITY, VIDEO_RANGE_MAX_RETRIES, VIDEO_RELOGIN_MAX_ATTEMPTS,
+        VIDEO_REPAIR_BLOCK_SIZE,
     },
     error::{AppError, Result},
     model::{
-        CanvasVideo, CanvasVideoResponse, GetCanvasVideoInfoResponse, ItemPage, ProgressPayload,
-        Subject, VideoCourse, VideoInfo, VideoPlayInfo,
+        ApiVersion, CanvasVideo, CanvasVideoResponse, CanvasVideoResponseBody, CanvasVideoSortKey,
+        ConflictPolicy, DownloadResult, ExternalTool, GetCanvasVideoInfoResponse, ItemPage,
+        ProgressPayload, SortOrder, Subject, SubjectClass, Term, ThumbnailReady, Video,
+        VideoClipRange, VideoCourse, VideoDirectUrl, VideoDownloadFailure, VideoDownloadReport,
+        VideoDownloadResult, VideoFilenameFields, VideoInfo, VideoListEntry, VideoListFormat,
+        VideoMetadata, VideoPlayInfo, VideoQuality, VideoRepairReport, VideoSearchHit,
+        VideoSidecars, VideoUrlProbe, VideoUrlStatus, WatchRecord,
     },
-    utils::{self, write_file_at_offset},
+    utils,
 };
 
+// Lazily yields every item behind a paged video api url, only requesting the next page
+// once the consumer has drained the current one.
+pub fn paged_stream<T>(client: Arc<Client>, url: String) -> impl Stream<Item = Result<T>>
+where
+    T: Serialize + DeserializeOwned,
+{
+    stream! {
+        let mut page_index = 1;
+        loop {
+            let item_page = match client.get_item_page::<T>(&url, page_index).await {
+                Ok(item_page) => item_page,
+                Err(e) => {
+                    yield Err(e);
+                    break;
+                }
+            };
+            let ItemPage { page, list } = item_page;
+            for item in list {
+                yield Ok(item);
+            }
+            if page.page_count == 0 || page.page_next == page_index {
+                break;
+            }
+            page_index += 1;
+        }
+    }
+}
+
+// The course list may contain nulls, and is empty for subjects without a course
+// for the given teacher (audited courses for example)
+fn collect_video_courses(items: Vec<Option<VideoCourse>>) -> Vec<VideoCourse> {
+    items.into_iter().flatten().collect()
+}
+
+// Every whitespace separated term of `keyword` has to appear in the text, ignoring case
+fn keyword_matches(text: &str, keyword: &str) -> bool {
+    let text = text.to_lowercase();
+    keyword
+        .to_lowercase()
+        .split_whitespace()
+        .all(|term| text.contains(term))
+}
+
+// Terms may match different fields, "数值分析 第10周" finds the week 10 recording of the subject
+pub fn video_search_hits(
+    subject: &Subject,
+    courses: &[VideoCourse],
+    keyword: &str,
+) -> Vec<VideoSearchHit> {
+    courses
+        .iter()
+        .flat_map(|course| &course.response_vo_list)
+        .filter(|video| {
+            let text = format!(
+                "{} {} {} {}",
+                subject.subject_name, subject.tecl_name, video.user_name, video.vide_name
+            );
+            keyword_matches(&text, keyword)
+        })
+        .map(|video| VideoSearchHit {
+            subject_id: subject.subject_id,
+            tecl_id: subject.tecl_id,
+            subject_name: subject.subject_name.clone(),
+            teacher: video.user_name.clone(),
+            cour_id: video.cour_id,
+            video_id: video.id,
+            video_name: video.vide_name.clone(),
+            cour_begin_time: video.cour_begin_time,
+        })
+        .collect()
+}
+
+// Recording times share one "%Y-%m-%d %H:%M:%S" format, so they sort as plain strings
+pub fn sort_canvas_videos(videos: &mut [CanvasVideo], key: CanvasVideoSortKey, order: SortOrder) {
+    match key {
+        CanvasVideoSortKey::Date => {
+            videos.sort_by(|v1, v2| v1.course_begin_time.cmp(&v2.course_begin_time))
+        }
+        CanvasVideoSortKey::Duration => videos.sort_by_key(CanvasVideo::duration_secs),
+        CanvasVideoSortKey::Title => videos.sort_by(|v1, v2| v1.video_name.cmp(&v2.video_name)),
+    }
+    if order == SortOrder::Desc {
+        videos.reverse();
+    }
+}
+
+// The platform has no recommendation api, the lectures recorded after `video_id` are
+// recommended instead, the closest one first
+pub fn recommended_videos(mut videos: Vec<CanvasVideo>, video_id: &str) -> Vec<CanvasVideo> {
+    sort_canvas_videos(&mut videos, CanvasVideoSortKey::Date, SortOrder::Asc);
+    let Some(index) = videos.iter().position(|video| video.video_id == video_id) else {
+        return vec![];
+    };
+    videos
+        .into_iter()
+        .skip(index + 1)
+        .take(RECOMMENDED_VIDEOS_LIMIT)
+        .collect()
+}
+
+// Unix seconds after which a signed link stops working, for the cdn signatures that state it
+// as an `expires` style query parameter
+pub fn signed_url_expiry(url: &str) -> Option<u64> {
+    let url = Url::parse(url).ok()?;
+    url.query_pairs().find_map(|(key, value)| {
+        let key = key.to_ascii_lowercase();
+        if key == "expires" || key.ends_with("-expires") || key.ends_with("_expires") {
+            value.parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+// One link per camera of the recording, main camera first. Live broadcasts only have rtmp
+// links, which can't be downloaded and are left out.
+pub fn video_direct_urls(info: &VideoInfo, quality: VideoQuality) -> Vec<VideoDirectUrl> {
+    info.video_play_response_vo_list
+        .iter()
+        .filter_map(|play| {
+            let url = play
+                .candidate_urls(quality)
+                .into_iter()
+                .find(|url| url.starts_with("http://") || url.starts_with("https://"))?;
+            let on_platform = Url::parse(url).is_ok_and(|parsed| {
+                parsed
+                    .host_str()
+                    .is_some_and(|host| host == "sjtu.edu.cn" || host.ends_with(".sjtu.edu.cn"))
+            });
+            Some(VideoDirectUrl {
+                play_id: play.id,
+                url: url.to_owned(),
+                quality: play.url_quality(url),
+                referer: on_platform.then(|| VIDEO_REFERER.to_owned()),
+                expires_at: signed_url_expiry(url),
+            })
+        })
+        .collect()
+}
+
+// The exported row of `video`, `info` fills in the duration and provides the download urls
+pub fn video_list_entry(
+    video: &CanvasVideo,
+    info: Option<&VideoInfo>,
+    quality: VideoQuality,
+) -> VideoListEntry {
+    let download_urls = info
+        .map(|info| {
+            info.video_play_response_vo_list
+                .iter()
+                .filter_map(|play| play.select_url(quality))
+                .map(str::to_owned)
+                .collect()
+        })
+        .unwrap_or_default();
+    VideoListEntry {
+        title: video.video_name.clone(),
+        recorded_at: video.course_begin_time.clone(),
+        duration_secs: video
+            .duration_secs()
+            .or_else(|| info.map(|info| info.vide_play_time)),
+        video_id: video.video_id.clone(),
+        download_urls,
+    }
+}
+
+fn format_video_duration(secs: i64) -> String {
+    format!("{}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)
+}
+
+// CSV gets a readable duration and one url per line of the cell, JSON keeps the plain values
+pub fn render_video_list(
+    entries: &[VideoListEntry],
+    format: VideoListFormat,
+    include_urls: bool,
+) -> Result<String> {
+    if format == VideoListFormat::Json {
+        return Ok(serde_json::to_string_pretty(entries)?);
+    }
+    let mut header = vec!["title", "recorded_at", "duration", "video_id"];
+    if include_urls {
+        header.push("download_url");
+    }
+    let rows: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            let mut row = vec![
+                entry.title.clone(),
+                entry.recorded_at.clone(),
+                entry
+                    .duration_secs
+                    .map(format_video_duration)
+                    .unwrap_or_default(),
+                entry.video_id.clone(),
+            ];
+            if include_urls {
+                row.push(entry.download_urls.join("\n"));
+            }
+            row
+        })
+        .collect();
+    Ok(utils::export_csv(&header, &rows))
+}
+
+// The recording tool launches courses.sjtu.edu.cn, its id differs between canvas accounts
+pub fn find_video_tool(tools: &[ExternalTool]) -> Option<i64> {
+    let without_scheme = |url: &str| url.split_once("://").map_or(url, |(_, rest)| rest);
+    let launch_url = without_scheme(VIDEO_LTI_LAUNCH_URL);
+    tools
+        .iter()
+        .find(|tool| {
+            tool.url
+                .as_deref()
+                .is_some_and(|url| without_scheme(url).starts_with(launch_url))
+        })
+        .map(|tool| tool.id)
+}
+
+// The page to request after `page_index`, None once the list is exhausted
+fn next_canvas_video_page(body: &Option<CanvasVideoResponseBody>, page_index: i64) -> Option<i64> {
+    let body = body.as_ref()?;
+    let page = &body.page;
+    if body.list.is_empty() || page.page_count == 0 || page.page_next == page_index {
+        return None;
+    }
+    Some(page_index + 1)
+}
+
+// The beginning of a page for the logs, cut at a char boundary
+fn page_head(body: &str) -> &str {
+    body.char_indices()
+        .nth(PAGE_LOG_PREFIX_CHARS)
+        .map_or(body, |(end, _)| &body[..end])
+}
+
+// The key is base64 encoded in `<meta id="xForSecName" vaule="...">`. The page misspells the
+// attribute, `value` and `content` are accepted too so the login survives the day it gets
+// fixed. Without the meta the scripts of the page are searched for the same blob.
+fn parse_oauth_consumer_key(body: &str) -> Result<String> {
+    let document = Document::from(body);
+    let meta_key = document
+        .find(Name("meta"))
+        .filter(|n: &Node| {
+            n.attr("id") == Some(OAUTH_KEY_NAME) || n.attr("name") == Some(OAUTH_KEY_NAME)
+        })
+        .find_map(|meta| {
+            ["vaule", "value", "content"]
+                .into_iter()
+                .find_map(|attr| meta.attr(attr))
+                .map(str::to_owned)
+        });
+    let key = meta_key.or_else(|| {
+        let re = Regex::new(&format!(
+            r#"{}['"]?\s*[:=,]\s*['"]([A-Za-z0-9+/]+={{0,2}})['"]"#,
+            OAUTH_KEY_NAME
+        ))
+        .unwrap();
+        document
+            .find(Name("script"))
+            .find_map(|script| Some(re.captures(&script.text())?.get(1)?.as_str().to_owned()))
+    });
+    let Some(key) = key else {
+        tracing::warn!("no {} found in page: {}", OAUTH_KEY_NAME, page_head(body));
+        return Err(AppError::OAuthConsumerKeyNotFound);
+    };
+    let bytes = STANDARD
+        .decode(&key)
+        .map_err(|e| AppError::InvalidOAuthConsumerKey(format!("{:?}, {}", key, e)))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// Parallel classes of a subject are listed as separate subjects sharing the subject id
+pub fn subject_classes(subjects: &[Subject], subject_id: i64) -> Vec<SubjectClass> {
+    let mut classes: Vec<SubjectClass> = vec![];
+    for subject in subjects.iter().filter(|s| s.subject_id == subject_id) {
+        if classes.iter().any(|class| class.tecl_id == subject.tecl_id) {
+            continue;
+        }
+        classes.push(SubjectClass {
+            tecl_id: subject.tecl_id,
+            teacher_name: subject.user_name.clone(),
+            class_name: subject.tecl_name.clone(),
+        });
+    }
+    classes
+}
+
+// Every term the subjects were taught in, newest first
+pub fn list_terms(subjects: &[Subject]) -> Vec<Term> {
+    let mut terms: Vec<_> = subjects.iter().map(Subject::term).collect();
+    terms.sort_by(|t1, t2| t2.cmp(t1));
+    terms.dedup();
+    terms
+}
+
+fn watched_on(record: &WatchRecord, date: NaiveDate) -> bool {
+    record
+        .watch_time
+        .get(..10)
+        .and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+        == Some(date)
+}
+
+// Human readable descriptions of what changed between two fetches of the same video
+fn diff_video_info(cached: &VideoInfo, current: &VideoInfo) -> Vec<String> {
+    let mut changes = vec![];
+    if cached.vide_name != current.vide_name {
+        changes.push(format!(
+            "标题：{} -> {}",
+            cached.vide_name, current.vide_name
+        ));
+    }
+    if cached.vide_play_time != current.vide_play_time {
+        changes.push(format!(
+            "时长：{}s -> {}s",
+            cached.vide_play_time, current.vide_play_time
+        ));
+    }
+    if cached.vide_begin_time != current.vide_begin_time
+        || cached.vide_end_time != current.vide_end_time
+    {
+        changes.push(format!(
+            "录制时间：{} ~ {} -> {} ~ {}",
+            cached.vide_begin_time,
+            cached.vide_end_time,
+            current.vide_begin_time,
+            current.vide_end_time
+        ));
+    }
+    let quality =
+        |play: &VideoPlayInfo| match (play.rtmp_url_hdv.is_empty(), play.rtmp_url_sdv.is_empty()) {
+            (false, false) => "高清/标清",
+            (false, true) => "高清",
+            (true, false) => "标清",
+            (true, true) => "无",
+        };
+    for (index, play) in current.video_play_response_vo_list.iter().enumerate() {
+        let Some(cached_play) = cached
+            .video_play_response_vo_list
+            .iter()
+            .find(|cached_play| cached_play.id == play.id)
+        else {
+            changes.push(format!("新增视频流 {}", index + 1));
+            continue;
+        };
+        if cached_play.vide_play_time != play.vide_play_time {
+            changes.push(format!(
+                "视频流 {} 时长：{}s -> {}s",
+                index + 1,
+                cached_play.vide_play_time,
+                play.vide_play_time
+            ));
+        }
+        if quality(cached_play) != quality(play) {
+            changes.push(format!(
+                "视频流 {} 清晰度：{} -> {}",
+                index + 1,
+                quality(cached_play),
+                quality(play)
+            ));
+        }
+    }
+    let removed = cached
+        .video_play_response_vo_list
+        .iter()
+        .filter(|cached_play| {
+            !current
+                .video_play_response_vo_list
+                .iter()
+                .any(|play| play.id == cached_play.id)
+        })
+        .count();
+    if removed > 0 {
+        changes.push(format!("移除 {} 个视频流", removed));
+    }
+    changes
+}
+
+// Ranges [begin, end] of all-zero blocks, the holes a crashed download leaves in its
+// preallocated file. Each hole is widened by a block on both sides, its edges rarely
+// fall on a block boundary and refetching a few more bytes is harmless.
+fn find_zero_ranges<R: Read>(
+    mut reader: R,
+    size: u64,
+    block_size: u64,
+) -> io::Result<Vec<(u64, u64)>> {
+    let mut ranges: Vec<(u64, u64)> = vec![];
+    let mut buf = vec![0; block_size as usize];
+    let mut offset = 0;
+    while offset < size {
+        let len = block_size.min(size - offset) as usize;
+        reader.read_exact(&mut buf[..len])?;
+        if buf[..len].iter().all(|byte| *byte == 0) {
+            let begin = offset.saturating_sub(block_size);
+            let end = (offset + len as u64 + block_size).min(size) - 1;
+            match ranges.last_mut() {
+                Some(last) if begin <= last.1 + 1 => last.1 = end,
+                _ => ranges.push((begin, end)),
+            }
+        }
+        offset += len as u64;
+    }
+    Ok(ranges)
+}
+
 // Apis here are for course video
 // We take references from: https://github.com/prcwcy/sjtu-canvas-video-download/blob/master/sjtu_canvas_video.py
 impl Client {
@@ -52,10 +480,42 @@ impl Client {
             .add_cookie_str(cookie, &Url::parse(VIDEO_BASE_URL).unwrap());
     }
 
-    pub async fn get_uuid(&self) -> Result<Option<String>> {
-        let resp = self.cli.get(MY_SJTU_URL).send().await?.error_for_status()?;
+    // Network errors and pages without uuid are both retried, the latter is reported as
+    // AppError::UuidNotFound so a redesign of the page can be told apart from a flaky network.
+    pub async fn get_uuid(&self) -> Result<String> {
+        let mut attempt = 1;
+        let mut delay = Duration::from_millis(GET_UUID_RETRY_BASE_DELAY_MS);
+        loop {
+            match self.fetch_uuid().await.map_err(AppError::classify) {
+                Err(e)
+                    if attempt < GET_UUID_MAX_ATTEMPTS
+                        && (e.is_retryable()
+                            || matches!(e, AppError::Network(_) | AppError::UuidNotFound)) =>
+                {
+                    tracing::warn!(
+                        "get uuid failed at attempt {}: {}, retry after {:?}",
+                        attempt,
+                        e,
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn fetch_uuid(&self) -> Result<String> {
+        let resp = self
+            .cli
+            .get(MY_SJTU_URL)
+            .timeout(self.request_timeout())
+            .send()
+            .await?
+            .error_for_status()?;
         let body = resp.text().await?;
-        // let document = Document::from(body.as_str());
         let re = Regex::new(
             r#"uuid=([0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12})"#,
         )
@@ -63,16 +523,22 @@ impl Client {
 
         if let Some(captures) = re.captures(&body) {
             if let Some(uuid) = captures.get(1) {
-                return Ok(Some(uuid.as_str().to_owned()));
+                return Ok(uuid.as_str().to_owned());
             }
         }
 
-        Ok(None)
+        tracing::debug!("no uuid found in page: {}", page_head(&body));
+        Err(AppError::UuidNotFound)
     }
 
     pub async fn express_login(&self, uuid: &str) -> Result<Option<String>> {
         let url = format!("{}?uuid={}", EXPRESS_LOGIN_URL, uuid);
-        self.cli.get(&url).send().await?.error_for_status()?;
+        self.cli
+            .get(&url)
+            .timeout(self.request_timeout())
+            .send()
+            .await?
+            .error_for_status()?;
         let domain = Url::parse(AUTH_URL).unwrap();
         if let Some(value) = self.jar.cookies(&domain) {
             if let Ok(cookies) = value.to_str() {
@@ -80,6 +546,7 @@ impl Client {
                 for kv in kvs {
                     let kv: Vec<_> = kv.trim().split('=').collect();
                     if kv.len() >= 2 && kv[0] == "JAAuthCookie" {
+                        self.mark_logged_in();
                         return Ok(Some(kv[1].to_owned()));
                     }
                 }
@@ -91,7 +558,7 @@ impl Client {
     pub async fn login_video_website(&self, cookie: &str) -> Result<Option<String>> {
         self.jar
             .add_cookie_str(cookie, &Url::parse(AUTH_URL).unwrap());
-        let response = self.get_request(VIDEO_LOGIN_URL, None::<&str>).await?;
+        let response = self.get_login_page(VIDEO_LOGIN_URL).await?;
         let url = response.url();
         if let Some(domain) = url.domain() {
             if domain == "jaccount.sjtu.edu.cn" {
@@ -100,6 +567,7 @@ impl Client {
         }
         if let Some(cookies) = self.jar.cookies(&Url::parse(VIDEO_BASE_URL).unwrap()) {
             if let Ok(cookies) = cookies.to_str() {
+                self.mark_logged_in();
                 return Ok(Some(cookies.to_owned()));
             }
         }
@@ -109,39 +577,92 @@ impl Client {
     pub async fn login_canvas_website(&self, cookie: &str) -> Result<()> {
         self.jar
             .add_cookie_str(cookie, &Url::parse(AUTH_URL).unwrap());
-        let response = self.get_request(CANVAS_LOGIN_URL, None::<&str>).await?;
+        let response = self.get_login_page(CANVAS_LOGIN_URL).await?;
         let url = response.url();
         if let Some(domain) = url.domain() {
             if domain == "jaccount.sjtu.edu.cn" {
                 return Err(AppError::LoginError);
             }
         }
+        self.mark_logged_in();
         Ok(())
     }
 
-    pub async fn get_page_items<T: Serialize + DeserializeOwned>(
+    // A page that fails with a timeout, 5xx or connection error is retried on its own,
+    // so one flaky page doesn't fail the whole listing
+    async fn get_item_page<T: Serialize + DeserializeOwned>(
         &self,
         url: &str,
-    ) -> Result<Vec<T>> {
-        let mut page_index = 1;
-        let mut all_items = vec![];
-
+        page_index: i64,
+    ) -> Result<ItemPage<T>> {
+        let paged_url = format!(
+            "{}pageSize={}&pageIndex={}",
+            url, VIDEO_PAGE_SIZE, page_index
+        );
+        let mut attempt = 1;
+        let mut delay = Duration::from_millis(VIDEO_PAGE_RETRY_BASE_DELAY_MS);
         loop {
-            let paged_url = format!("{}pageSize=100&pageIndex={}", url, page_index);
-            let item_page = self
+            let result = self
                 .get_json_with_cookie::<_, ItemPage<T>>(&paged_url, None::<&str>)
-                .await?;
-            all_items.extend(item_page.list);
-            let page = &item_page.page;
-            if page.page_count == 0 || page.page_next == page_index {
-                break;
+                .await
+                .map_err(AppError::classify);
+            match result {
+                Err(e)
+                    if attempt < VIDEO_PAGE_MAX_ATTEMPTS
+                        && (e.is_retryable() || matches!(e, AppError::Network(_))) =>
+                {
+                    tracing::warn!(
+                        "get page {} of {} failed at attempt {}: {}, retry after {:?}",
+                        page_index,
+                        url,
+                        attempt,
+                        e,
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+                result => return result,
             }
-            page_index += 1;
         }
-        Ok(all_items)
     }
 
-    pub async fn get_subjects(&self) -> Result<Vec<Subject>> {
+    // The first page tells how many pages there are, the rest are fetched
+    // VIDEO_PAGE_CONCURRENCY at a time and merged back in page order
+    pub async fn get_page_items<T>(self: Arc<Self>, url: &str) -> Result<Vec<T>>
+    where
+        T: Serialize + DeserializeOwned + Send + 'static,
+    {
+        let first_page = self.get_item_page::<T>(url, 1).await?;
+        let page_count = first_page.page.page_count.max(1) as usize;
+
+        let semaphore = Arc::new(Semaphore::new(VIDEO_PAGE_CONCURRENCY));
+        let mut tasks = JoinSet::new();
+        let mut results = Vec::with_capacity(page_count);
+        results.push(first_page.list);
+        for page_index in 2..=page_count {
+            results.push(vec![]);
+            let url = url.to_owned();
+            let cloned_self = self.clone();
+            let semaphore = semaphore.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let item_page = cloned_self
+                    .get_item_page::<T>(&url, page_index as i64)
+                    .await?;
+                Ok::<_, AppError>((page_index, item_page.list))
+            });
+        }
+
+        while let Some(res) = tasks.join_next().await {
+            let (page_index, items) = res??;
+            results[page_index - 1] = items;
+        }
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    pub async fn get_subjects(self: Arc<Self>) -> Result<Vec<Subject>> {
         let url = format!(
             "{}/system/course/subject/findSubjectVodList?",
             VIDEO_BASE_URL
@@ -149,21 +670,59 @@ impl Client {
         self.get_page_items(&url).await
     }
 
+    pub fn watch_history(self: Arc<Self>) -> impl Stream<Item = Result<WatchRecord>> {
+        let url = format!(
+            "{}/system/resource/vodVideo/findVodPlayHistoryList?",
+            VIDEO_BASE_URL
+        );
+        paged_stream(self, url)
+    }
+
+    // The history api has no date parameter, so records are filtered locally while the
+    // pages come in instead of holding the whole history
+    pub async fn get_video_platform_history_by_date(
+        self: Arc<Self>,
+        date: NaiveDate,
+    ) -> Result<Vec<WatchRecord>> {
+        self.watch_history()
+            .try_filter(|record| future::ready(watched_on(record, date)))
+            .try_collect()
+            .await
+    }
+
+    async fn get_video_tool_id(&self, course_id: i64, token: &str) -> Result<i64> {
+        let url = format!(
+            "{}/api/v1/courses/{}/external_tools?include_parents=true",
+            self.base_url.read().await,
+            course_id
+        );
+        let tools: Vec<ExternalTool> = self.list_items(&url, token).await?;
+        find_video_tool(&tools).ok_or(AppError::VideoToolNotFound(course_id))
+    }
+
     async fn get_form_data_for_canvas_course_id(
         &self,
         course_id: i64,
+        token: &str,
     ) -> Result<Option<HashMap<String, String>>> {
+        let tool_id = self.get_video_tool_id(course_id, token).await?;
         let url = format!(
-            "https://oc.sjtu.edu.cn/courses/{}/external_tools/8199",
-            course_id
+            "{}/courses/{}/external_tools/{}",
+            self.base_url.read().await,
+            course_id,
+            tool_id
         );
-        let response = self.cli.get(&url).send().await?;
+        self.ensure_logged_in()?;
+        let response = self
+            .cli
+            .get(&url)
+            .timeout(self.request_timeout())
+            .send()
+            .await?;
         let body = response.text().await?;
         let document = Document::from(body.as_str());
         // tracing::info!("resp: {:?}", body);
-        let form = document
-            .find(Attr("action", "https://courses.sjtu.edu.cn/lti/launch"))
-            .next();
+        let form = document.find(Attr("action", VIDEO_LTI_LAUNCH_URL)).next();
 
         if form.is_none() {
             return Ok(None);
@@ -181,22 +740,22 @@ impl Client {
         Ok(Some(data))
     }
 
-    async fn to_canvas_course_id(&self, course_id: i64) -> Result<Option<String>> {
-        let data = match self.get_form_data_for_canvas_course_id(course_id).await? {
+    // Launches the video lti tool of the course, which also sets up the session cookies
+    // that the video list api relies on
+    pub async fn to_canvas_course_id(&self, course_id: i64, token: &str) -> Result<Option<String>> {
+        let data = match self
+            .get_form_data_for_canvas_course_id(course_id, token)
+            .await?
+        {
             Some(data) => data,
             None => return Ok(None),
         };
 
         // cancel redirection
-        let client = reqwest::Client::builder()
+        let client = Self::http_client_builder(&self.jar, &self.timeouts, self.proxy()?.as_ref())
             .redirect(Policy::none())
-            .cookie_provider(self.jar.clone())
             .build()?;
-        let resp = client
-            .post("https://courses.sjtu.edu.cn/lti/launch")
-            .form(&data)
-            .send()
-            .await?;
+        let resp = client.post(VIDEO_LTI_LAUNCH_URL).form(&data).send().await?;
 
         let location_header = resp.headers().get("location");
         if location_header.is_none() {
@@ -207,62 +766,114 @@ impl Client {
         Ok(canvas_course_id.map(|id| id.to_owned()))
     }
 
-    pub async fn get_canvas_videos(&self, course_id: i64) -> Result<Vec<CanvasVideo>> {
-        let canvas_course_id = self.to_canvas_course_id(course_id).await?;
-        if canvas_course_id.is_none() {
-            return Ok(vec![]);
+    pub async fn list_canvas_videos(&self, canvas_course_id: &str) -> Result<Vec<CanvasVideo>> {
+        let mut videos = vec![];
+        let mut total = 0;
+        let mut page_index = 1;
+        loop {
+            let resp = self
+                .get_canvas_video_page(canvas_course_id, page_index)
+                .await?;
+            let next_page = next_canvas_video_page(&resp.body, page_index);
+            if let Some(body) = resp.body {
+                total = body.page.row_count;
+                videos.extend(body.list);
+            }
+            match next_page {
+                Some(next_page) => page_index = next_page,
+                None => break,
+            }
         }
-        // tracing::info!("canvas_course_id: {:?}", canvas_course_id);
-        let canvas_course_id = canvas_course_id.unwrap();
+        if (videos.len() as i64) < total {
+            tracing::warn!(
+                "only {} of {} videos listed for canvas course {}",
+                videos.len(),
+                total,
+                canvas_course_id
+            );
+        }
+        Ok(videos)
+    }
+
+    async fn get_canvas_video_page(
+        &self,
+        canvas_course_id: &str,
+        page_index: i64,
+    ) -> Result<CanvasVideoResponse> {
         let url = "https://courses.sjtu.edu.cn/lti/vodVideo/findVodVideoList";
+        let page_index = page_index.to_string();
+        let page_size = CANVAS_VIDEO_PAGE_SIZE.to_string();
         let mut data = HashMap::new();
-        data.insert("pageIndex", "1");
-        data.insert("pageSize", "1000");
-        data.insert("canvasCourseId", canvas_course_id.as_str());
+        data.insert("pageIndex", page_index.as_str());
+        data.insert("pageSize", page_size.as_str());
+        data.insert("canvasCourseId", canvas_course_id);
 
         let resp = self
             .post_form(url, None::<&str>, &data)
             .await?
-            .error_for_status()?;
+            .error_for_status()
+            .map_err(|e| AppError::from(e).classify())?;
         let body = resp.bytes().await?;
-        // tracing::info!("body: {}", String::from_utf8_lossy(&body.to_vec()));
-        let resp = utils::parse_json::<CanvasVideoResponse>(&body)?;
-        let videos = match resp.body {
-            Some(body) => body.list,
-            None => vec![],
-        };
-        Ok(videos)
+        utils::parse_json::<CanvasVideoResponse>(&body)
     }
 
-    pub async fn get_oauth_consumer_key(&self) -> Result<Option<String>> {
-        let resp = self.get_request(VIDEO_OAUTH_KEY_URL, None::<&str>).await?;
-        let body = resp.text().await?;
+    // The platform exposes its version through a `<meta name="version">` tag on the homepage,
+    // older deployments only carry it in the query string of bundled assets (`app.js?v=2.1.3`)
+    pub async fn detect_video_platform_api_version(&self) -> Result<ApiVersion> {
+        let body = self
+            .get_request(VIDEO_BASE_URL, None::<&str>)
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
         let document = Document::from(body.as_str());
-
-        let Some(meta) = document
+        let meta_version = document
             .find(Name("meta"))
-            .find(|n: &Node| n.attr("id").unwrap_or_default() == "xForSecName")
-        else {
-            return Ok(None);
-        };
-        let Some(v) = meta.attr("vaule") else {
-            return Ok(None);
-        };
-        let bytes = &STANDARD.decode(v)?;
-        Ok(Some(format!("{}", String::from_utf8_lossy(bytes))))
+            .find(|n: &Node| n.attr("name").unwrap_or_default() == "version")
+            .and_then(|n| n.attr("content"))
+            .and_then(ApiVersion::parse);
+        if let Some(version) = meta_version {
+            return Ok(version);
+        }
+
+        let re = Regex::new(r#"[?&]v(?:ersion)?=v?(\d+(?:\.\d+){0,2})\b"#).unwrap();
+        re.captures(&body)
+            .and_then(|captures| ApiVersion::parse(captures.get(1)?.as_str()))
+            .ok_or(AppError::ApiVersionNotFound)
     }
 
-    pub async fn get_video_course(
-        &self,
+    pub async fn get_oauth_consumer_key(&self) -> Result<String> {
+        let resp = self.get_request(VIDEO_OAUTH_KEY_URL, None::<&str>).await?;
+        let body = resp.text().await?;
+        parse_oauth_consumer_key(&body)
+    }
+
+    pub async fn get_video_courses(
+        self: Arc<Self>,
         subject_id: i64,
         tecl_id: i64,
-    ) -> Result<Option<VideoCourse>> {
+    ) -> Result<Vec<VideoCourse>> {
         let url = format!(
             "{}/system/resource/vodVideo/getCourseListBySubject?orderField=courTimes&subjectId={}&teclId={}&",
             VIDEO_BASE_URL, subject_id, tecl_id
         );
-        let mut courses = self.get_page_items(&url).await?;
-        Ok(courses.remove(0))
+        let courses = self.get_page_items(&url).await?;
+        Ok(collect_video_courses(courses))
+    }
+
+    // The platform has no search api, so the recordings of every course entry are filtered here
+    pub async fn search_videos(
+        self: Arc<Self>,
+        subject_id: i64,
+        tecl_id: i64,
+        keyword: &str,
+    ) -> Result<Vec<Video>> {
+        let courses = self.get_video_courses(subject_id, tecl_id).await?;
+        Ok(courses
+            .into_iter()
+            .flat_map(|course| course.response_vo_list)
+            .filter(|video| keyword_matches(&video.vide_name, keyword))
+            .collect())
     }
 
     fn get_oauth_signature(
@@ -283,117 +894,1032 @@ impl Client {
         (since_the_epoch.as_nanos() / 1_000_000).to_string()
     }
 
-    async fn download_video_partial(&self, url: &str, begin: u64, end: u64) -> Result<Response> {
-        let range_value = HeaderValue::from_str(&format!("bytes={}-{}", begin, end)).unwrap();
-        let response = self
-            .cli
-            .get(url)
-            .header(RANGE, range_value)
-            .header(REFERER, "https://courses.sjtu.edu.cn")
-            .send()
-            .await?;
-        Ok(response)
+    pub async fn download_video_partial(
+        &self,
+        url: &str,
+        begin: u64,
+        end: u64,
+    ) -> Result<Response> {
+        self.ensure_logged_in()?;
+        for _ in 0..VIDEO_RELOGIN_MAX_ATTEMPTS {
+            let generation = self.video_session_generation.load(Ordering::SeqCst);
+            let range_value = HeaderValue::from_str(&format!("bytes={}-{}", begin, end)).unwrap();
+            let response = self
+                .cli
+                .get(url)
+                .timeout(self.download_timeout())
+                .header(RANGE, range_value)
+                .header(REFERER, "https://courses.sjtu.edu.cn")
+                .send()
+                .await?;
+            if !Self::is_login_page(&response) {
+                return Ok(response);
+            }
+            tracing::warn!(
+                "video session expired while downloading range {}-{}, re-login",
+                begin,
+                end
+            );
+            self.relogin_video_website(generation).await?;
+        }
+        Err(AppError::LoginError)
+    }
+
+    // An expired session redirects range requests to jaccount, which answers with an html page.
+    // The redirect itself is also checked in case it wasn't followed.
+    fn is_login_page(response: &Response) -> bool {
+        if response.url().domain() == Some("jaccount.sjtu.edu.cn") {
+            return true;
+        }
+        if response.status().is_redirection() {
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| Url::parse(value).ok());
+            if location.is_some_and(|url| url.domain() == Some("jaccount.sjtu.edu.cn")) {
+                return true;
+            }
+        }
+        response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/html"))
+    }
+
+    // Re-login with the JAAuthCookie already stored in the cookie jar. `generation` is the session
+    // the caller saw expire: if another task has re-logged in since, there's nothing left to do.
+    async fn relogin_video_website(&self, generation: u64) -> Result<()> {
+        let _guard = self.video_relogin_lock.lock().await;
+        if self.logged_out.load(Ordering::SeqCst) {
+            return Err(AppError::LoginError);
+        }
+        if self.video_session_generation.load(Ordering::SeqCst) != generation {
+            return Ok(());
+        }
+        let response = self.get_login_page(VIDEO_LOGIN_URL).await?;
+        if response.url().domain() == Some("jaccount.sjtu.edu.cn") {
+            tracing::error!("failed to re-login video website, JAAuthCookie expired");
+            return Err(AppError::LoginError);
+        }
+        self.video_session_generation.fetch_add(1, Ordering::SeqCst);
+        tracing::info!("Successfully re-logged in video website");
+        Ok(())
     }
 
     async fn get_download_video_size(&self, url: &str) -> Result<u64> {
-        let resp = self.download_video_partial(url, 0, 0).await?;
-        let range = resp.headers().get(CONTENT_RANGE);
-        if let Some(range) = range {
-            let range = range.to_str()?;
-            let parts: Vec<_> = range.split('/').collect();
-            let size = if parts.len() == 2 {
-                parts[1].parse().unwrap_or_default()
-            } else {
-                0
-            };
-            Ok(size)
+        let (size, _) = self.probe_download_video(url).await?;
+        Ok(size)
+    }
+
+    // Checked before any file is created, so a full disk doesn't leave a partial download behind
+    fn ensure_video_disk_space(save_path: &str, size: u64) -> Result<()> {
+        let save_dir = match Path::new(save_path).parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        utils::ensure_disk_space(&save_dir.to_string_lossy(), size + VIDEO_DISK_SPACE_MARGIN)
+    }
+
+    // Returns the video size (0 if unknown) and whether the server accepts range requests
+    async fn probe_download_video(&self, url: &str) -> Result<(u64, bool)> {
+        // a dead link must fail here, so the next candidate url gets a chance
+        let resp = self
+            .download_video_partial(url, 0, 0)
+            .await?
+            .error_for_status()
+            .map_err(|e| AppError::from(e).classify())?;
+        let accept_ranges = resp.status() == StatusCode::PARTIAL_CONTENT
+            || resp
+                .headers()
+                .get(ACCEPT_RANGES)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| value.eq_ignore_ascii_case("bytes"));
+        Ok((Self::content_range_size(&resp)?, accept_ranges))
+    }
+
+    // The total of a `Content-Range: bytes 0-0/<size>` header, 0 if there is none
+    fn content_range_size(resp: &Response) -> Result<u64> {
+        let Some(range) = resp.headers().get(CONTENT_RANGE) else {
+            return Ok(0);
+        };
+        let parts: Vec<_> = range.to_str()?.split('/').collect();
+        let size = if parts.len() == 2 {
+            parts[1].parse().unwrap_or_default()
         } else {
-            Ok(0)
-        }
+            0
+        };
+        Ok(size)
     }
 
-    pub async fn download_video<F: Fn(ProgressPayload) + Send + 'static>(
-        self: Arc<Self>,
+    // Checks the hd and sd links of the play with a one byte range request each, so a dead
+    // link shows up before the download starts rather than halfway through it
+    pub async fn probe_video_urls(
+        &self,
         video: &VideoPlayInfo,
+        quality: VideoQuality,
+    ) -> VideoUrlStatus {
+        let (hd, sd) = tokio::join!(
+            self.probe_video_url(&video.rtmp_url_hdv),
+            self.probe_video_url(&video.rtmp_url_sdv)
+        );
+        VideoUrlStatus::new(video.id, hd, sd, quality)
+    }
+
+    async fn probe_video_url(&self, url: &str) -> Option<VideoUrlProbe> {
+        if url.is_empty() {
+            return None;
+        }
+        let probe = match self.download_video_partial(url, 0, 0).await {
+            Ok(resp) => VideoUrlProbe {
+                url: url.to_owned(),
+                status: Some(resp.status().as_u16()),
+                available: resp.status().is_success(),
+                // a server ignoring the range answers 200 with the whole video
+                size: match Self::content_range_size(&resp).unwrap_or_default() {
+                    0 => resp.content_length().unwrap_or_default(),
+                    size => size,
+                },
+                error: None,
+            },
+            Err(e) => VideoUrlProbe {
+                url: url.to_owned(),
+                error: Some(e.to_string()),
+                ..Default::default()
+            },
+        };
+        Some(probe)
+    }
+
+    // Fallback for servers without range support: fetch the whole stream with one GET
+    // and write it to disk as it arrives. `total` is 0 when the size is unknown.
+    // A whole video can take longer than any request timeout, so the timeouts apply to
+    // getting the response and to each chunk instead, a stalled stream still fails.
+    async fn download_video_single_stream<F: Fn(ProgressPayload)>(
+        &self,
+        video_id: i64,
+        url: &str,
         save_path: &str,
         progress_handler: F,
     ) -> Result<()> {
-        let output_file = Arc::new(Mutex::new(File::create(save_path)?));
-        let url = &video.rtmp_url_hdv;
-        let size = self.get_download_video_size(url).await?;
-        let payload = ProgressPayload {
-            uuid: video.id.to_string(),
+        self.ensure_logged_in()?;
+        let _permit = self.acquire_transfer().await;
+        let request = self
+            .cli
+            .get(url)
+            .header(REFERER, "https://courses.sjtu.edu.cn")
+            .send();
+        let mut response = tokio::time::timeout(self.request_timeout(), request)
+            .await
+            .map_err(|_| AppError::NetworkTimeout)??
+            .error_for_status()?;
+        let size = response.content_length().unwrap_or_default();
+        Self::ensure_video_disk_space(save_path, size)?;
+        let mut payload = ProgressPayload {
+            uuid: video_id.to_string(),
             processed: 0,
             total: size,
+            ..Default::default()
         };
         progress_handler(payload.clone());
-        if size == 0 {
-            tracing::warn!(
-                "try to download video as {}, but size is 0, can't download",
-                save_path
-            );
-            return Err(AppError::VideoDownloadError(save_path.to_owned()));
+
+        // like the ranged download, only a finished stream gets the real name
+        let part_path = format!("{}.part", save_path);
+        let mut output_file = File::create(&part_path)?;
+        while let Some(chunk) = tokio::time::timeout(self.download_timeout(), response.chunk())
+            .await
+            .map_err(|_| AppError::NetworkTimeout)??
+        {
+            output_file.write_all(&chunk)?;
+            payload.processed += chunk.len() as u64;
+            progress_handler(payload.clone());
         }
+        output_file.flush()?;
+        drop(output_file);
 
-        let progress_handler = Arc::new(Mutex::new(progress_handler));
-        let payload = Arc::new(Mutex::new(payload));
+        if size != 0 && payload.processed != size {
+            return Err(AppError::VideoDownloadError {
+                save_path: save_path.to_owned(),
+                bytes_written: payload.processed,
+                expected_bytes: size,
+            });
+        }
+        fs::rename(&part_path, save_path)?;
+        Self::save_video_checksum(save_path)?;
+        tracing::info!(
+            "Successfully downloaded video to {} in a single stream",
+            save_path
+        );
+        Ok(())
+    }
 
-        let nproc = num_cpus::get();
-        tracing::info!("nproc: {}", nproc);
-        let chunk_size = size / nproc as u64;
-        let mut tasks = JoinSet::new();
-        for i in 0..nproc {
-            let begin = i as u64 * chunk_size;
-            let end = if i == nproc - 1 {
-                size - 1
-            } else {
-                (i + 1) as u64 * chunk_size - 1
-            };
-            let self_clone = self.clone();
-            let save_path = save_path.to_owned();
-            let output_file = output_file.clone();
-            let url = url.clone();
-            let payload = payload.clone();
-            let progress_handler = progress_handler.clone();
-            tasks.spawn(async move {
-                let mut current_begin = begin;
-                while current_begin < end {
-                    let response = self_clone
-                        .download_video_partial(
-                            &url,
-                            current_begin,
-                            current_begin + VIDEO_CHUNK_SIZE,
-                        )
-                        .await?;
-                    let status = response.status();
-                    if !(status == StatusCode::OK || status == StatusCode::PARTIAL_CONTENT) {
-                        tracing::error!("status not ok: {}", status);
-                        return Err(AppError::VideoDownloadError(save_path));
+    fn is_complete_file(save_path: &str, size: u64) -> bool {
+        let size_matched = match fs::metadata(save_path) {
+            Ok(metadata) => size != 0 && metadata.is_file() && metadata.len() == size,
+            Err(_) => false,
+        };
+        if !size_matched {
+            return false;
+        }
+        match fs::read_to_string(Self::video_checksum_path(save_path)) {
+            Ok(checksum) => {
+                Self::compute_file_md5(save_path).is_ok_and(|actual| actual == checksum.trim())
+            }
+            // downloaded by an older version, trust the size
+            Err(_) => true,
+        }
+    }
+
+    // Size of the stream `download_video` would fetch, 0 if it's unknown (e.g. HLS)
+    pub async fn get_video_play_size(
+        &self,
+        video: &VideoPlayInfo,
+        quality: VideoQuality,
+    ) -> Result<u64> {
+        let Some(url) = video.select_url(quality) else {
+            return Err(AppError::NoPlayableVideoUrl(video.id));
+        };
+        if self.is_hls_stream(url).await? {
+            return Ok(0);
+        }
+        self.get_download_video_size(url).await
+    }
+
+    pub async fn get_course_videos_size(
+        &self,
+        videos: &[CanvasVideo],
+        quality: VideoQuality,
+    ) -> Result<u64> {
+        let mut size = 0;
+        for video in videos {
+            let info = self.get_canvas_video_info(&video.video_id).await?;
+            for play in &info.video_play_response_vo_list {
+                size += self.get_video_play_size(play, quality).await?;
+            }
+        }
+        Ok(size)
+    }
+
+    pub async fn is_already_downloaded(
+        &self,
+        video: &VideoPlayInfo,
+        save_path: &str,
+        quality: VideoQuality,
+    ) -> Result<bool> {
+        if fs::metadata(save_path).is_err() {
+            return Ok(false);
+        }
+        let Some(url) = video.select_url(quality) else {
+            return Ok(false);
+        };
+        let size = self.get_download_video_size(url).await?;
+        Ok(Self::is_complete_file(save_path, size))
+    }
+
+    // report a skipped file as fully processed so the UI can mark it complete
+    fn skip_download<F: Fn(ProgressPayload)>(
+        video_id: i64,
+        save_path: &str,
+        progress_handler: &F,
+    ) -> VideoDownloadResult {
+        let size = fs::metadata(save_path)
+            .map(|metadata| metadata.len())
+            .unwrap_or_default();
+        progress_handler(ProgressPayload {
+            uuid: video_id.to_string(),
+            processed: size,
+            total: size,
+            ..Default::default()
+        });
+        VideoDownloadResult::Skipped
+    }
+
+    fn download_result(
+        status: VideoDownloadResult,
+        save_path: &str,
+        source_url: Option<String>,
+        started: Instant,
+    ) -> DownloadResult {
+        let bytes_written = match status {
+            VideoDownloadResult::Downloaded => fs::metadata(save_path)
+                .map(|metadata| metadata.len())
+                .unwrap_or_default(),
+            VideoDownloadResult::Skipped => 0,
+        };
+        DownloadResult {
+            status,
+            path: save_path.to_owned(),
+            source_url,
+            bytes_written,
+            duration_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+
+    pub async fn download_video<F: Fn(ProgressPayload) + Send + 'static>(
+        self: Arc<Self>,
+        video: &VideoPlayInfo,
+        save_path: &str,
+        quality: VideoQuality,
+        conflict_policy: ConflictPolicy,
+        progress_handler: F,
+    ) -> Result<DownloadResult> {
+        let (progress_sender, mut progress_receiver) =
+            broadcast::channel(VIDEO_PROGRESS_CHANNEL_CAPACITY);
+        let mut throttle = self.progress_throttle();
+        let forwarder = tokio::spawn(async move {
+            loop {
+                match progress_receiver.recv().await {
+                    Ok(progress) => {
+                        if let Some(progress) = throttle.update(progress) {
+                            progress_handler(progress);
+                        }
                     }
-                    let bytes = response.bytes().await?;
-                    let read_bytes = bytes.len() as u64;
-                    current_begin += read_bytes;
-                    tracing::info!("read_bytes: {:?}", read_bytes);
-                    {
-                        let mut file = output_file.lock().await;
-                        write_file_at_offset(file.by_ref(), &bytes, begin)?;
-                        // release lock automatically after scope release
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("progress handler lagged, {} updates skipped", skipped)
                     }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            // whether the download finished or failed, its last progress is reported
+            if let Some(progress) = throttle.flush() {
+                progress_handler(progress);
+            }
+        });
+        let result = self
+            .download_video_with_progress(
+                video,
+                save_path,
+                quality,
+                conflict_policy,
+                progress_sender,
+            )
+            .await;
+        // all senders are dropped by now, so the forwarder exits after draining the channel
+        forwarder.await?;
+        result
+    }
 
-                    let mut payload_guard = payload.lock().await;
-                    payload_guard.processed += read_bytes;
-                    progress_handler.lock().await(payload_guard.clone());
+    // Same as `download_video`, but publishes progress to a broadcast channel so that
+    // several subscribers (event emitter, logger, speed meter) can consume it independently.
+    pub async fn download_video_with_progress(
+        self: Arc<Self>,
+        video: &VideoPlayInfo,
+        save_path: &str,
+        quality: VideoQuality,
+        conflict_policy: ConflictPolicy,
+        progress_sender: broadcast::Sender<ProgressPayload>,
+    ) -> Result<DownloadResult> {
+        let started = Instant::now();
+        let progress_handler = {
+            let progress_sender = progress_sender.clone();
+            move |progress| {
+                // no subscriber is not an error, nobody is interested in the progress
+                let _ = progress_sender.send(progress);
+            }
+        };
+        let mut candidates = video.candidate_urls(quality).into_iter();
+        let (mut url, mut probe) = self
+            .probe_video_candidates(video.id, &mut candidates)
+            .await?;
+        let exists = Path::new(save_path).exists();
+        if let Some((size, _)) = probe {
+            if Self::is_complete_file(save_path, size) {
+                tracing::info!("video already downloaded to {}, skip", save_path);
+                let status = Self::skip_download(video.id, save_path, &progress_handler);
+                return Ok(Self::download_result(status, save_path, None, started));
+            }
+        }
+        let renamed_path;
+        let save_path = match conflict_policy {
+            _ if !exists => save_path,
+            ConflictPolicy::Overwrite => save_path,
+            ConflictPolicy::Skip => {
+                tracing::info!("{} already exists, skip", save_path);
+                let status = Self::skip_download(video.id, save_path, &progress_handler);
+                return Ok(Self::download_result(status, save_path, None, started));
+            }
+            ConflictPolicy::Rename => {
+                renamed_path = utils::unique_path(Path::new(save_path));
+                tracing::info!("{} already exists, save as {:?}", save_path, renamed_path);
+                renamed_path.to_str().unwrap()
+            }
+        };
+        loop {
+            let result = self
+                .clone()
+                .download_video_from(video.id, &url, probe, save_path, &progress_sender)
+                .await;
+            let e = match result {
+                Ok(()) => break,
+                // another stream won't fit either
+                Err(e @ AppError::InsufficientDiskSpace { .. }) => return Err(e),
+                Err(e) => e,
+            };
+            // the other streams are separate encodings, the download starts over on the next one
+            // and its size becomes the new total
+            tracing::warn!("failed to download {} from {}: {}", save_path, url, e);
+            match self.probe_video_candidates(video.id, &mut candidates).await {
+                Ok((next_url, next_probe)) => {
+                    tracing::info!("fallback to {} for {}", next_url, save_path);
+                    (url, probe) = (next_url, next_probe);
+                }
+                Err(_) => return Err(e),
+            }
+        }
+        Ok(Self::download_result(
+            VideoDownloadResult::Downloaded,
+            save_path,
+            Some(url),
+            started,
+        ))
+    }
+
+    // Probes the candidate urls in turn until one answers. The probe is the size and range
+    // support of the video, or None for an HLS stream.
+    async fn probe_video_candidates<'a>(
+        &self,
+        video_id: i64,
+        candidates: &mut impl Iterator<Item = &'a str>,
+    ) -> Result<(String, Option<(u64, bool)>)> {
+        let mut last_error = AppError::NoPlayableVideoUrl(video_id);
+        for url in candidates {
+            let probe = match self.is_hls_stream(url).await {
+                Ok(true) => Ok(None),
+                Ok(false) => self.probe_download_video(url).await.map(Some),
+                Err(e) => Err(e),
+            };
+            match probe {
+                Ok(probe) => return Ok((url.to_owned(), probe)),
+                Err(e) => {
+                    tracing::warn!("failed to probe {}: {}", url, e);
+                    last_error = e;
                 }
-                Ok(())
+            }
+        }
+        Err(last_error)
+    }
+
+    // Downloads the video at `url` to `save_path`, with `probe` as returned by
+    // `probe_video_candidates`
+    async fn download_video_from(
+        self: Arc<Self>,
+        video_id: i64,
+        url: &str,
+        probe: Option<(u64, bool)>,
+        save_path: &str,
+        progress_sender: &broadcast::Sender<ProgressPayload>,
+    ) -> Result<()> {
+        let progress_handler = {
+            let progress_sender = progress_sender.clone();
+            move |progress| {
+                let _ = progress_sender.send(progress);
+            }
+        };
+        let Some((size, accept_ranges)) = probe else {
+            return self
+                .download_hls_video(video_id, url, save_path, progress_handler)
+                .await;
+        };
+        if size == 0 || !accept_ranges {
+            tracing::warn!(
+                "server doesn't support range requests for {}, fallback to a single stream",
+                save_path
+            );
+            return self
+                .download_video_single_stream(video_id, url, save_path, progress_handler)
+                .await;
+        }
+        Self::ensure_video_disk_space(save_path, size)?;
+        let payload = ProgressPayload {
+            uuid: video_id.to_string(),
+            processed: 0,
+            total: size,
+            ..Default::default()
+        };
+        progress_handler(payload.clone());
+
+        // download into a preallocated part file so an interrupted download never looks complete,
+        // every task writes through its own handle at its own offsets without locking
+        let part_path = format!("{}.part", save_path);
+        File::create(&part_path)?.set_len(size)?;
+        let payload = Arc::new(Mutex::new(payload));
+
+        let nproc = num_cpus::get().min(size as usize);
+        tracing::info!("nproc: {}", nproc);
+        let chunk_size = size / nproc as u64;
+        let mut tasks = JoinSet::new();
+        for i in 0..nproc {
+            let begin = i as u64 * chunk_size;
+            let end = if i == nproc - 1 {
+                size - 1
+            } else {
+                (i + 1) as u64 * chunk_size - 1
+            };
+            let self_clone = self.clone();
+            let save_path = save_path.to_owned();
+            let part_path = part_path.clone();
+            let url = url.to_owned();
+            let payload = payload.clone();
+            let progress_sender = progress_sender.clone();
+            tasks.spawn(async move {
+                let written = match OpenOptions::new().write(true).open(&part_path) {
+                    Ok(mut output_file) => {
+                        self_clone
+                            .download_video_range(
+                                &url,
+                                &save_path,
+                                begin,
+                                end,
+                                &mut output_file,
+                                &payload,
+                                &progress_sender,
+                            )
+                            .await
+                    }
+                    Err(e) => Err(e.into()),
+                };
+                (begin, end, written)
             });
         }
+        let mut short_ranges = vec![];
         while let Some(result) = tasks.join_next().await {
-            result??;
+            let (begin, end, written) = result?;
+            let written = written?;
+            if begin + written <= end {
+                short_ranges.push((begin + written, end));
+            }
         }
-        tracing::info!("Successfully downloaded video to {}", save_path);
+
+        let mut output_file = OpenOptions::new().write(true).open(&part_path)?;
+        for attempt in 1..=VIDEO_RANGE_MAX_RETRIES {
+            if short_ranges.is_empty() {
+                break;
+            }
+            tracing::warn!(
+                "{} short ranges in {}, refetch attempt {}",
+                short_ranges.len(),
+                save_path,
+                attempt
+            );
+            let mut still_short = vec![];
+            for (begin, end) in short_ranges {
+                let written = self
+                    .download_video_range(
+                        url,
+                        save_path,
+                        begin,
+                        end,
+                        &mut output_file,
+                        &payload,
+                        &progress_sender,
+                    )
+                    .await?;
+                if begin + written <= end {
+                    still_short.push((begin + written, end));
+                }
+            }
+            short_ranges = still_short;
+        }
+        if !short_ranges.is_empty() {
+            let ranges: Vec<_> = short_ranges
+                .iter()
+                .map(|(begin, end)| format!("{}-{}", begin, end))
+                .collect();
+            tracing::error!("short ranges in {}: {}", part_path, ranges.join(", "));
+            let missing: u64 = short_ranges
+                .iter()
+                .map(|(begin, end)| end - begin + 1)
+                .sum();
+            return Err(AppError::VideoDownloadError {
+                save_path: save_path.to_owned(),
+                bytes_written: size - missing,
+                expected_bytes: size,
+            });
+        }
+
+        drop(output_file);
+
+        let written_size = fs::metadata(&part_path)?.len();
+        if written_size != size {
+            return Err(AppError::VideoDownloadError {
+                save_path: save_path.to_owned(),
+                bytes_written: written_size,
+                expected_bytes: size,
+            });
+        }
+        fs::rename(&part_path, save_path)?;
+        Self::save_video_checksum(save_path)?;
+        tracing::info!(
+            "Successfully downloaded video to {} from {}",
+            save_path,
+            url
+        );
+        Ok(())
+    }
+
+    // Fetches bytes [begin, end] into `output_file` and returns how many bytes were written.
+    // It stops early when the server returns an empty body, the caller checks for short ranges.
+    #[allow(clippy::too_many_arguments)]
+    async fn download_video_range(
+        &self,
+        url: &str,
+        save_path: &str,
+        begin: u64,
+        end: u64,
+        output_file: &mut File,
+        payload: &Mutex<ProgressPayload>,
+        progress_sender: &broadcast::Sender<ProgressPayload>,
+    ) -> Result<u64> {
+        let mut current_begin = begin;
+        while current_begin <= end {
+            let current_end = end.min(current_begin + VIDEO_CHUNK_SIZE - 1);
+            let permit = self.acquire_transfer().await;
+            let response = self
+                .download_video_partial(url, current_begin, current_end)
+                .await?;
+            let status = response.status();
+            if !(status == StatusCode::OK || status == StatusCode::PARTIAL_CONTENT) {
+                tracing::error!("status not ok: {}", status);
+                let payload = payload.lock().await;
+                return Err(AppError::VideoDownloadError {
+                    save_path: save_path.to_owned(),
+                    bytes_written: payload.processed,
+                    expected_bytes: payload.total,
+                });
+            }
+            let bytes = response.bytes().await?;
+            drop(permit);
+            // never write past the requested range, a server ignoring `Range` sends everything
+            let bytes = &bytes[..bytes.len().min((current_end - current_begin + 1) as usize)];
+            let read_bytes = bytes.len() as u64;
+            tracing::info!("read_bytes: {:?}", read_bytes);
+            if read_bytes == 0 {
+                break;
+            }
+            utils::write_file_at(output_file, bytes, current_begin)?;
+            current_begin += read_bytes;
+
+            let mut payload_guard = payload.lock().await;
+            payload_guard.processed += read_bytes;
+            let _ = progress_sender.send(payload_guard.clone());
+        }
+        Ok(current_begin - begin)
+    }
+
+    // Refill the holes of a downloaded (or interrupted `.part`) video instead of downloading
+    // it again. A repaired part file is moved to `save_path` like a finished download.
+    pub async fn repair_video(
+        &self,
+        video: &VideoPlayInfo,
+        save_path: &str,
+        quality: VideoQuality,
+    ) -> Result<VideoRepairReport> {
+        let Some(url) = video.select_url(quality) else {
+            return Err(AppError::NoPlayableVideoUrl(video.id));
+        };
+        if self.is_hls_stream(url).await? {
+            return Err(AppError::UnsupportedOperation(
+                "HLS recordings are downloaded as a whole".to_owned(),
+            ));
+        }
+        let (size, accept_ranges) = self.probe_download_video(url).await?;
+        if size == 0 || !accept_ranges {
+            return Err(AppError::UnsupportedOperation(
+                "server doesn't support range requests".to_owned(),
+            ));
+        }
+        let part_path = format!("{}.part", save_path);
+        let repair_path = if Path::new(save_path).exists() {
+            save_path
+        } else if Path::new(&part_path).exists() {
+            part_path.as_str()
+        } else {
+            return Err(io::Error::from(io::ErrorKind::NotFound).into());
+        };
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(repair_path)?;
+        // a truncated tail reads as zeros once the file has its full length
+        file.set_len(size)?;
+        let ranges = find_zero_ranges(File::open(repair_path)?, size, VIDEO_REPAIR_BLOCK_SIZE)?;
+        tracing::info!("{} holes found in {}", ranges.len(), repair_path);
+
+        let payload = Mutex::new(ProgressPayload {
+            uuid: video.id.to_string(),
+            total: ranges.iter().map(|(begin, end)| end - begin + 1).sum(),
+            ..Default::default()
+        });
+        // nobody listens, the repair is reported as a whole
+        let (progress_sender, _) = broadcast::channel(1);
+        for &(begin, end) in &ranges {
+            let written = self
+                .download_video_range(
+                    url,
+                    repair_path,
+                    begin,
+                    end,
+                    &mut file,
+                    &payload,
+                    &progress_sender,
+                )
+                .await?;
+            if begin + written <= end {
+                tracing::error!(
+                    "short range in {}: {}-{}",
+                    repair_path,
+                    begin + written,
+                    end
+                );
+                // the holes not refilled yet are all that's missing
+                let payload = payload.lock().await;
+                return Err(AppError::VideoDownloadError {
+                    save_path: repair_path.to_owned(),
+                    bytes_written: size - (payload.total - payload.processed),
+                    expected_bytes: size,
+                });
+            }
+        }
+        drop(file);
+
+        let written_size = fs::metadata(repair_path)?.len();
+        if written_size != size {
+            return Err(AppError::VideoDownloadError {
+                save_path: repair_path.to_owned(),
+                bytes_written: written_size,
+                expected_bytes: size,
+            });
+        }
+        if repair_path != save_path {
+            fs::rename(repair_path, save_path)?;
+        }
+        Self::save_video_checksum(save_path)?;
+        Ok(VideoRepairReport {
+            save_path: save_path.to_owned(),
+            repaired: ranges,
+        })
+    }
+
+    fn video_checksum_path(save_path: &str) -> String {
+        format!("{}.md5", save_path)
+    }
+
+    fn compute_file_md5(path: &str) -> Result<String> {
+        let mut file = File::open(path)?;
+        let mut hasher = Md5::new();
+        io::copy(&mut file, &mut hasher)?;
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    // Stored next to the video so later runs can detect a corrupted file
+    fn save_video_checksum(save_path: &str) -> Result<()> {
+        let checksum = Self::compute_file_md5(save_path)?;
+        fs::write(Self::video_checksum_path(save_path), checksum)?;
+        Ok(())
+    }
+
+    pub fn video_metadata_path(save_path: &str) -> String {
+        format!("{}.info.json", save_path)
+    }
+
+    pub fn read_video_metadata(save_path: &str) -> Option<VideoMetadata> {
+        let content = fs::read(Self::video_metadata_path(save_path)).ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    // Describes where a downloaded video came from, so it can still be told apart once archived.
+    // Written to a temporary file first so an interrupted write never leaves a broken sidecar.
+    // `source_url` is the link of the play the video was actually downloaded from
+    pub fn save_video_metadata(
+        save_path: &str,
+        video: &VideoInfo,
+        play: &VideoPlayInfo,
+        source_url: &str,
+        clip: Option<VideoClipRange>,
+    ) -> Result<()> {
+        let metadata = VideoMetadata {
+            course_name: video.cour_name.clone(),
+            subject: video.subj_name.clone(),
+            teacher: video.user_name.clone(),
+            recording_time: video.vide_begin_time.clone(),
+            video_id: video.id,
+            play_id: play.id,
+            quality: play.url_quality(source_url),
+            source_url: source_url.to_owned(),
+            size: fs::metadata(save_path)?.len(),
+            downloaded_at: utils::unix_timestamp(),
+            clip,
+        };
+        let metadata_path = Self::video_metadata_path(save_path);
+        let tmp_path = format!("{}.tmp", metadata_path);
+        fs::write(&tmp_path, serde_json::to_vec_pretty(&metadata)?)?;
+        fs::rename(&tmp_path, &metadata_path)?;
         Ok(())
     }
 
+    // Download the part of a recording between `start` and `end` seconds. Only HLS streams can
+    // be cut without decoding, for mp4 a full download would be needed so it's refused.
+    pub async fn download_video_clip<F: Fn(ProgressPayload)>(
+        &self,
+        video: &VideoPlayInfo,
+        save_path: &str,
+        quality: VideoQuality,
+        start: f64,
+        end: f64,
+        progress_handler: F,
+    ) -> Result<VideoClipRange> {
+        if !(start >= 0.0 && start < end) {
+            return Err(AppError::InvalidClipRange(start, end));
+        }
+        let Some(url) = video.select_url(quality) else {
+            return Err(AppError::NoPlayableVideoUrl(video.id));
+        };
+        if !self.is_hls_stream(url).await? {
+            return Err(AppError::UnsupportedOperation(
+                "clip download is only available for HLS streams".to_owned(),
+            ));
+        }
+        self.download_hls_clip(video.id, url, save_path, start, end, progress_handler)
+            .await
+    }
+
+    // keep the same naming as the frontend: main stream first, then screen recordings
+    pub fn video_play_save_name(video_name: &str, index: usize) -> String {
+        let part = if index == 0 { "" } else { "_录屏" };
+        let suffix = if index > 2 {
+            format!("_{}.mp4", index)
+        } else {
+            ".mp4".to_owned()
+        };
+        format!("{}{}{}", utils::sanitize_filename(video_name), part, suffix)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn download_canvas_video<F: Fn(ProgressPayload) + Send + Sync + 'static>(
+        self: Arc<Self>,
+        video: &CanvasVideo,
+        course_name: &str,
+        filename_template: &str,
+        save_dir: &str,
+        quality: VideoQuality,
+        conflict_policy: ConflictPolicy,
+        sidecars: VideoSidecars,
+        progress_handler: Arc<F>,
+    ) -> Result<VideoDownloadResult> {
+        let info = self.get_canvas_video_info(&video.video_id).await?;
+        tracing::info!("Downloading {}", info);
+        // only count the video as skipped when every play of it was skipped
+        let mut result = VideoDownloadResult::Skipped;
+        let mut main_stream_path = None;
+        for (index, play) in info.video_play_response_vo_list.iter().enumerate() {
+            let fields = VideoFilenameFields::from_canvas_video(video, course_name, index);
+            let video_name = utils::format_video_filename(filename_template, &fields);
+            let video_name = video_name.strip_suffix(".mp4").unwrap_or(&video_name);
+            let save_name = Self::video_play_save_name(video_name, index);
+            let mut save_path = Path::new(save_dir).join(save_name);
+            let mut conflict_policy = conflict_policy;
+            // the sidecar identifies the file, which is more reliable than comparing sizes
+            match Self::read_video_metadata(save_path.to_str().unwrap()) {
+                Some(metadata)
+                    if metadata.play_id == play.id
+                        && Self::is_complete_file(save_path.to_str().unwrap(), metadata.size) =>
+                {
+                    tracing::info!("video {:?} already downloaded, skip", save_path);
+                    Self::skip_download(play.id, save_path.to_str().unwrap(), &*progress_handler);
+                    continue;
+                }
+                Some(metadata) if metadata.play_id != play.id => {
+                    // another recording got the same name, keep both
+                    save_path = utils::unique_path(&save_path);
+                    conflict_policy = ConflictPolicy::Overwrite;
+                }
+                _ => {}
+            }
+            let save_path = save_path.to_str().unwrap();
+            let play_progress_handler = progress_handler.clone();
+            let play_result = self
+                .clone()
+                .download_video(play, save_path, quality, conflict_policy, move |progress| {
+                    play_progress_handler(progress)
+                })
+                .await?;
+            if play_result.status == VideoDownloadResult::Downloaded {
+                result = VideoDownloadResult::Downloaded;
+                // a renamed download gets its sidecars next to the file it was saved as
+                if sidecars.metadata {
+                    let source_url = play_result.source_url.as_deref().unwrap_or_default();
+                    Self::save_video_metadata(&play_result.path, &info, play, source_url, None)?;
+                }
+                if index == 0 {
+                    main_stream_path = Some(play_result.path);
+                }
+            }
+        }
+        // the transcript belongs to the recording, it goes next to the main stream
+        if let Some(main_stream_path) = main_stream_path {
+            if sidecars.srt || sidecars.vtt {
+                if let Err(e) = self
+                    .save_video_subtitles(info.id, &main_stream_path, sidecars.srt, sidecars.vtt)
+                    .await
+                {
+                    tracing::warn!("failed to save subtitles of {}: {}", main_stream_path, e);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    // Download every video of a course, `concurrency` videos at a time.
+    // A failed video doesn't abort the others, it's recorded in the returned report instead.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn download_course_videos<F, G>(
+        self: Arc<Self>,
+        course_id: i64,
+        videos: Vec<CanvasVideo>,
+        course_name: &str,
+        filename_template: &str,
+        save_dir: &str,
+        quality: VideoQuality,
+        conflict_policy: ConflictPolicy,
+        sidecars: VideoSidecars,
+        concurrency: usize,
+        progress_handler: F,
+        summary_handler: G,
+    ) -> Result<VideoDownloadReport>
+    where
+        F: Fn(ProgressPayload) + Send + Sync + 'static,
+        G: Fn(ProgressPayload),
+    {
+        let mut summary = ProgressPayload {
+            uuid: course_id.to_string(),
+            processed: 0,
+            total: videos.len() as u64,
+            ..Default::default()
+        };
+        summary_handler(summary.clone());
+
+        let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+        let progress_handler = Arc::new(progress_handler);
+        let mut tasks = JoinSet::new();
+        for video in videos {
+            let self_cloned = self.clone();
+            let semaphore = semaphore.clone();
+            let save_dir = save_dir.to_owned();
+            let course_name = course_name.to_owned();
+            let filename_template = filename_template.to_owned();
+            let progress_handler = progress_handler.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let result = self_cloned
+                    .download_canvas_video(
+                        &video,
+                        &course_name,
+                        &filename_template,
+                        &save_dir,
+                        quality,
+                        conflict_policy,
+                        sidecars,
+                        progress_handler,
+                    )
+                    .await;
+                (video.video_name, result)
+            });
+        }
+
+        let mut report = VideoDownloadReport::default();
+        while let Some(res) = tasks.join_next().await {
+            let (name, result) = res?;
+            match result {
+                Ok(VideoDownloadResult::Downloaded) => report.succeeded.push(name),
+                Ok(VideoDownloadResult::Skipped) => report.skipped.push(name),
+                Err(e) => {
+                    tracing::error!("failed to download video {}: {}", name, e);
+                    report.failed.push(VideoDownloadFailure {
+                        name,
+                        error: e.to_string(),
+                    });
+                }
+            }
+            summary.processed += 1;
+            summary_handler(summary.clone());
+        }
+        Ok(report)
+    }
+
+    // Re-fetch a video and compare it with a previously fetched copy, None if nothing changed
+    pub async fn get_video_info_diff(
+        &self,
+        video_id: i64,
+        oauth_consumer_key: &str,
+        cached: &VideoInfo,
+    ) -> Result<Option<Vec<String>>> {
+        let current = self.get_video_info(video_id, oauth_consumer_key).await?;
+        let changes = diff_video_info(cached, &current);
+        Ok((!changes.is_empty()).then_some(changes))
+    }
+
     pub async fn get_canvas_video_info(&self, video_id: &str) -> Result<VideoInfo> {
         let mut form_data = HashMap::new();
         let url = "https://courses.sjtu.edu.cn/lti/vodVideo/getVodVideoInfos";
@@ -409,6 +1935,118 @@ impl Client {
         Ok(resp.body)
     }
 
+    // Covers are served by the same CDN as the videos, which rejects requests without the
+    // session cookies and REFERER. Existing covers are kept, returns whether one was fetched.
+    async fn download_cover_from_url(&self, url: &str, save_path: &str) -> Result<bool> {
+        if Path::new(save_path).exists() {
+            return Ok(false);
+        }
+        self.ensure_logged_in()?;
+        let _permit = self.acquire_transfer().await;
+        let response = self
+            .cli
+            .get(url)
+            .timeout(self.download_timeout())
+            .header(REFERER, "https://courses.sjtu.edu.cn")
+            .send()
+            .await?;
+        if Self::is_login_page(&response) {
+            return Err(AppError::LoginError);
+        }
+        let bytes = response.error_for_status()?.bytes().await?;
+        let tmp_path = format!("{}.tmp", save_path);
+        fs::write(&tmp_path, &bytes)?;
+        fs::rename(&tmp_path, save_path)?;
+        Ok(true)
+    }
+
+    // Cover urls may be relative to the video website
+    fn cover_url(url: Option<&str>) -> Option<String> {
+        let url = url.filter(|url| !url.is_empty())?;
+        let url = Url::parse(VIDEO_BASE_URL).ok()?.join(url).ok()?;
+        Some(url.to_string())
+    }
+
+    fn cover_path(save_dir: &str, video_id: &str, url: &str) -> String {
+        let ext = Path::new(url.split('?').next().unwrap_or_default())
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("jpg");
+        format!("{}/{}.{}", save_dir, video_id, ext)
+    }
+
+    // Returns the path of the cover, or None if the video has none
+    pub async fn download_video_cover(
+        &self,
+        video_id: &str,
+        save_dir: &str,
+    ) -> Result<Option<String>> {
+        let info = self.get_canvas_video_info(video_id).await?;
+        let Some(url) = Self::cover_url(info.vide_img_url.as_deref()) else {
+            return Ok(None);
+        };
+        let save_path = Self::cover_path(save_dir, video_id, &url);
+        self.download_cover_from_url(&url, &save_path).await?;
+        Ok(Some(save_path))
+    }
+
+    // Fetch the covers of a video list into `cache_dir` ahead of time, `ready_handler`
+    // is called for every cover on disk as soon as it's there
+    pub async fn prefetch_thumbnails<F>(
+        self: Arc<Self>,
+        videos: Vec<CanvasVideo>,
+        cache_dir: &str,
+        ready_handler: F,
+    ) -> Result<HashMap<String, String>>
+    where
+        F: Fn(ThumbnailReady),
+    {
+        let semaphore = Arc::new(Semaphore::new(VIDEO_COVER_CONCURRENCY));
+        let mut tasks = JoinSet::new();
+        for video in videos {
+            let self_cloned = self.clone();
+            let semaphore = semaphore.clone();
+            let cache_dir = cache_dir.to_owned();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let result = match Self::cover_url(video.video_img_url.as_deref()) {
+                    Some(url) => {
+                        let save_path = Self::cover_path(&cache_dir, &video.video_id, &url);
+                        self_cloned
+                            .download_cover_from_url(&url, &save_path)
+                            .await
+                            .map(|_| Some(save_path))
+                    }
+                    // the list api may leave the cover out, ask for the details instead
+                    None => {
+                        self_cloned
+                            .download_video_cover(&video.video_id, &cache_dir)
+                            .await
+                    }
+                };
+                (video.video_id, result)
+            });
+        }
+
+        let mut covers = HashMap::new();
+        while let Some(res) = tasks.join_next().await {
+            match res? {
+                (video_id, Ok(Some(path))) => {
+                    ready_handler(ThumbnailReady {
+                        video_id: video_id.clone(),
+                        path: path.clone(),
+                    });
+                    covers.insert(video_id, path);
+                }
+                (_, Ok(None)) => (),
+                (video_id, Err(e)) => {
+                    tracing::warn!("failed to download cover of video {}: {}", video_id, e)
+                }
+            }
+        }
+        Ok(covers)
+    }
+
     pub async fn get_video_info(
         &self,
         video_id: i64,
@@ -429,9 +2067,11 @@ impl Client {
         form_data.insert(OAUTH_RANDOM_P1, OAUTH_RANDOM_P1_VAL);
         form_data.insert(OAUTH_RANDOM_P2, OAUTH_RANDOM_P2_VAL);
 
+        self.ensure_logged_in()?;
         let response = self
             .cli
             .post(VIDEO_INFO_URL)
+            .timeout(self.request_timeout())
             .form(&form_data)
             .header(ACCEPT, "application/json")
             .header("oauth-consumer-key", oauth_consumer_key)
@@ -445,6 +2085,40 @@ impl Client {
         let video = utils::parse_json(&bytes)?;
         Ok(video)
     }
+
+    // get_video_info with exponential back-off.
+    // Retryable: AppError::NetworkTimeout and AppError::ServerError (5xx).
+    // Returned immediately: AppError::Unauthorized, AppError::NotFound and any other error.
+    pub async fn get_video_info_with_retry(
+        &self,
+        video_id: i64,
+        oauth_consumer_key: &str,
+        max_attempts: u32,
+    ) -> Result<VideoInfo> {
+        let mut attempt = 1;
+        let mut delay = Duration::from_millis(VIDEO_INFO_RETRY_BASE_DELAY_MS);
+        loop {
+            let result = self
+                .get_video_info(video_id, oauth_consumer_key)
+                .await
+                .map_err(AppError::classify);
+            match result {
+                Err(e) if e.is_retryable() && attempt < max_attempts => {
+                    tracing::warn!(
+                        "get video info {} failed at attempt {}: {}, retry after {:?}",
+                        video_id,
+                        attempt,
+                        e,
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -457,8 +2131,6 @@ mod tests {
     async fn test_get_uuid() -> Result<()> {
         let cli = Client::new();
         let uuid = cli.get_uuid().await?;
-        assert!(uuid.is_some());
-        let uuid: String = uuid.unwrap();
         assert!(!uuid.is_empty());
         Ok(())
     }
@@ -474,7 +2146,13 @@ mod tests {
         };
         let cli_cloned = cli.clone();
         cli_cloned
-            .download_video(&video_info, save_path, |_| {})
+            .download_video(
+                &video_info,
+                save_path,
+                VideoQuality::Auto,
+                ConflictPolicy::Overwrite,
+                |_| {},
+            )
             .await?;
 
         // download original video
@@ -491,15 +2169,631 @@ mod tests {
     }
 
     #[test]
-    fn test_get_oauth_signature() -> Result<()> {
-        let cli = Client::new();
-        let oauth_nonce = "1709784720392";
-        let id = 3601811;
-        let oauth_consumer_key = "DADD2CA9923D5E31331C4B79B39A1E4B";
+    fn test_collect_video_courses() -> Result<()> {
+        let empty_page = r#"{
+            "page": {
+                "pageIndex": 1, "pageSize": 100, "pageCount": 0, "pageFirst": 1,
+                "pageLast": 0, "pageNext": 1, "pagePrev": 1, "pageShowBegin": 1,
+                "pageShowEnd": 0, "pageShowCount": 0, "rowCount": 0, "rowBegin": 0
+            },
+            "list": []
+        }"#;
+        let page: ItemPage<Option<VideoCourse>> = serde_json::from_str(empty_page)?;
+        assert!(collect_video_courses(page.list).is_empty());
+
+        let courses = collect_video_courses(vec![
+            Some(VideoCourse::default()),
+            None,
+            Some(VideoCourse::default()),
+        ]);
+        assert_eq!(2, courses.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_next_canvas_video_page() -> Result<()> {
+        let page = |index: i64, next: i64, videos: &str| {
+            format!(
+                r#"{{
+                    "code": 0, "desc": "ok",
+                    "body": {{
+                        "page": {{
+                            "pageIndex": {index}, "pageSize": 2, "pageCount": 2, "pageFirst": 1,
+                            "pageLast": 2, "pageNext": {next}, "pagePrev": 1, "pageShowBegin": 1,
+                            "pageShowEnd": 2, "pageShowCount": 2, "rowCount": 3, "rowBegin": 0
+                        }},
+                        "list": [{videos}]
+                    }}
+                }}"#
+            )
+        };
+        let video = |id: &str| {
+            format!(
+                r#"{{"videoId": "{id}", "userName": "", "videoName": "", "classroomName": "",
+                    "courseBeginTime": "", "courseEndTime": ""}}"#
+            )
+        };
+        let pages = [
+            page(1, 2, &format!("{},{}", video("1"), video("2"))),
+            page(2, 2, &video("3")),
+        ];
+
+        let mut videos = vec![];
+        let mut page_index = 1;
+        loop {
+            let resp: CanvasVideoResponse = serde_json::from_str(&pages[page_index as usize - 1])?;
+            let next_page = next_canvas_video_page(&resp.body, page_index);
+            videos.extend(resp.body.unwrap().list);
+            match next_page {
+                Some(next_page) => page_index = next_page,
+                None => break,
+            }
+        }
+        let ids: Vec<_> = videos.iter().map(|video| video.video_id.as_str()).collect();
+        assert_eq!(vec!["1", "2", "3"], ids);
+
+        // an empty page or a missing body ends the listing as well
+        let empty: CanvasVideoResponse = serde_json::from_str(&page(3, 4, ""))?;
+        assert_eq!(None, next_canvas_video_page(&empty.body, 3));
+        assert_eq!(None, next_canvas_video_page(&None, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_canvas_videos() -> Result<()> {
+        // an older response without duration and week
+        let old: CanvasVideo = serde_json::from_str(
+            r#"{"videoId": "1", "userName": "", "videoName": "b", "classroomName": "",
+                "courseBeginTime": "2024-03-01 08:00:00", "courseEndTime": "2024-03-01 09:40:00"}"#,
+        )?;
+        assert_eq!(None, old.video_play_time);
+        assert_eq!(Some(100 * 60), old.duration_secs());
+
+        let new = CanvasVideo {
+            video_id: "2".to_owned(),
+            video_name: "a".to_owned(),
+            course_begin_time: "2024-02-26 08:00:00".to_owned(),
+            video_play_time: Some(45 * 60),
+            course_week: Some(1),
+            ..Default::default()
+        };
+        let mut videos = vec![old, new];
+        let ids = |videos: &[CanvasVideo]| -> Vec<String> {
+            videos.iter().map(|video| video.video_id.clone()).collect()
+        };
+
+        sort_canvas_videos(&mut videos, CanvasVideoSortKey::Date, SortOrder::Asc);
+        assert_eq!(vec!["2", "1"], ids(&videos));
+        sort_canvas_videos(&mut videos, CanvasVideoSortKey::Date, SortOrder::Desc);
+        assert_eq!(vec!["1", "2"], ids(&videos));
+        sort_canvas_videos(&mut videos, CanvasVideoSortKey::Duration, SortOrder::Desc);
+        assert_eq!(vec!["1", "2"], ids(&videos));
+        sort_canvas_videos(&mut videos, CanvasVideoSortKey::Title, SortOrder::Asc);
+        assert_eq!(vec!["2", "1"], ids(&videos));
+        Ok(())
+    }
+
+    #[test]
+    fn test_video_search_hits() {
+        let subject = Subject {
+            subject_id: 1,
+            tecl_id: 2,
+            subject_name: "数值分析".to_owned(),
+            tecl_name: "张老师".to_owned(),
+            ..Default::default()
+        };
+        let video = |id: i64, name: &str| Video {
+            id,
+            vide_name: name.to_owned(),
+            user_name: "张老师".to_owned(),
+            ..Default::default()
+        };
+        let courses = vec![VideoCourse {
+            response_vo_list: vec![video(10, "第10周 周一"), video(11, "第11周 周一")],
+            ..Default::default()
+        }];
+
+        let hits = video_search_hits(&subject, &courses, "数值分析 第10周");
+        assert_eq!(1, hits.len());
         assert_eq!(
-            "2b499a5303048d6522118e79711c5ee0",
-            cli.get_oauth_signature(id, oauth_nonce, oauth_consumer_key)
+            (1, 2, 10),
+            (hits[0].subject_id, hits[0].tecl_id, hits[0].video_id)
         );
+        assert_eq!(2, video_search_hits(&subject, &courses, "张老师").len());
+        assert!(video_search_hits(&subject, &courses, "线性代数").is_empty());
+    }
+
+    #[test]
+    fn test_keyword_matches() {
+        assert!(keyword_matches("数据结构 第3讲 Linked List", "linked"));
+        assert!(keyword_matches("数据结构 第3讲 Linked List", "第3讲  LIST"));
+        assert!(keyword_matches("数据结构 第3讲 Linked List", ""));
+        assert!(!keyword_matches("数据结构 第3讲 Linked List", "第4讲"));
+        assert!(!keyword_matches("数据结构 第3讲 Linked List", "list tree"));
+    }
+
+    #[test]
+    fn test_select_video_url() {
+        let mut video = VideoPlayInfo {
+            rtmp_url_hdv: "hd".to_owned(),
+            rtmp_url_sdv: "sd".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(Some("hd"), video.select_url(VideoQuality::Auto));
+        assert_eq!(Some("hd"), video.select_url(VideoQuality::Hd));
+        assert_eq!(Some("sd"), video.select_url(VideoQuality::Sd));
+
+        video.rtmp_url_sdv.clear();
+        assert_eq!(Some("hd"), video.select_url(VideoQuality::Sd));
+
+        video.rtmp_url_hdv.clear();
+        assert_eq!(None, video.select_url(VideoQuality::Auto));
+    }
+
+    #[test]
+    fn test_candidate_video_urls() {
+        let mut video = VideoPlayInfo {
+            rtmp_url_hdv: "hd".to_owned(),
+            rtmp_url_sdv: "sd".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(vec!["hd", "sd"], video.candidate_urls(VideoQuality::Auto));
+        assert_eq!(vec!["sd", "hd"], video.candidate_urls(VideoQuality::Sd));
+        assert_eq!(VideoQuality::Sd, video.url_quality("sd"));
+        assert_eq!(VideoQuality::Hd, video.url_quality("hd"));
+
+        // some plays repeat one link for both qualities, it is tried once
+        video.rtmp_url_sdv = "hd".to_owned();
+        assert_eq!(vec!["hd"], video.candidate_urls(VideoQuality::Sd));
+
+        video.rtmp_url_sdv.clear();
+        assert_eq!(vec!["hd"], video.candidate_urls(VideoQuality::Sd));
+    }
+
+    #[test]
+    fn test_video_direct_urls() {
+        let mut info = VideoInfo {
+            video_play_response_vo_list: vec![
+                VideoPlayInfo {
+                    id: 1,
+                    rtmp_url_hdv: "https://live.sjtu.edu.cn/vod/1.mp4".to_owned(),
+                    rtmp_url_sdv: "https://cdn.example.com/1.mp4?Expires=1700000000".to_owned(),
+                    ..Default::default()
+                },
+                VideoPlayInfo {
+                    id: 2,
+                    rtmp_url_hdv: "rtmp://live.sjtu.edu.cn/live/2".to_owned(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let urls = video_direct_urls(&info, VideoQuality::Auto);
+        assert_eq!(1, urls.len());
+        assert_eq!(VideoQuality::Hd, urls[0].quality);
+        assert_eq!(Some(VIDEO_REFERER), urls[0].referer.as_deref());
+        assert_eq!(None, urls[0].expires_at);
+
+        let urls = video_direct_urls(&info, VideoQuality::Sd);
+        assert_eq!(VideoQuality::Sd, urls[0].quality);
+        assert_eq!(None, urls[0].referer);
+        assert_eq!(Some(1700000000), urls[0].expires_at);
+
+        info.video_play_response_vo_list.remove(0);
+        assert!(video_direct_urls(&info, VideoQuality::Auto).is_empty());
+    }
+
+    #[test]
+    fn test_signed_url_expiry() {
+        assert_eq!(
+            Some(1700000000),
+            signed_url_expiry("https://cdn.example.com/v.mp4?sign=abc&Expires=1700000000")
+        );
+        assert_eq!(
+            Some(1700000600),
+            signed_url_expiry("https://cdn.example.com/v.mp4?x-oss-expires=1700000600")
+        );
+        assert_eq!(
+            None,
+            signed_url_expiry("https://cdn.example.com/v.mp4?expires=soon")
+        );
+        assert_eq!(
+            None,
+            signed_url_expiry("https://cdn.example.com/v.mp4?token=abc")
+        );
+        assert_eq!(None, signed_url_expiry("not a url"));
+    }
+
+    #[test]
+    fn test_recommended_videos() {
+        let video = |id: &str, begin: &str| CanvasVideo {
+            video_id: id.to_owned(),
+            course_begin_time: begin.to_owned(),
+            ..Default::default()
+        };
+        let videos = vec![
+            video("c", "2024-03-08 08:00:00"),
+            video("a", "2024-03-01 08:00:00"),
+            video("e", "2024-03-22 08:00:00"),
+            video("b", "2024-03-04 08:00:00"),
+            video("d", "2024-03-15 08:00:00"),
+        ];
+        let ids = |videos: Vec<CanvasVideo>| -> Vec<String> {
+            videos.into_iter().map(|video| video.video_id).collect()
+        };
+        assert_eq!(
+            vec!["b", "c", "d"],
+            ids(recommended_videos(videos.clone(), "a"))
+        );
+        assert_eq!(vec!["e"], ids(recommended_videos(videos.clone(), "d")));
+        assert!(recommended_videos(videos.clone(), "e").is_empty());
+        assert!(recommended_videos(videos, "unknown").is_empty());
+    }
+
+    #[test]
+    fn test_find_video_tool() {
+        let tool = |id: i64, url: Option<&str>| ExternalTool {
+            id,
+            name: format!("tool {}", id),
+            url: url.map(str::to_owned),
+        };
+        let tools = vec![
+            tool(1, None),
+            tool(2, Some("https://oc.sjtu.edu.cn/lti/other")),
+            tool(8199, Some("http://courses.sjtu.edu.cn/lti/launch")),
+        ];
+        assert_eq!(Some(8199), find_video_tool(&tools));
+        assert_eq!(None, find_video_tool(&tools[..2]));
+    }
+
+    #[test]
+    fn test_render_video_list() -> Result<()> {
+        let video = CanvasVideo {
+            video_id: "v1".to_owned(),
+            video_name: "数据结构, 第3讲".to_owned(),
+            course_begin_time: "2024-03-01 08:00:00".to_owned(),
+            ..Default::default()
+        };
+        let info = VideoInfo {
+            vide_play_time: 5400,
+            video_play_response_vo_list: vec![
+                VideoPlayInfo {
+                    rtmp_url_hdv: "hd1".to_owned(),
+                    ..Default::default()
+                },
+                VideoPlayInfo {
+                    rtmp_url_hdv: "hd2".to_owned(),
+                    rtmp_url_sdv: "sd2".to_owned(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let entry = video_list_entry(&video, Some(&info), VideoQuality::Sd);
+        assert_eq!(Some(5400), entry.duration_secs);
+        assert_eq!(vec!["hd1", "sd2"], entry.download_urls);
+
+        let csv = render_video_list(&[entry.clone()], VideoListFormat::Csv, true)?;
+        assert!(
+            csv.ends_with("\"数据结构, 第3讲\",2024-03-01 08:00:00,1:30:00,v1,\"hd1\nsd2\"\r\n")
+        );
+        let csv = render_video_list(&[entry.clone()], VideoListFormat::Csv, false)?;
+        assert!(csv.ends_with("\"数据结构, 第3讲\",2024-03-01 08:00:00,1:30:00,v1\r\n"));
+
+        let json = render_video_list(&[entry], VideoListFormat::Json, true)?;
+        let parsed: Vec<VideoListEntry> = serde_json::from_str(&json)?;
+        assert_eq!(vec!["hd1", "sd2"], parsed[0].download_urls);
+
+        let entry = video_list_entry(&video, None, VideoQuality::Auto);
+        assert_eq!(None, entry.duration_secs);
+        assert!(entry.download_urls.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_api_version() {
+        assert_eq!(Some(ApiVersion::new(2, 1, 3)), ApiVersion::parse("2.1.3"));
+        assert_eq!(Some(ApiVersion::new(2, 1, 0)), ApiVersion::parse("v2.1"));
+        assert_eq!(None, ApiVersion::parse("latest"));
+        assert!(ApiVersion::new(2, 0, 1) >= ApiVersion::new(2, 0, 0));
+        assert!(ApiVersion::new(1, 9, 9) < ApiVersion::new(2, 0, 0));
+        assert!(ApiVersion::new(2, 10, 0) > ApiVersion::new(2, 9, 0));
+    }
+
+    #[test]
+    fn test_parse_oauth_consumer_key() -> Result<()> {
+        let page = |attr: &str| {
+            format!(
+                r#"<html><head><meta id="xForSecName" name="xForSecName" {}="a2V5"></head></html>"#,
+                attr
+            )
+        };
+        assert_eq!("key", parse_oauth_consumer_key(&page("vaule"))?);
+        assert_eq!("key", parse_oauth_consumer_key(&page("value"))?);
+        assert_eq!("key", parse_oauth_consumer_key(&page("content"))?);
+
+        let renamed = r#"<meta id="secName" vaule="a2V5">"#;
+        assert!(matches!(
+            parse_oauth_consumer_key(renamed),
+            Err(AppError::OAuthConsumerKeyNotFound)
+        ));
+        assert!(matches!(
+            parse_oauth_consumer_key("<html></html>"),
+            Err(AppError::OAuthConsumerKeyNotFound)
+        ));
+
+        let script = r#"<html><head><script>
+            window.config = { "xForSecName": "a2V5", debug: false };
+        </script></head></html>"#;
+        assert_eq!("key", parse_oauth_consumer_key(script)?);
+        let script = r#"<script>var xForSecName = 'a2V5';</script>"#;
+        assert_eq!("key", parse_oauth_consumer_key(script)?);
+
+        let invalid = r#"<meta id="xForSecName" vaule="not base64!">"#;
+        assert!(matches!(
+            parse_oauth_consumer_key(invalid),
+            Err(AppError::InvalidOAuthConsumerKey(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_video_url_status() {
+        let probe = |available| {
+            Some(VideoUrlProbe {
+                available,
+                ..Default::default()
+            })
+        };
+        let usable = |hd, sd, quality| VideoUrlStatus::new(1, hd, sd, quality).usable_quality;
+        assert_eq!(
+            Some(VideoQuality::Hd),
+            usable(probe(true), probe(true), VideoQuality::Auto)
+        );
+        assert_eq!(
+            Some(VideoQuality::Sd),
+            usable(probe(false), probe(true), VideoQuality::Hd)
+        );
+        assert_eq!(
+            Some(VideoQuality::Sd),
+            usable(probe(true), probe(true), VideoQuality::Sd)
+        );
+        assert_eq!(
+            Some(VideoQuality::Hd),
+            usable(probe(true), None, VideoQuality::Sd)
+        );
+        assert_eq!(None, usable(probe(false), None, VideoQuality::Auto));
+    }
+
+    #[test]
+    fn test_subject_classes() {
+        let subject = |subject_id, tecl_id, user_name: &str| Subject {
+            subject_id,
+            tecl_id,
+            user_name: user_name.to_owned(),
+            tecl_name: format!("class {}", tecl_id),
+            ..Default::default()
+        };
+        let subjects = vec![
+            subject(1, 10, "Alice"),
+            subject(2, 20, "Carol"),
+            subject(1, 11, "Bob"),
+            subject(1, 10, "Alice"),
+        ];
+        let classes = subject_classes(&subjects, 1);
+        assert_eq!(
+            vec![(10, "Alice"), (11, "Bob")],
+            classes
+                .iter()
+                .map(|class| (class.tecl_id, class.teacher_name.as_str()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("class 11", classes[1].class_name);
+        assert!(subject_classes(&subjects, 3).is_empty());
+    }
+
+    #[test]
+    fn test_list_terms() {
+        let subject = |begin_year, term_time| Subject {
+            begin_year,
+            end_year: begin_year + 1,
+            term_time,
+            ..Default::default()
+        };
+        let subjects = vec![
+            subject(2023, 2),
+            subject(2024, 1),
+            subject(2023, 1),
+            subject(2024, 1),
+        ];
+        let terms: Vec<_> = list_terms(&subjects)
+            .iter()
+            .map(|term| (term.begin_year, term.term_time))
+            .collect();
+        assert_eq!(vec![(2024, 1), (2023, 2), (2023, 1)], terms);
+        assert!(list_terms(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_watched_on() {
+        let record = |vide_id, watch_time: &str| WatchRecord {
+            vide_id,
+            watch_time: watch_time.to_owned(),
+            ..Default::default()
+        };
+        let records = vec![
+            record(1, "2024-03-01 08:00:00"),
+            record(2, "2024-03-02 23:59:59"),
+            record(3, "2024-03-01 12:30:00"),
+            record(4, ""),
+        ];
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let filtered: Vec<_> = records
+            .iter()
+            .filter(|record| watched_on(record, date))
+            .map(|record| record.vide_id)
+            .collect();
+        assert_eq!(vec![1, 3], filtered);
+    }
+
+    #[test]
+    fn test_find_zero_ranges() -> Result<()> {
+        let mut data = vec![1u8; 40];
+        // a hole not aligned to the 4 byte blocks
+        data[10..22].fill(0);
+        // a truncated tail
+        data[36..].fill(0);
+        let ranges = find_zero_ranges(io::Cursor::new(&data), data.len() as u64, 4)?;
+        assert_eq!(vec![(8, 23), (32, 39)], ranges);
+        for (begin, end) in &ranges {
+            assert!(data[*begin as usize..=*end as usize].contains(&0));
+        }
+
+        assert!(find_zero_ranges(io::Cursor::new(vec![1u8; 10]), 10, 4)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_video_info() {
+        let play = VideoPlayInfo {
+            id: 1,
+            vide_play_time: 100,
+            rtmp_url_hdv: "hd".to_owned(),
+            ..Default::default()
+        };
+        let cached = VideoInfo {
+            vide_name: "第1周".to_owned(),
+            vide_play_time: 100,
+            video_play_response_vo_list: vec![play.clone()],
+            ..Default::default()
+        };
+        assert!(diff_video_info(&cached, &cached).is_empty());
+
+        let current = VideoInfo {
+            vide_play_time: 120,
+            video_play_response_vo_list: vec![
+                VideoPlayInfo {
+                    rtmp_url_sdv: "sd".to_owned(),
+                    ..play
+                },
+                VideoPlayInfo {
+                    id: 2,
+                    ..Default::default()
+                },
+            ],
+            ..cached.clone()
+        };
+        assert_eq!(
+            vec![
+                "时长：100s -> 120s".to_owned(),
+                "视频流 1 清晰度：高清 -> 高清/标清".to_owned(),
+                "新增视频流 2".to_owned(),
+            ],
+            diff_video_info(&cached, &current)
+        );
+    }
+
+    #[test]
+    fn test_video_checksum() -> Result<()> {
+        let save_path = "test_video_checksum.mp4";
+        fs::write(save_path, b"video content")?;
+        Client::save_video_checksum(save_path)?;
+        assert!(Client::is_complete_file(save_path, 13));
+        assert!(!Client::is_complete_file(save_path, 12));
+
+        // same size but different content
+        fs::write(save_path, b"video CONTENT")?;
+        assert!(!Client::is_complete_file(save_path, 13));
+
+        fs::remove_file(save_path)?;
+        fs::remove_file(Client::video_checksum_path(save_path))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_video_metadata() -> Result<()> {
+        let save_path = "test_video_metadata.mp4";
+        assert!(Client::read_video_metadata(save_path).is_none());
+        fs::write(save_path, b"video content")?;
+        let video = VideoInfo {
+            id: 1,
+            cour_name: "操作系统".to_owned(),
+            ..Default::default()
+        };
+        let play = VideoPlayInfo {
+            id: 2,
+            rtmp_url_hdv: "https://example.com/hd.mp4".to_owned(),
+            ..Default::default()
+        };
+        Client::save_video_metadata(save_path, &video, &play, &play.rtmp_url_hdv, None)?;
+        let metadata = Client::read_video_metadata(save_path).unwrap();
+        assert_eq!("操作系统", metadata.course_name);
+        assert_eq!(2, metadata.play_id);
+        assert_eq!(13, metadata.size);
+        assert_eq!("https://example.com/hd.mp4", metadata.source_url);
+
+        fs::remove_file(save_path)?;
+        fs::remove_file(Client::video_metadata_path(save_path))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_oauth_signature() -> Result<()> {
+        struct TestCase {
+            video_id: i64,
+            oauth_nonce: &'static str,
+            oauth_consumer_key: &'static str,
+            expected: &'static str,
+        }
+
+        let cases = vec![
+            TestCase {
+                video_id: 3601811,
+                oauth_nonce: "1709784720392",
+                oauth_consumer_key: "DADD2CA9923D5E31331C4B79B39A1E4B",
+                expected: "2b499a5303048d6522118e79711c5ee0",
+            },
+            TestCase {
+                video_id: 1,
+                oauth_nonce: "1709784720392",
+                oauth_consumer_key: "DADD2CA9923D5E31331C4B79B39A1E4B",
+                expected: "b2cf1c8e13960b1286cde8f89ade6a19",
+            },
+            TestCase {
+                video_id: 9007199254740993,
+                oauth_nonce: "1709784720392",
+                oauth_consumer_key: "DADD2CA9923D5E31331C4B79B39A1E4B",
+                expected: "871f5b84b4b6a5ddd5e6da583f919dcc",
+            },
+            // the nonce is signed verbatim, leading zeros included
+            TestCase {
+                video_id: 3601811,
+                oauth_nonce: "0001709784720",
+                oauth_consumer_key: "DADD2CA9923D5E31331C4B79B39A1E4B",
+                expected: "c294f331c429887b3c1798230955e7ef",
+            },
+            // so is the consumer key, it's never url encoded
+            TestCase {
+                video_id: 3601811,
+                oauth_nonce: "1709784720392",
+                oauth_consumer_key: "a+b/c=d&e%f",
+                expected: "65b5458488634313ce4be904ffc69e92",
+            },
+        ];
+
+        let cli = Client::new();
+        for case in cases {
+            assert_eq!(
+                case.expected,
+                cli.get_oauth_signature(case.video_id, case.oauth_nonce, case.oauth_consumer_key),
+                "video_id: {}, oauth_nonce: {}, oauth_consumer_key: {}",
+                case.video_id,
+                case.oauth_nonce,
+                case.oauth_consumer_key
+            );
+        }
         Ok(())
     }
 }
@@ -1,10 +1,11 @@
+use reqwest::StatusCode;
 use std::io;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Network error: {0}")]
-    Network(#[from] reqwest::Error),
+    Network(reqwest::Error),
     #[error("Json deserialization error: {0}, object type: {1}, context: {2}")]
     JsonDeserialize(serde_json::Error, String, String),
     #[error("Json parse error: {0}")]
@@ -26,6 +27,8 @@ pub enum AppError {
     FunctionUnsupported,
     #[error("Submission upload error: {0}")]
     SubmissionUpload(String),
+    #[error("WebSocket error: {0}")]
+    WebSocket(#[from] tokio_tungstenite::tungstenite::Error),
     #[error("Join error: {0}")]
     JoinError(#[from] tokio::task::JoinError),
     #[error("QRCode Image error: {0}")]
@@ -44,8 +47,109 @@ pub enum AppError {
     OpenStdoutError,
     #[error("Failed to open stderr")]
     OpenStderrError,
-    #[error("Failed to download video {0}")]
-    VideoDownloadError(String),
+    // how much of the file made it to disk tells whether a resume or repair is worthwhile
+    #[error(
+        "Failed to download video {save_path}, {bytes_written} of {expected_bytes} bytes written"
+    )]
+    VideoDownloadError {
+        save_path: String,
+        bytes_written: u64,
+        expected_bytes: u64,
+    },
+    #[error("No playable url for video {0}")]
+    NoPlayableVideoUrl(i64),
+    #[error("Network timeout")]
+    NetworkTimeout,
+    #[error("Server error: {0}")]
+    ServerError(u16),
+    #[error("Unauthorized")]
+    Unauthorized,
+    #[error("Not found")]
+    NotFound,
+    #[error("HLS error: {0}")]
+    HlsError(String),
+    #[error("Failed to detect video platform api version")]
+    ApiVersionNotFound,
+    #[error("ffmpeg not found, please install ffmpeg first")]
+    FfmpegNotFound,
+    #[error("Failed to remux video {0}")]
+    VideoRemuxError(String),
+    #[error("Failed to extract audio from video {0}")]
+    AudioExtractError(String),
+    #[error("Failed to merge video streams of {0}")]
+    VideoMergeError(String),
+    #[error(
+        "Insufficient disk space: {} MB needed but only {} MB available",
+        needed / 1024 / 1024,
+        available / 1024 / 1024
+    )]
+    InsufficientDiskSpace { needed: u64, available: u64 },
+    #[error("Invalid date: {0}")]
+    InvalidDate(String),
+    #[error("Unsupported operation: {0}")]
+    UnsupportedOperation(String),
+    #[error("Invalid clip range: {0}s - {1}s")]
+    InvalidClipRange(f64, f64),
+    #[error("File too large: {size} bytes, at most {limit} bytes allowed")]
+    FileTooLarge { size: u64, limit: u64 },
+    #[error("Unsupported file format: {0}")]
+    UnsupportedFormat(String),
+    #[error("No metadata found for video {0}, it may not be downloaded by this app")]
+    VideoMetadataNotFound(String),
+    #[error("No login uuid found in the my.sjtu.edu.cn page, the page may have changed")]
+    UuidNotFound,
+    #[error("No oauth consumer key found in the video page, the page may have changed")]
+    OAuthConsumerKeyNotFound,
+    #[error("Invalid oauth consumer key in the video page, it isn't base64: {0}")]
+    InvalidOAuthConsumerKey(String),
+    #[error("Invalid proxy {0}, an http://, https:// or socks5:// url is expected")]
+    InvalidProxy(String),
+    #[error("Can't connect to proxy {proxy}: {reason}")]
+    ProxyUnreachable { proxy: String, reason: String },
+    #[error("Subject {subject_id} has {classes} classes, one of them must be chosen")]
+    SubjectClassRequired { subject_id: i64, classes: usize },
+    #[error("Invalid collaboration url {0}, only http(s) links can be opened")]
+    InvalidCollaborationUrl(String),
+    #[error("Course {0} has no video recording tool configured")]
+    VideoToolNotFound(i64),
+    #[error("Video {0} has no downloadable stream, it may be a live broadcast")]
+    NoDownloadableStream(String),
+    #[error("Invalid video id {0}, the video platform uses numeric ids")]
+    InvalidVideoId(String),
+}
+
+// timeouts are told apart right away, callers shouldn't have to dig into reqwest errors
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            AppError::NetworkTimeout
+        } else {
+            AppError::Network(e)
+        }
+    }
+}
+
+impl AppError {
+    // narrow a generic network error down to the kind of http failure it represents
+    pub fn classify(self) -> Self {
+        let status = match &self {
+            AppError::Network(e) if e.is_timeout() => return AppError::NetworkTimeout,
+            AppError::Network(e) => e.status(),
+            _ => None,
+        };
+        match status {
+            Some(StatusCode::UNAUTHORIZED) | Some(StatusCode::FORBIDDEN) => AppError::Unauthorized,
+            Some(StatusCode::NOT_FOUND) => AppError::NotFound,
+            Some(status) if status.is_server_error() => AppError::ServerError(status.as_u16()),
+            _ => self,
+        }
+    }
+
+    // only timeouts and 5xx responses are worth retrying, everything else
+    // (Unauthorized, NotFound, parse errors...) won't go away by itself
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, AppError::NetworkTimeout | AppError::ServerError(_))
+    }
 }
 
 impl serde::Serialize for AppError {
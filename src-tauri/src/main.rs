@@ -1,14 +1,23 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::sync::Arc;
+use std::{collections::HashMap, path::Path};
 
-use error::Result;
+use chrono::NaiveDate;
+use error::{AppError, Result};
 use model::{
-    Account, AccountInfo, AppConfig, Assignment, CalendarEvent, CanvasVideo, Colors, Course,
-    DiscussionTopic, File, Folder, FullDiscussion, LogLevel, QRCodeScanResult, RelationshipTopo,
-    Subject, Submission, User, UserSubmissions, VideoAggregateParams, VideoCourse, VideoInfo,
-    VideoPlayInfo,
+    Account, AccountInfo, Announcement, ApiVersion, AppConfig, AppointmentGroup, Assignment,
+    AudioFormat, CalendarEvent, CanvasGroup, CanvasVideo, CanvasVideoSortKey, Collaboration,
+    Colors, ConflictPolicy, ContentMigration, Course, DiscussionTopic, DownloadHistoryEntry,
+    DownloadHistoryFilter, DownloadResult, DownloadTask, File, Folder, FolderDownloadReport,
+    FullDiscussion, GradeEntry, LearningOutcome, LogLevel, LoginStatus, MediaRecording, MediaTrack,
+    MergeLayout, OutcomeRollup, QRCodeScanResult, QrSession, QueuedVideoDownload, RelationshipTopo,
+    SortOrder, Subject, SubjectClass, Submission, SubmissionComment, Term, TranscriptSegment,
+    TransferStats, UsageRights, User, UserSubmissions, Video, VideoAggregateParams, VideoClipRange,
+    VideoCourse, VideoDirectUrl, VideoDownloadReport, VideoDownloadResult, VideoIdSource,
+    VideoInfo, VideoInfoChanges, VideoListFormat, VideoPlayInfo, VideoPlaylist, VideoQuality,
+    VideoRepairReport, VideoSearchHit, VideoStream, VideoStreamsDownload, VideoUrlStatus,
+    WatchRecord,
 };
 
 use tauri::{api::path::config_dir, Runtime, Window};
@@ -65,6 +74,11 @@ fn read_account_info() -> Result<AccountInfo> {
     App::read_account_info()
 }
 
+#[tauri::command]
+async fn logout() -> Result<()> {
+    APP.logout().await
+}
+
 #[tauri::command]
 async fn switch_account(account: Account) -> Result<()> {
     APP.switch_account(&account).await
@@ -117,6 +131,11 @@ async fn list_discussion_topics(course_id: i64) -> Result<Vec<DiscussionTopic>>
     APP.list_discussion_topics(course_id).await
 }
 
+#[tauri::command]
+async fn list_announcements(course_id: i64) -> Result<Vec<Announcement>> {
+    APP.list_announcements(course_id).await
+}
+
 #[tauri::command]
 async fn get_full_discussion(course_id: i64, topic_id: i64) -> Result<FullDiscussion> {
     APP.get_full_discussion(course_id, topic_id).await
@@ -132,6 +151,65 @@ async fn list_course_files(course_id: i64) -> Result<Vec<File>> {
     APP.list_course_files(course_id).await
 }
 
+#[tauri::command]
+async fn search_course_files(course_id: i64, query: String) -> Result<Vec<File>> {
+    APP.search_course_files(course_id, &query).await
+}
+
+#[tauri::command]
+async fn get_canvas_collaborations(course_id: i64) -> Result<Vec<Collaboration>> {
+    APP.get_canvas_collaborations(course_id).await
+}
+
+#[tauri::command]
+fn open_collaboration_url(url: String) -> Result<()> {
+    APP.open_collaboration_url(&url)
+}
+
+#[tauri::command]
+async fn get_canvas_groups(course_id: i64) -> Result<Vec<CanvasGroup>> {
+    APP.get_canvas_groups(course_id).await
+}
+
+#[tauri::command]
+async fn get_canvas_appointment_groups(course_id: i64) -> Result<Vec<AppointmentGroup>> {
+    APP.get_canvas_appointment_groups(course_id).await
+}
+
+#[tauri::command]
+async fn get_canvas_outcome_rollups(course_id: i64) -> Result<Vec<OutcomeRollup>> {
+    APP.get_canvas_outcome_rollups(course_id).await
+}
+
+#[tauri::command]
+async fn get_canvas_learning_outcomes(course_id: i64) -> Result<Vec<LearningOutcome>> {
+    APP.get_canvas_learning_outcomes(course_id).await
+}
+
+#[tauri::command]
+async fn get_canvas_media_recordings(course_id: i64) -> Result<Vec<MediaRecording>> {
+    APP.get_canvas_media_recordings(course_id).await
+}
+
+#[tauri::command]
+async fn get_canvas_content_migrations(course_id: i64) -> Result<Vec<ContentMigration>> {
+    APP.get_canvas_content_migrations(course_id).await
+}
+
+#[tauri::command]
+async fn get_canvas_media_track(media_id: String) -> Result<Vec<MediaTrack>> {
+    APP.get_canvas_media_track(&media_id).await
+}
+
+#[tauri::command]
+async fn download_canvas_media_captions(
+    media_id: String,
+    save_name: String,
+) -> Result<Vec<String>> {
+    APP.download_canvas_media_captions(&media_id, &save_name)
+        .await
+}
+
 #[tauri::command]
 async fn list_course_images(course_id: i64) -> Result<Vec<File>> {
     APP.list_course_images(course_id).await
@@ -202,6 +280,26 @@ fn check_path(path: String) -> bool {
     App::check_path(&path)
 }
 
+#[tauri::command]
+async fn upload_canvas_profile_picture(image_path: String) -> Result<()> {
+    APP.upload_canvas_profile_picture(&image_path).await
+}
+
+#[tauri::command]
+async fn export_calendar_events(events: Vec<CalendarEvent>, save_name: String) -> Result<()> {
+    APP.export_calendar_events(&events, &save_name).await
+}
+
+#[tauri::command]
+async fn check_network_proxy(proxy_url: String) -> Result<()> {
+    APP.check_network_proxy(&proxy_url).await
+}
+
+#[tauri::command]
+async fn export_course_calendar(course_id: i64, save_name: String) -> Result<()> {
+    APP.export_course_calendar(course_id, &save_name).await
+}
+
 #[tauri::command]
 async fn export_users(users: Vec<User>, save_name: String) -> Result<()> {
     APP.export_users(&users, &save_name).await
@@ -269,6 +367,28 @@ async fn download_file<R: Runtime>(window: Window<R>, file: File) -> Result<()>
     .await
 }
 
+#[tauri::command]
+async fn download_folder<R: Runtime>(
+    window: Window<R>,
+    course_id: i64,
+    folder_id: Option<i64>,
+    local_dir: String,
+) -> Result<FolderDownloadReport> {
+    let progress_window = window.clone();
+    APP.download_folder(
+        course_id,
+        folder_id,
+        &local_dir,
+        move |progress| {
+            let _ = progress_window.emit("download://progress", progress);
+        },
+        move |summary| {
+            let _ = window.emit("folder_download://progress", summary);
+        },
+    )
+    .await
+}
+
 #[tauri::command]
 async fn download_course_file<R: Runtime>(
     window: Window<R>,
@@ -315,11 +435,26 @@ async fn list_calendar_events(
         .await
 }
 
+#[tauri::command]
+async fn get_calendar_events(
+    course_id: i64,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<CalendarEvent>> {
+    APP.get_calendar_events(course_id, &start_date, &end_date)
+        .await
+}
+
 #[tauri::command]
 async fn get_folder_by_id(folder_id: i64) -> Result<Folder> {
     APP.get_folder_by_id(folder_id).await
 }
 
+#[tauri::command]
+async fn get_canvas_file_usage_rights(file_id: i64) -> Result<UsageRights> {
+    APP.get_canvas_file_usage_rights(file_id).await
+}
+
 #[tauri::command]
 async fn get_colors() -> Result<Colors> {
     APP.get_colors().await
@@ -343,13 +478,41 @@ async fn upload_submission_file(
 }
 
 #[tauri::command]
-async fn submit_assignment(
+async fn submit_assignment<R: Runtime>(
+    window: Window<R>,
     course_id: i64,
     assignment_id: i64,
     file_paths: Vec<String>,
     comment: Option<String>,
 ) -> Result<()> {
-    APP.submit_assignment(course_id, assignment_id, &file_paths, comment.as_deref())
+    APP.submit_assignment(
+        course_id,
+        assignment_id,
+        &file_paths,
+        comment.as_deref(),
+        move |progress| {
+            let _ = window.emit("submission_upload://progress", progress);
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+async fn get_student_view_student(course_id: i64) -> Result<User> {
+    APP.get_student_view_student(course_id).await
+}
+
+#[tauri::command]
+async fn list_student_view_assignments(course_id: i64) -> Result<Vec<Assignment>> {
+    APP.list_student_view_assignments(course_id).await
+}
+
+#[tauri::command]
+async fn get_canvas_submission_comments(
+    course_id: i64,
+    assignment_id: i64,
+) -> Result<Vec<SubmissionComment>> {
+    APP.get_canvas_submission_comments(course_id, assignment_id)
         .await
 }
 
@@ -468,7 +631,22 @@ async fn add_assignment_ddl_override(
 
 // Apis for course video
 #[tauri::command]
-async fn get_uuid() -> Result<Option<String>> {
+async fn check_session_valid() -> Result<bool> {
+    APP.check_session_valid().await
+}
+
+#[tauri::command]
+async fn start_qr_login() -> Result<QrSession> {
+    APP.start_qr_login().await
+}
+
+#[tauri::command]
+async fn poll_qr_login(uuid: String) -> Result<LoginStatus> {
+    APP.poll_qr_login(&uuid).await
+}
+
+#[tauri::command]
+async fn get_uuid() -> Result<String> {
     APP.get_uuid().await
 }
 
@@ -483,13 +661,101 @@ async fn login_video_website() -> Result<()> {
 }
 
 #[tauri::command]
-async fn get_subjects() -> Result<Vec<Subject>> {
-    APP.get_subjects().await
+async fn get_video_platform_api_version() -> Result<ApiVersion> {
+    APP.get_video_platform_api_version().await
+}
+
+#[tauri::command]
+async fn video_platform_supports(major: u32, minor: u32, build: u32) -> bool {
+    APP.video_platform_supports(ApiVersion::new(major, minor, build))
+        .await
+}
+
+#[tauri::command]
+async fn get_subjects(force_refresh: Option<bool>, term: Option<Term>) -> Result<Vec<Subject>> {
+    let mut subjects = APP
+        .get_subjects_cached(force_refresh.unwrap_or_default())
+        .await?;
+    if let Some(term) = term {
+        subjects.retain(|subject| subject.term() == term);
+    }
+    Ok(subjects)
+}
+
+#[tauri::command]
+async fn get_terms(force_refresh: Option<bool>) -> Result<Vec<Term>> {
+    APP.get_terms(force_refresh.unwrap_or_default()).await
+}
+
+// `date` is formatted as YYYY-MM-DD
+#[tauri::command]
+async fn get_video_platform_history_by_date(date: String) -> Result<Vec<WatchRecord>> {
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|_| AppError::InvalidDate(date.clone()))?;
+    APP.get_video_platform_history_by_date(date).await
+}
+
+#[tauri::command]
+async fn get_canvas_videos(
+    course_id: i64,
+    sort_by: Option<CanvasVideoSortKey>,
+    order: Option<SortOrder>,
+) -> Result<Vec<CanvasVideo>> {
+    APP.get_canvas_videos(course_id, sort_by, order.unwrap_or_default())
+        .await
+}
+
+#[tauri::command]
+async fn get_recommended_videos(course_id: i64, video_id: String) -> Result<Vec<CanvasVideo>> {
+    APP.get_recommended_videos(course_id, &video_id).await
+}
+
+#[tauri::command]
+async fn export_video_playlist(
+    course_id: i64,
+    save_name: String,
+    refresh: bool,
+) -> Result<VideoPlaylist> {
+    APP.export_video_playlist(course_id, &save_name, refresh)
+        .await
+}
+
+#[tauri::command]
+async fn export_video_list(
+    course_id: i64,
+    format: VideoListFormat,
+    save_name: String,
+    include_urls: bool,
+) -> Result<()> {
+    APP.export_video_list(course_id, format, &save_name, include_urls)
+        .await
+}
+
+#[tauri::command]
+async fn refresh_canvas_course_id(course_id: i64) -> Result<Option<String>> {
+    APP.refresh_canvas_course_id(course_id).await
+}
+
+#[tauri::command]
+async fn download_video_cover(video_id: String) -> Result<Option<String>> {
+    APP.download_video_cover(&video_id).await
+}
+
+// Runs until every cover is fetched, the frontend doesn't wait for it
+#[tauri::command]
+async fn prefetch_thumbnails<R: Runtime>(
+    window: Window<R>,
+    videos: Vec<CanvasVideo>,
+) -> Result<HashMap<String, String>> {
+    APP.prefetch_thumbnails(videos, |ready| {
+        let _ = window.emit("thumbnail-ready", ready);
+    })
+    .await
 }
 
 #[tauri::command]
-async fn get_canvas_videos(course_id: i64) -> Result<Vec<CanvasVideo>> {
-    APP.get_canvas_videos(course_id).await
+async fn download_course_video_covers(course_id: i64) -> Result<HashMap<String, String>> {
+    APP.download_course_video_covers(course_id).await
 }
 
 #[tauri::command]
@@ -498,8 +764,35 @@ async fn login_canvas_website() -> Result<()> {
 }
 
 #[tauri::command]
-async fn get_video_course(subject_id: i64, tecl_id: i64) -> Result<Option<VideoCourse>> {
-    APP.get_video_course(subject_id, tecl_id).await
+async fn get_subject_classes(subject_id: i64) -> Result<Vec<SubjectClass>> {
+    APP.get_subject_classes(subject_id).await
+}
+
+// `tecl_id` can be left out for a subject taught in a single class
+#[tauri::command]
+async fn get_video_courses(subject_id: i64, tecl_id: Option<i64>) -> Result<Vec<VideoCourse>> {
+    APP.get_video_courses(subject_id, tecl_id).await
+}
+
+#[tauri::command]
+async fn search_subject_videos(
+    subject_id: i64,
+    tecl_id: Option<i64>,
+    keyword: String,
+) -> Result<Vec<Video>> {
+    APP.search_subject_videos(subject_id, tecl_id, &keyword)
+        .await
+}
+
+#[tauri::command]
+async fn search_videos<R: Runtime>(
+    window: Window<R>,
+    keyword: String,
+) -> Result<Vec<VideoSearchHit>> {
+    APP.search_videos(&keyword, |hits| {
+        let _ = window.emit("video_search://hits", hits);
+    })
+    .await
 }
 
 #[tauri::command]
@@ -507,6 +800,65 @@ async fn get_video_info(video_id: i64) -> Result<VideoInfo> {
     APP.get_video_info(video_id).await
 }
 
+#[tauri::command]
+async fn copy_video_subtitles_to_sidecar(video_path: String) -> Result<()> {
+    APP.copy_video_subtitles_to_sidecar(&video_path).await
+}
+
+#[tauri::command]
+async fn get_video_transcript(video_id: i64) -> Result<Vec<TranscriptSegment>> {
+    APP.get_video_transcript(video_id).await
+}
+
+#[tauri::command]
+async fn get_canvas_video_transcript(video_id: String) -> Result<Vec<TranscriptSegment>> {
+    APP.get_canvas_video_transcript(&video_id).await
+}
+
+#[tauri::command]
+async fn get_video_platform_subtitle_languages(video_id: i64) -> Result<Vec<String>> {
+    APP.get_video_platform_subtitle_languages(video_id).await
+}
+
+#[tauri::command]
+async fn get_video_info_diff<R: Runtime>(
+    window: Window<R>,
+    video_id: i64,
+    cached: VideoInfo,
+) -> Result<Option<Vec<String>>> {
+    let changes = APP.get_video_info_diff(video_id, &cached).await?;
+    if let Some(changes) = &changes {
+        let _ = window.emit(
+            "video_info://changed",
+            VideoInfoChanges {
+                video_id,
+                changes: changes.clone(),
+            },
+        );
+    }
+    Ok(changes)
+}
+
+#[tauri::command]
+async fn get_video_summary(video: VideoInfo, quality: Option<VideoQuality>) -> String {
+    APP.get_video_summary(&video, quality.unwrap_or_default())
+        .await
+}
+
+#[tauri::command]
+async fn get_video_direct_url(
+    video_id: String,
+    source: Option<VideoIdSource>,
+    quality: Option<VideoQuality>,
+) -> Result<Vec<VideoDirectUrl>> {
+    APP.get_video_direct_url(
+        &video_id,
+        source.unwrap_or_default(),
+        quality.unwrap_or_default(),
+    )
+    .await
+}
+
 #[tauri::command]
 async fn get_canvas_video_info(video_id: String) -> Result<VideoInfo> {
     APP.get_canvas_video_info(&video_id).await
@@ -527,14 +879,284 @@ async fn download_video<R: Runtime>(
     window: Window<R>,
     video: VideoPlayInfo,
     save_name: String,
-) -> Result<()> {
-    let window = Arc::new(window);
-    APP.download_video(&video, &save_name, move |progress| {
-        let _ = window.clone().emit("video_download://progress", progress);
-    })
+    video_info: Option<VideoInfo>,
+    quality: Option<VideoQuality>,
+    conflict_policy: Option<ConflictPolicy>,
+    remux: Option<bool>,
+    ignore_disk_space: Option<bool>,
+) -> Result<DownloadResult> {
+    // name the file after the configured template when the whole recording is known
+    let save_name = match &video_info {
+        Some(video_info) => {
+            let index = video_info
+                .video_play_response_vo_list
+                .iter()
+                .position(|play| play.id == video.id)
+                .unwrap_or_default();
+            APP.video_save_name(video_info, index).await
+        }
+        None => save_name,
+    };
+    let quality = quality.unwrap_or_default();
+    let conflict_policy = match conflict_policy {
+        Some(conflict_policy) => conflict_policy,
+        None => APP.default_conflict_policy().await,
+    };
+    if !ignore_disk_space.unwrap_or_default() {
+        APP.check_video_disk_space(&video, quality).await?;
+    }
+    APP.queue_video_download(&video, &save_name, quality)?;
+    let progress_handler = APP.download_progress_handler();
+    let result = APP
+        .download_video(
+            &video,
+            &save_name,
+            quality,
+            conflict_policy,
+            move |progress| {
+                APP.update_queued_video_progress(&progress);
+                progress_handler(progress);
+            },
+        )
+        .await;
+    APP.finish_download_task(&video.id.to_string(), &result);
+    APP.finish_queued_video(video.id, &result);
+    let mut result = result?;
+    // a renamed download keeps its sidecars and remuxing on the file it was saved as
+    let save_name = Path::new(&result.path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or(save_name);
+    if remux.unwrap_or_default() && result.status == VideoDownloadResult::Downloaded {
+        let _ = window.emit("video_download://remuxing", video.id.to_string());
+        result.bytes_written = APP.remux_video(&save_name).await?;
+    }
+    if let Some(video_info) = video_info {
+        if result.status == VideoDownloadResult::Downloaded {
+            let source_url = result.source_url.as_deref().unwrap_or_default();
+            APP.save_video_metadata(&video_info, &video, &save_name, source_url, None)
+                .await?;
+            let is_main_stream = video_info
+                .video_play_response_vo_list
+                .first()
+                .is_some_and(|play| play.id == video.id);
+            if is_main_stream {
+                APP.save_video_subtitles(&video_info, &save_name).await;
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+async fn probe_video_urls(video: VideoPlayInfo, quality: Option<VideoQuality>) -> VideoUrlStatus {
+    APP.probe_video_urls(&video, quality.unwrap_or_default())
+        .await
+}
+
+#[tauri::command]
+fn list_video_queue() -> Result<Vec<QueuedVideoDownload>> {
+    APP.list_video_queue()
+}
+
+#[tauri::command]
+fn remove_queued_videos(video_ids: Vec<i64>) -> Result<()> {
+    APP.remove_queued_videos(&video_ids)
+}
+
+#[tauri::command]
+async fn resume_video_download(
+    video: VideoPlayInfo,
+    save_name: String,
+    quality: Option<VideoQuality>,
+) -> Result<Option<VideoRepairReport>> {
+    APP.resume_video_download(&video, &save_name, quality.unwrap_or_default())
+        .await
+}
+
+#[tauri::command]
+async fn repair_video(
+    video: VideoPlayInfo,
+    save_name: String,
+    quality: Option<VideoQuality>,
+) -> Result<VideoRepairReport> {
+    APP.repair_video(&video, &save_name, quality.unwrap_or_default())
+        .await
+}
+
+#[tauri::command]
+async fn download_video_clip(
+    video: VideoPlayInfo,
+    save_name: String,
+    video_info: Option<VideoInfo>,
+    quality: Option<VideoQuality>,
+    start: f64,
+    end: f64,
+) -> Result<VideoClipRange> {
+    let result = APP
+        .download_video_clip(
+            &video,
+            video_info.as_ref(),
+            &save_name,
+            quality.unwrap_or_default(),
+            start,
+            end,
+            APP.download_progress_handler(),
+        )
+        .await;
+    APP.finish_download_task(&video.id.to_string(), &result);
+    result
+}
+
+#[tauri::command]
+async fn download_audio_only(
+    video: VideoPlayInfo,
+    save_name: String,
+    quality: Option<VideoQuality>,
+    format: Option<AudioFormat>,
+    bitrate: Option<u32>,
+) -> Result<String> {
+    let result = APP
+        .download_audio_only(
+            &video,
+            &save_name,
+            quality.unwrap_or_default(),
+            format.unwrap_or_default(),
+            bitrate,
+            APP.download_progress_handler(),
+        )
+        .await;
+    APP.finish_download_task(&video.id.to_string(), &result);
+    result
+}
+
+#[tauri::command]
+async fn download_merged_video(
+    video: VideoInfo,
+    video_name: String,
+    quality: Option<VideoQuality>,
+    layout: Option<MergeLayout>,
+    conflict_policy: Option<ConflictPolicy>,
+) -> Result<VideoStreamsDownload> {
+    let conflict_policy = match conflict_policy {
+        Some(conflict_policy) => conflict_policy,
+        None => APP.default_conflict_policy().await,
+    };
+    let result = APP
+        .download_merged_video(
+            &video,
+            &video_name,
+            quality.unwrap_or_default(),
+            layout.unwrap_or_default(),
+            conflict_policy,
+            APP.download_progress_handler(),
+        )
+        .await;
+    APP.finish_download_task(&video.id.to_string(), &result);
+    result
+}
+
+#[tauri::command]
+async fn download_video_streams(
+    video: VideoInfo,
+    video_name: String,
+    stream: Option<VideoStream>,
+    quality: Option<VideoQuality>,
+    layout: Option<MergeLayout>,
+    conflict_policy: Option<ConflictPolicy>,
+) -> Result<VideoStreamsDownload> {
+    let conflict_policy = match conflict_policy {
+        Some(conflict_policy) => conflict_policy,
+        None => APP.default_conflict_policy().await,
+    };
+    let result = APP
+        .download_video_streams(
+            &video,
+            &video_name,
+            stream.unwrap_or_default(),
+            quality.unwrap_or_default(),
+            layout.unwrap_or_default(),
+            conflict_policy,
+            APP.download_progress_handler(),
+        )
+        .await;
+    APP.finish_download_task(&video.id.to_string(), &result);
+    result
+}
+
+#[tauri::command]
+async fn download_course_videos<R: Runtime>(
+    window: Window<R>,
+    course_id: i64,
+    save_dir: String,
+    quality: Option<VideoQuality>,
+    conflict_policy: Option<ConflictPolicy>,
+    ignore_disk_space: Option<bool>,
+) -> Result<VideoDownloadReport> {
+    let quality = quality.unwrap_or_default();
+    let conflict_policy = match conflict_policy {
+        Some(conflict_policy) => conflict_policy,
+        None => APP.default_conflict_policy().await,
+    };
+    if !ignore_disk_space.unwrap_or_default() {
+        APP.check_course_videos_disk_space(course_id, &save_dir, quality)
+            .await?;
+    }
+    APP.download_course_videos(
+        course_id,
+        &save_dir,
+        quality,
+        conflict_policy,
+        APP.download_progress_handler(),
+        move |summary| {
+            let _ = window.emit("video_batch_download://progress", summary);
+        },
+    )
     .await
 }
 
+#[tauri::command]
+async fn get_grades(course_id: i64) -> Result<Vec<GradeEntry>> {
+    APP.get_grades(course_id).await
+}
+
+#[tauri::command]
+fn get_download_history(
+    filter: Option<DownloadHistoryFilter>,
+) -> Result<Vec<DownloadHistoryEntry>> {
+    APP.get_download_history(&filter.unwrap_or_default())
+}
+
+#[tauri::command]
+fn clear_download_history() -> Result<()> {
+    APP.clear_download_history()
+}
+
+#[tauri::command]
+fn get_transfer_stats() -> TransferStats {
+    APP.get_transfer_stats()
+}
+
+#[tauri::command]
+fn list_download_tasks() -> Vec<DownloadTask> {
+    APP.list_download_tasks()
+}
+
+#[tauri::command]
+async fn preview_video_filename(template: Option<String>) -> String {
+    APP.preview_video_filename(template).await
+}
+
+#[tauri::command]
+async fn is_video_downloaded(
+    video: VideoPlayInfo,
+    save_name: String,
+    quality: Option<VideoQuality>,
+) -> Result<bool> {
+    APP.is_video_downloaded(&video, &save_name, quality.unwrap_or_default())
+        .await
+}
+
 // Apis for jbox
 #[tauri::command]
 async fn login_jbox() -> Result<()> {
@@ -604,6 +1226,10 @@ async fn main() -> Result<()> {
     setup_log()?;
     APP.init().await?;
     tauri::Builder::default()
+        .setup(|app| {
+            APP.set_app_handle(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             read_log_content,
             console_log,
@@ -611,6 +1237,7 @@ async fn main() -> Result<()> {
             run_video_aggregate,
             collect_relationship,
             switch_account,
+            logout,
             create_account,
             delete_account,
             read_account_info,
@@ -619,8 +1246,21 @@ async fn main() -> Result<()> {
             list_user_submissions,
             get_full_discussion,
             list_discussion_topics,
+            list_announcements,
+            get_grades,
             sync_course_files,
             list_course_files,
+            search_course_files,
+            get_canvas_media_recordings,
+            get_canvas_collaborations,
+            open_collaboration_url,
+            get_canvas_groups,
+            get_canvas_appointment_groups,
+            get_canvas_learning_outcomes,
+            get_canvas_outcome_rollups,
+            get_canvas_content_migrations,
+            get_canvas_media_track,
+            download_canvas_media_captions,
             list_course_images,
             list_course_users,
             list_course_students,
@@ -634,11 +1274,13 @@ async fn main() -> Result<()> {
             list_my_folders,
             list_folder_folders,
             list_calendar_events,
+            get_calendar_events,
             test_token,
             upload_submission_file,
             submit_assignment,
             get_me,
             get_folder_by_id,
+            get_canvas_file_usage_rights,
             get_colors,
             get_config,
             get_raw_config,
@@ -655,9 +1297,14 @@ async fn main() -> Result<()> {
             delete_my_file,
             download_file,
             download_course_file,
+            download_folder,
             download_my_file,
             check_path,
             export_users,
+            export_calendar_events,
+            export_course_calendar,
+            check_network_proxy,
+            upload_canvas_profile_picture,
             update_grade,
             delete_submission_comment,
             delete_my_submission_comment,
@@ -666,18 +1313,61 @@ async fn main() -> Result<()> {
             add_assignment_ddl_override,
             delete_assignment_ddl_override,
             get_my_single_submission,
+            get_canvas_submission_comments,
+            get_student_view_student,
+            list_student_view_assignments,
             // Utils
             convert_pptx_to_pdf,
             // Apis for course video
             get_uuid,
+            check_session_valid,
+            start_qr_login,
+            poll_qr_login,
             express_login,
+            get_video_platform_api_version,
+            video_platform_supports,
             get_subjects,
+            get_terms,
+            get_subject_classes,
+            get_video_platform_history_by_date,
             get_canvas_videos,
+            get_recommended_videos,
+            export_video_list,
+            export_video_playlist,
+            refresh_canvas_course_id,
+            download_video_cover,
+            download_course_video_covers,
+            prefetch_thumbnails,
             login_canvas_website,
-            get_video_course,
+            get_video_courses,
+            search_subject_videos,
+            search_videos,
             get_video_info,
             get_canvas_video_info,
+            get_video_direct_url,
+            get_video_summary,
+            get_video_info_diff,
+            get_video_transcript,
+            copy_video_subtitles_to_sidecar,
+            get_canvas_video_transcript,
+            get_video_platform_subtitle_languages,
             download_video,
+            download_audio_only,
+            download_video_clip,
+            probe_video_urls,
+            repair_video,
+            download_merged_video,
+            download_video_streams,
+            download_course_videos,
+            list_download_tasks,
+            list_video_queue,
+            remove_queued_videos,
+            resume_video_download,
+            get_transfer_stats,
+            get_download_history,
+            clear_download_history,
+            preview_video_filename,
+            is_video_downloaded,
             login_video_website,
             prepare_proxy,
             stop_proxy,
@@ -1,5 +1,7 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
+use chrono::{DateTime, NaiveDateTime, Utc};
+use regex::Regex;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -66,6 +68,229 @@ pub struct File {
     pub content_type: String,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LearningOutcome {
+    pub id: i64,
+    #[serde(default)]
+    pub title: String,
+    // html
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub mastery_points: Option<f64>,
+    #[serde(default)]
+    pub ratings: Vec<OutcomeRating>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutcomeRating {
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub points: f64,
+}
+
+// An outcome as linked into one of the course's outcome groups
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutcomeLink {
+    pub outcome: LearningOutcome,
+}
+
+// A page of /outcome_rollups, ids are referenced through string "links"
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutcomeRollupsPage {
+    #[serde(default)]
+    pub rollups: Vec<RawOutcomeRollup>,
+    #[serde(default)]
+    pub linked: OutcomeRollupsLinked,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutcomeRollupsLinked {
+    #[serde(default)]
+    pub outcomes: Vec<LearningOutcome>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RawOutcomeRollup {
+    #[serde(default)]
+    pub scores: Vec<RawOutcomeScore>,
+    pub links: RollupLinks,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RollupLinks {
+    pub user: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RawOutcomeScore {
+    pub score: Option<f64>,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub count: i64,
+    pub links: ScoreLinks,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreLinks {
+    pub outcome: String,
+}
+
+// The mastery of one student over the course's outcomes
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutcomeRollup {
+    pub user_id: i64,
+    pub scores: Vec<OutcomeScore>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutcomeScore {
+    pub outcome_id: i64,
+    pub title: String,
+    // None while nothing aligned to the outcome has been assessed
+    pub score: Option<f64>,
+    // how many assessments the score is made of
+    pub count: i64,
+    pub mastery_points: Option<f64>,
+    pub mastered: bool,
+}
+
+// A shared document embedded in a course, e.g. a Google Doc or a OneDrive file
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Collaboration {
+    pub id: i64,
+    #[serde(default)]
+    pub title: String,
+    // "Google Docs", "Microsoft Office" or "external_tool"
+    #[serde(default)]
+    pub collaboration_type: String,
+    // external tool collaborations are launched through canvas and may have no url
+    #[serde(default)]
+    pub url: Option<String>,
+    pub created_at: String,
+}
+
+// An lti tool installed in a course or inherited from its account
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExternalTool {
+    pub id: i64,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+// A group of students in a course, usually for a group project
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CanvasGroup {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub members_count: i64,
+    // "parent_context_auto_join", "parent_context_request" or "invitation_only"
+    #[serde(default)]
+    pub join_level: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+// Office hours and other time slots students can sign up for
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppointmentGroup {
+    pub id: i64,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub location_name: Option<String>,
+    #[serde(default)]
+    pub start_at: Option<String>,
+    #[serde(default)]
+    pub end_at: Option<String>,
+    #[serde(default)]
+    pub appointments_count: i64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaRecording {
+    pub media_id: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub media_type: String,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub duration: Option<f64>,
+    #[serde(default)]
+    pub url: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContentMigration {
+    pub id: i64,
+    pub migration_type: String,
+    #[serde(default)]
+    pub progress_url: Option<String>,
+    pub workflow_state: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaTrack {
+    pub id: i64,
+    // captions or subtitles
+    pub kind: String,
+    pub locale: String,
+    // srt text, only returned with `include[]=content`
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub url: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRights {
+    #[serde(default)]
+    pub use_justification: Option<String>,
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub license_name: Option<String>,
+    #[serde(default)]
+    pub public_domain: bool,
+    #[serde(default)]
+    pub free_use_and_share: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedVideoCourses {
+    // unix timestamp in seconds
+    pub fetched_at: u64,
+    pub courses: Vec<VideoCourse>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedSubjects {
+    // unix timestamp in seconds
+    pub fetched_at: u64,
+    pub subjects: Vec<Subject>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedCanvasCourseId {
+    // unix timestamp in seconds
+    pub fetched_at: u64,
+    pub canvas_course_id: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedCourseFiles {
+    // unix timestamp in seconds
+    pub fetched_at: u64,
+    pub files: Vec<File>,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UserSubmissions {
     pub user_id: i64,
@@ -150,6 +375,34 @@ pub struct AppConfig {
     pub course_assignment_file_bindings: HashMap<i64, Vec<File>>,
     #[serde(default)]
     pub show_alert_map: HashMap<String, bool>,
+    #[serde(default = "default_video_download_concurrency")]
+    pub video_download_concurrency: usize,
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+    #[serde(default = "default_video_filename_template")]
+    pub video_filename_template: String,
+    #[serde(default)]
+    pub write_video_metadata: bool,
+    #[serde(default)]
+    pub save_video_subtitles: bool,
+    // also write a .vtt besides the .srt
+    #[serde(default)]
+    pub save_video_subtitles_vtt: bool,
+    // applied when the app starts
+    #[serde(default)]
+    pub network_timeouts: NetworkTimeouts,
+    // minimal interval between two progress updates of a download, applied when the app starts
+    #[serde(default = "default_progress_interval_ms")]
+    pub progress_interval_ms: u64,
+    // http transfers of all downloads together, applied when the app starts
+    #[serde(default = "default_max_concurrent_transfers")]
+    pub max_concurrent_transfers: usize,
+    // the oldest entries are dropped beyond it
+    #[serde(default = "default_download_history_limit")]
+    pub download_history_limit: usize,
+    // http(s):// or socks5:// proxy for every request, applied when the app starts
+    #[serde(default)]
+    pub network_proxy: String,
 }
 
 impl Default for AppConfig {
@@ -166,6 +419,46 @@ impl Default for AppConfig {
             jbox_login_info: Default::default(),
             course_assignment_file_bindings: Default::default(),
             show_alert_map: Default::default(),
+            video_download_concurrency: default_video_download_concurrency(),
+            conflict_policy: Default::default(),
+            video_filename_template: default_video_filename_template(),
+            write_video_metadata: false,
+            save_video_subtitles: false,
+            save_video_subtitles_vtt: false,
+            network_timeouts: Default::default(),
+            progress_interval_ms: default_progress_interval_ms(),
+            max_concurrent_transfers: default_max_concurrent_transfers(),
+            download_history_limit: default_download_history_limit(),
+            network_proxy: Default::default(),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferStats {
+    pub limit: usize,
+    pub in_flight: usize,
+    // waiting for one of the in-flight transfers to finish
+    pub queued: usize,
+}
+
+// In seconds. Requests reading a whole file at once only use the connect timeout,
+// how long they take depends on the file size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkTimeouts {
+    pub connect_secs: u64,
+    pub request_secs: u64,
+    // a single chunk of a video download
+    pub download_secs: u64,
+}
+
+impl Default for NetworkTimeouts {
+    fn default() -> Self {
+        Self {
+            connect_secs: 10,
+            request_secs: 30,
+            download_secs: 120,
         }
     }
 }
@@ -174,11 +467,172 @@ fn default_proxy_port() -> u16 {
     3030
 }
 
+impl AppConfig {
+    pub fn video_sidecars(&self) -> VideoSidecars {
+        VideoSidecars {
+            metadata: self.write_video_metadata,
+            srt: self.save_video_subtitles,
+            vtt: self.save_video_subtitles && self.save_video_subtitles_vtt,
+        }
+    }
+
+    // The configured proxy, or the one in HTTP_PROXY when none is configured
+    pub fn network_proxy_url(&self) -> String {
+        let proxy = self.network_proxy.trim();
+        if !proxy.is_empty() {
+            return proxy.to_owned();
+        }
+        ["HTTP_PROXY", "http_proxy"]
+            .into_iter()
+            .find_map(|name| std::env::var(name).ok())
+            .unwrap_or_default()
+    }
+}
+
+fn default_video_filename_template() -> String {
+    "{title}".to_owned()
+}
+
+fn default_video_download_concurrency() -> usize {
+    3
+}
+
+fn default_progress_interval_ms() -> u64 {
+    200
+}
+
+fn default_max_concurrent_transfers() -> usize {
+    8
+}
+
+fn default_download_history_limit() -> usize {
+    1000
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProgressPayload {
     pub uuid: String,
     pub processed: u64,
     pub total: u64,
+    #[serde(default)]
+    pub stage: ProgressStage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum DownloadTaskState {
+    // waiting to be started, or interrupted by closing the app
+    Queued,
+    #[default]
+    Downloading,
+    Succeeded,
+    Failed,
+}
+
+// Payload of the "download_task://progress" event, the last one of every task is kept
+// so a reloaded page can pick up running downloads
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownloadTask {
+    pub task_id: String,
+    pub state: DownloadTaskState,
+    pub processed: u64,
+    pub total: u64,
+    pub stage: ProgressStage,
+    pub error: Option<String>,
+}
+
+impl From<ProgressPayload> for DownloadTask {
+    fn from(progress: ProgressPayload) -> Self {
+        Self {
+            task_id: progress.uuid,
+            state: DownloadTaskState::Downloading,
+            processed: progress.processed,
+            total: progress.total,
+            stage: progress.stage,
+            error: None,
+        }
+    }
+}
+
+// Which api a video id came from, canvas ids are strings while the platform's are numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum VideoIdSource {
+    #[default]
+    Canvas,
+    Platform,
+}
+
+// A stream link ready to be handed to another tool such as ffmpeg
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoDirectUrl {
+    pub play_id: i64,
+    pub url: String,
+    pub quality: VideoQuality,
+    // the platform's own hosts only serve requests carrying it
+    pub referer: Option<String>,
+    // unix seconds, stated by signed links only. Without it the link lasts as long as the
+    // video session it was resolved with.
+    pub expires_at: Option<u64>,
+}
+
+// A video download of the download table, persisted so the queue survives a restart
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedVideoDownload {
+    pub video: VideoPlayInfo,
+    pub save_name: String,
+    pub quality: VideoQuality,
+    pub state: DownloadTaskState,
+    pub processed: u64,
+    pub total: u64,
+    pub error: Option<String>,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ProgressStage {
+    #[default]
+    Download,
+    // extracting the audio track of a downloaded video
+    Extract,
+    // merging the teacher and courseware streams into one video
+    Merge,
+}
+
+// which streams of a dual-camera recording to download
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum VideoStream {
+    #[default]
+    Camera,
+    Screen,
+    Both,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoStreamsDownload {
+    pub paths: Vec<String>,
+    // both streams were downloaded but ffmpeg isn't available to merge them
+    pub merge_skipped: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum MergeLayout {
+    #[default]
+    SideBySide,
+    // courseware in full screen with the teacher in the bottom right corner
+    PictureInPicture,
+}
+
+// One of the pictures a user can pick as avatar, uploaded ones have the id of their file
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Avatar {
+    #[serde(rename = "type")]
+    pub type_field: String,
+    #[serde(default)]
+    pub id: Option<i64>,
+    pub token: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub display_name: String,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -206,7 +660,14 @@ pub struct CalendarEvent {
     pub id: String,
     #[serde(rename = "type")]
     pub type_field: String,
-    pub assignment: Assignment,
+    // only assignment (and quiz) events have one
+    #[serde(default)]
+    pub assignment: Option<Assignment>,
+    // filled in from `type_field` and `assignment` after fetching
+    #[serde(default)]
+    pub kind: CalendarEventKind,
+    #[serde(default)]
+    pub assignment_id: Option<i64>,
     pub html_url: String,
     pub context_code: String,
     pub context_name: String,
@@ -214,17 +675,48 @@ pub struct CalendarEvent {
     pub end_at: Option<String>,
     #[serde(default)]
     pub start_at: Option<String>,
+    #[serde(default)]
+    pub all_day: bool,
+    #[serde(default)]
+    pub all_day_date: Option<String>,
     pub url: String,
     pub important_dates: bool,
 }
 
+impl CalendarEvent {
+    pub fn classify(&mut self) {
+        self.assignment_id = self.assignment.as_ref().map(|assignment| assignment.id);
+        self.kind = match &self.assignment {
+            None => CalendarEventKind::Event,
+            Some(assignment)
+                if assignment
+                    .submission_types
+                    .iter()
+                    .any(|t| t == "online_quiz") =>
+            {
+                CalendarEventKind::Quiz
+            }
+            Some(_) => CalendarEventKind::Assignment,
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum CalendarEventKind {
+    #[default]
+    Event,
+    Assignment,
+    Quiz,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Assignment {
     pub id: i64,
     #[serde(default)]
     pub description: Option<String>,
+    // parsed so deadlines can be sorted and counted down without caring about the format
     #[serde(default)]
-    pub due_at: Option<String>,
+    pub due_at: Option<DateTime<Utc>>,
     #[serde(default)]
     pub unlock_at: Option<String>,
     #[serde(default)]
@@ -250,6 +742,32 @@ pub struct Assignment {
     pub all_dates: Vec<AssignmentDate>,
     #[serde(default)]
     pub score_statistics: Option<ScoreStatistics>,
+    #[serde(default)]
+    pub assignment_group_id: Option<i64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssignmentGroup {
+    pub id: i64,
+    #[serde(default)]
+    pub name: String,
+    // percentage of the final grade, only used when the course weights its groups
+    #[serde(default)]
+    pub group_weight: f64,
+}
+
+// One assignment of the gradebook as the current user sees it
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GradeEntry {
+    pub assignment_id: i64,
+    pub assignment_name: String,
+    pub group_name: Option<String>,
+    pub group_weight: Option<f64>,
+    pub score: Option<f64>,
+    pub grade: Option<String>,
+    pub points_possible: Option<f64>,
+    pub graded_at: Option<String>,
+    pub comments: Vec<SubmissionComment>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -313,6 +831,10 @@ pub struct Submission {
     pub submitted_at: Option<String>,
     #[serde(default)]
     pub grade: Option<String>,
+    #[serde(default)]
+    pub score: Option<f64>,
+    #[serde(default)]
+    pub graded_at: Option<String>,
     pub assignment_id: i64,
     pub user_id: i64,
     pub late: bool,
@@ -394,6 +916,23 @@ pub struct PageInfo {
     pub row_begin: i64,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchRecord {
+    pub vide_id: i64,
+    #[serde(default)]
+    pub vide_name: String,
+    #[serde(default)]
+    pub cour_name: String,
+    #[serde(default)]
+    pub subj_name: String,
+    // formatted as "YYYY-MM-DD HH:MM:SS"
+    #[serde(default)]
+    pub watch_time: String,
+    #[serde(default)]
+    pub play_duration: i64,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Subject {
@@ -413,6 +952,36 @@ pub struct Subject {
     pub end_year: i64,
 }
 
+impl Subject {
+    pub fn term(&self) -> Term {
+        Term {
+            begin_year: self.begin_year,
+            end_year: self.end_year,
+            term_time: self.term_time,
+        }
+    }
+}
+
+// One of the parallel classes of a subject, each has its own teacher and recordings
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubjectClass {
+    pub tecl_id: i64,
+    pub teacher_name: String,
+    pub class_name: String,
+}
+
+// A semester of the video platform, `term_time` numbers it within the school year
+// `begin_year`-`end_year`. Fields are in chronological order so the derived `Ord` sorts by time.
+#[derive(
+    Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct Term {
+    pub begin_year: i64,
+    pub end_year: i64,
+    pub term_time: i64,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct VideoCourse {
@@ -436,6 +1005,20 @@ pub struct VideoCourse {
     pub teti_term: i64,
 }
 
+// A recording found by a keyword search, with the ids needed to download it
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoSearchHit {
+    pub subject_id: i64,
+    pub tecl_id: i64,
+    pub subject_name: String,
+    pub teacher: String,
+    pub cour_id: i64,
+    pub video_id: i64,
+    pub video_name: String,
+    // milliseconds
+    pub cour_begin_time: i64,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Video {
@@ -488,12 +1071,49 @@ pub struct VideoInfo {
     pub tecl_id: i64,
     pub rtmp_url_hdv: String,
     pub user_avatar: String,
+    #[serde(default)]
+    pub vide_img_url: Option<String>,
     pub login_user_id: i64,
     pub vide_begin_time_ms: i64,
     pub vide_end_time_ms: i64,
     pub video_play_response_vo_list: Vec<VideoPlayInfo>,
 }
 
+impl VideoInfo {
+    // "[id] title | resolution | duration | size | begin time", size is unknown when None
+    pub fn summary(&self, size: Option<u64>) -> String {
+        let plays = &self.video_play_response_vo_list;
+        let resolution = if plays.iter().any(|play| !play.rtmp_url_hdv.is_empty()) {
+            "HD"
+        } else if plays.iter().any(|play| !play.rtmp_url_sdv.is_empty()) {
+            "SD"
+        } else {
+            "-"
+        };
+        let seconds = self.vide_play_time.max(0);
+        let duration = format!(
+            "{:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        );
+        let size = match size {
+            Some(size) => format!("{:.1} MB", size as f64 / 1024.0 / 1024.0),
+            None => "- MB".to_owned(),
+        };
+        format!(
+            "[{}] {} | {} | {} | {} | {}",
+            self.id, self.vide_name, resolution, duration, size, self.vide_begin_time
+        )
+    }
+}
+
+impl fmt::Display for VideoInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.summary(None))
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct VideoPlayInfo {
@@ -501,10 +1121,419 @@ pub struct VideoPlayInfo {
     pub vide_play_time: i64,
     pub client_ip_type: i64,
     pub rtmp_url_hdv: String,
+    #[serde(default)]
+    pub rtmp_url_sdv: String,
     pub cdvi_channel_num: i64,
     pub cdvi_view_num: i64,
 }
 
+// Cookies persisted between launches, in `Cookie` header format
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSession {
+    pub auth_cookies: String,
+    pub video_cookies: String,
+    pub saved_at: u64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QrSession {
+    pub uuid: String,
+    // jaccount's confirm url, rendered as a QR code and scanned with the jaccount app
+    pub qr_url: String,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LoginStatus {
+    #[default]
+    Pending,
+    Confirmed,
+    Expired,
+}
+
+// Payload of the "thumbnail-ready" event
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThumbnailReady {
+    pub video_id: String,
+    pub path: String,
+}
+
+// Payload of the "video_info://changed" event
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoInfoChanges {
+    pub video_id: i64,
+    pub changes: Vec<String>,
+}
+
+// Extra files written next to downloaded videos
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VideoSidecars {
+    pub metadata: bool,
+    pub srt: bool,
+    pub vtt: bool,
+}
+
+// Written to `<video>.info.json` next to a downloaded video
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoMetadata {
+    pub course_name: String,
+    pub subject: String,
+    pub teacher: String,
+    pub recording_time: String,
+    pub video_id: i64,
+    pub play_id: i64,
+    pub quality: VideoQuality,
+    pub source_url: String,
+    pub size: u64,
+    pub downloaded_at: u64,
+    // only part of the recording was downloaded, offsets are in seconds
+    #[serde(default)]
+    pub clip: Option<VideoClipRange>,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VideoClipRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+// Values of the placeholders in `AppConfig::video_filename_template`
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoFilenameFields {
+    pub course: String,
+    pub subject: String,
+    pub teacher: String,
+    pub classroom: String,
+    pub title: String,
+    pub date: String,
+    pub week: String,
+    pub index: usize,
+}
+
+impl VideoFilenameFields {
+    // "2024-03-11 08:00:00" -> "2024-03-11"
+    fn date_of(time: &str) -> String {
+        time.get(..10).unwrap_or(time).to_owned()
+    }
+
+    // "第3周 周一 1-2节" -> "3"
+    fn week_of(title: &str) -> String {
+        let re = Regex::new(r"第\s*(\d+)\s*周").unwrap();
+        re.captures(title)
+            .and_then(|captures| captures.get(1))
+            .map(|week| week.as_str().to_owned())
+            .unwrap_or_default()
+    }
+
+    pub fn from_canvas_video(video: &CanvasVideo, course: &str, index: usize) -> Self {
+        Self {
+            course: course.to_owned(),
+            subject: course.to_owned(),
+            teacher: video.user_name.clone(),
+            classroom: video.classroom_name.clone(),
+            title: video.video_name.clone(),
+            date: Self::date_of(&video.course_begin_time),
+            week: Self::week_of(&video.video_name),
+            index,
+        }
+    }
+
+    pub fn from_video_info(info: &VideoInfo, index: usize) -> Self {
+        Self {
+            course: info.cour_name.clone(),
+            subject: info.subj_name.clone(),
+            teacher: info.user_name.clone(),
+            classroom: info.clro_name.clone(),
+            title: info.vide_name.clone(),
+            date: Self::date_of(&info.vide_begin_time),
+            week: Self::week_of(&info.vide_name),
+            index,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum VideoQuality {
+    Hd,
+    Sd,
+    #[default]
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum AudioFormat {
+    #[default]
+    M4a,
+    Mp3,
+}
+
+impl AudioFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::M4a => "m4a",
+            AudioFormat::Mp3 => "mp3",
+        }
+    }
+}
+
+// what to do when the target file already exists with unexpected content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ConflictPolicy {
+    #[default]
+    Overwrite,
+    Skip,
+    // save as `name (1).mp4`, `name (2).mp4`... like browsers do
+    Rename,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VideoDownloadResult {
+    Downloaded,
+    Skipped,
+}
+
+// Where a video download ended up, `path` differs from the requested one when it was renamed.
+// `bytes_written` is 0 for a skipped video, nothing was transferred.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadResult {
+    pub status: VideoDownloadResult,
+    pub path: String,
+    // the link the video came from, None when it was skipped
+    pub source_url: Option<String>,
+    pub bytes_written: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoDownloadFailure {
+    pub name: String,
+    pub error: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoPlaylist {
+    pub path: String,
+    pub entries: usize,
+    // recordings left out because their links couldn't be resolved
+    pub failed: Vec<VideoDownloadFailure>,
+    // unix seconds, the earliest expiry the signed links state. The links are bound to the
+    // video session either way, None only means they don't say when they stop working.
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoDownloadReport {
+    pub succeeded: Vec<String>,
+    pub skipped: Vec<String>,
+    pub failed: Vec<VideoDownloadFailure>,
+}
+
+// The parameters of the download command, to start the same download again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum DownloadSource {
+    Video {
+        video: VideoPlayInfo,
+        save_name: String,
+        quality: VideoQuality,
+    },
+    File {
+        file: File,
+    },
+    CourseFile {
+        file: File,
+        course: Course,
+        folder_path: String,
+    },
+    MyFile {
+        file: File,
+        folder_path: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownloadHistoryEntry {
+    pub id: String,
+    pub name: String,
+    pub course: Option<String>,
+    pub save_path: String,
+    pub size: u64,
+    pub duration_ms: u64,
+    // bytes per second
+    pub average_speed: u64,
+    // unix timestamp in seconds
+    pub completed_at: u64,
+    // None if the download succeeded
+    pub error: Option<String>,
+    pub source: DownloadSource,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadHistoryFilter {
+    // matched against the name and the course
+    pub keyword: Option<String>,
+    // unix timestamp in seconds
+    pub since: Option<u64>,
+    pub failed_only: bool,
+}
+
+impl DownloadHistoryFilter {
+    pub fn matches(&self, entry: &DownloadHistoryEntry) -> bool {
+        if self.failed_only && entry.error.is_none() {
+            return false;
+        }
+        if self.since.is_some_and(|since| entry.completed_at < since) {
+            return false;
+        }
+        match &self.keyword {
+            Some(keyword) => {
+                entry.name.contains(keyword.as_str())
+                    || entry
+                        .course
+                        .as_ref()
+                        .is_some_and(|course| course.contains(keyword.as_str()))
+            }
+            None => true,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileDownloadFailure {
+    pub path: String,
+    pub error: String,
+}
+
+// Paths are relative to the local directory the folder is downloaded to
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FolderDownloadReport {
+    pub succeeded: Vec<String>,
+    // files and folders the user isn't allowed to download, skipped
+    pub locked: Vec<String>,
+    pub failed: Vec<FileDownloadFailure>,
+}
+
+// Byte ranges [begin, end] refetched by a repair
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoRepairReport {
+    pub save_path: String,
+    pub repaired: Vec<(u64, u64)>,
+}
+
+// How the link of one quality answered a one byte range request. `status` is None when no
+// response came back at all, `error` tells why then.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoUrlProbe {
+    pub url: String,
+    pub status: Option<u16>,
+    pub available: bool,
+    // 0 if the server didn't tell
+    pub size: u64,
+    pub error: Option<String>,
+}
+
+// Probes of the hd and sd links of a play, None for a quality the play has no link for
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoUrlStatus {
+    pub play_id: i64,
+    pub hd: Option<VideoUrlProbe>,
+    pub sd: Option<VideoUrlProbe>,
+    // the quality a download of `requested` quality should use, None if no link works
+    pub usable_quality: Option<VideoQuality>,
+}
+
+impl VideoUrlStatus {
+    pub fn new(
+        play_id: i64,
+        hd: Option<VideoUrlProbe>,
+        sd: Option<VideoUrlProbe>,
+        requested: VideoQuality,
+    ) -> Self {
+        let is_available =
+            |probe: &Option<VideoUrlProbe>| probe.as_ref().is_some_and(|probe| probe.available);
+        let candidates = match requested {
+            VideoQuality::Hd | VideoQuality::Auto => [VideoQuality::Hd, VideoQuality::Sd],
+            VideoQuality::Sd => [VideoQuality::Sd, VideoQuality::Hd],
+        };
+        let usable_quality = candidates.into_iter().find(|quality| match quality {
+            VideoQuality::Sd => is_available(&sd),
+            _ => is_available(&hd),
+        });
+        Self {
+            play_id,
+            hd,
+            sd,
+            usable_quality,
+        }
+    }
+}
+
+impl VideoPlayInfo {
+    // pick the url of requested quality, fall back to whichever one is non-empty
+    pub fn select_url(&self, quality: VideoQuality) -> Option<&str> {
+        self.candidate_urls(quality).first().copied()
+    }
+
+    // Every non-empty url of the play, the requested quality first
+    pub fn candidate_urls(&self, quality: VideoQuality) -> Vec<&str> {
+        let hd = self.rtmp_url_hdv.as_str();
+        let sd = self.rtmp_url_sdv.as_str();
+        let candidates = match quality {
+            VideoQuality::Hd | VideoQuality::Auto => [hd, sd],
+            VideoQuality::Sd => [sd, hd],
+        };
+        let mut urls: Vec<&str> = vec![];
+        for url in candidates {
+            if !url.is_empty() && !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        urls
+    }
+
+    pub fn url_quality(&self, url: &str) -> VideoQuality {
+        if url == self.rtmp_url_hdv {
+            VideoQuality::Hd
+        } else {
+            VideoQuality::Sd
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ApiVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub build: u32,
+}
+
+impl ApiVersion {
+    pub fn new(major: u32, minor: u32, build: u32) -> Self {
+        Self {
+            major,
+            minor,
+            build,
+        }
+    }
+
+    // accept `2.1.3`, `v2.1` or `2`, missing parts default to 0
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim().trim_start_matches(['v', 'V']);
+        let mut parts = version.split('.').map(|part| part.parse::<u32>().ok());
+        let major = parts.next()??;
+        let minor = parts.next().unwrap_or(Some(0))?;
+        let build = parts.next().unwrap_or(Some(0))?;
+        Some(Self::new(major, minor, build))
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct JboxLoginResult {
@@ -788,6 +1817,36 @@ pub struct DiscussionTopic {
     pub assignment: Option<Assignment>,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Announcement {
+    pub id: i64,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub posted_at: Option<String>,
+    // html
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub author: Option<Participant>,
+    #[serde(default)]
+    pub read_state: String,
+    #[serde(default)]
+    pub html_url: String,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    // files linked in the message, filled in from `message` after fetching
+    #[serde(default)]
+    pub file_links: Vec<AnnouncementFileLink>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnnouncementFileLink {
+    pub title: String,
+    pub url: String,
+    pub file_id: Option<i64>,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Permissions {
     #[serde(default)]
@@ -934,6 +1993,61 @@ pub struct CanvasVideo {
     pub classroom_name: String,
     pub course_begin_time: String,
     pub course_end_time: String,
+    #[serde(default, alias = "videImgUrl")]
+    pub video_img_url: Option<String>,
+    // seconds, missing in older responses
+    #[serde(default, alias = "videPlayTime")]
+    pub video_play_time: Option<i64>,
+    // teaching week of the recording, missing in older responses
+    #[serde(default, alias = "weekNum")]
+    pub course_week: Option<i64>,
+}
+
+impl CanvasVideo {
+    // Falls back to the scheduled course time when the platform doesn't send a duration
+    pub fn duration_secs(&self) -> Option<i64> {
+        if self.video_play_time.is_some() {
+            return self.video_play_time;
+        }
+        let parse = |time: &str| NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S").ok();
+        let begin = parse(&self.course_begin_time)?;
+        let end = parse(&self.course_end_time)?;
+        Some((end - begin).num_seconds()).filter(|secs| *secs >= 0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum CanvasVideoSortKey {
+    #[default]
+    Date,
+    Duration,
+    Title,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum VideoListFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+// A row of an exported video list
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoListEntry {
+    pub title: String,
+    pub recorded_at: String,
+    pub duration_secs: Option<i64>,
+    pub video_id: String,
+    // one per camera, only resolved when asked for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub download_urls: Vec<String>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
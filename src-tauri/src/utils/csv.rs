@@ -0,0 +1,58 @@
+// Excel only detects UTF-8 in a csv from the byte order mark, without it chinese text is garbled
+const UTF8_BOM: &str = "\u{feff}";
+// RFC 4180 ends every record with CRLF
+const RECORD_SEPARATOR: &str = "\r\n";
+
+// Renders `header` and `rows` as an RFC 4180 csv that Excel opens as UTF-8
+pub fn export_csv(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut csv = UTF8_BOM.to_owned();
+    let header: Vec<_> = header.iter().map(|field| escape_field(field)).collect();
+    csv.push_str(&header.join(","));
+    csv.push_str(RECORD_SEPARATOR);
+    for row in rows {
+        let row: Vec<_> = row.iter().map(|field| escape_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str(RECORD_SEPARATOR);
+    }
+    csv
+}
+
+// Quotes a field holding a separator, a quote or a line break, quotes inside are doubled
+fn escape_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_csv() {
+        let rows = vec![
+            vec!["计算机系统, 第一讲".to_owned(), "3600".to_owned()],
+            vec!["“引号”与\"quotes\"".to_owned(), "".to_owned()],
+            vec!["第一行\n第二行".to_owned(), "60".to_owned()],
+        ];
+        let csv = export_csv(&["title", "duration"], &rows);
+        assert!(csv.starts_with(UTF8_BOM));
+        assert_eq!(
+            csv.trim_start_matches(UTF8_BOM),
+            "title,duration\r\n\
+             \"计算机系统, 第一讲\",3600\r\n\
+             \"“引号”与\"\"quotes\"\"\",\r\n\
+             \"第一行\n第二行\",60\r\n"
+        );
+    }
+
+    #[test]
+    fn test_escape_field() {
+        assert_eq!(escape_field("plain"), "plain");
+        assert_eq!(escape_field("a,b"), "\"a,b\"");
+        assert_eq!(escape_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_field("中文标题"), "中文标题");
+    }
+}
@@ -0,0 +1,218 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Utc};
+
+use crate::model::{CalendarEvent, CalendarEventKind};
+
+const TIME_ZONE: &str = "Asia/Shanghai";
+// China has no daylight saving time, so a fixed offset is exact
+const TIME_ZONE_OFFSET_SECS: i32 = 8 * 3600;
+// How long before a deadline the reminder fires
+const DEADLINE_REMINDER: &str = "-P1D";
+// Content lines longer than this many octets must be folded, see RFC 5545 3.1
+const MAX_LINE_OCTETS: usize = 75;
+
+// Renders `events` as an RFC 5545 calendar, deadlines get a reminder alarm
+pub fn export_ics(events: &[CalendarEvent]) -> String {
+    export_ics_at(events, Utc::now())
+}
+
+pub fn export_ics_at(events: &[CalendarEvent], now: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//SJTU Canvas Helper//Calendar//ZH".to_owned(),
+        "CALSCALE:GREGORIAN".to_owned(),
+        "METHOD:PUBLISH".to_owned(),
+        format!("X-WR-TIMEZONE:{}", TIME_ZONE),
+        "BEGIN:VTIMEZONE".to_owned(),
+        format!("TZID:{}", TIME_ZONE),
+        "BEGIN:STANDARD".to_owned(),
+        "DTSTART:19700101T000000".to_owned(),
+        "TZOFFSETFROM:+0800".to_owned(),
+        "TZOFFSETTO:+0800".to_owned(),
+        "TZNAME:CST".to_owned(),
+        "END:STANDARD".to_owned(),
+        "END:VTIMEZONE".to_owned(),
+    ];
+    let dtstamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    for event in events {
+        lines.extend(event_lines(event, &dtstamp));
+    }
+    lines.push("END:VCALENDAR".to_owned());
+
+    let mut ics = String::new();
+    for line in lines {
+        ics.push_str(&fold_line(&line));
+        ics.push_str("\r\n");
+    }
+    ics
+}
+
+// Events without a start time can't be placed on a calendar and yield no lines
+fn event_lines(event: &CalendarEvent, dtstamp: &str) -> Vec<String> {
+    let Some((dtstart, dtend)) = event_time_range(event) else {
+        return vec![];
+    };
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_owned(),
+        format!("UID:{}@sjtu-canvas-helper", event.id),
+        format!("DTSTAMP:{}", dtstamp),
+        dtstart,
+        dtend,
+        format!("SUMMARY:{}", escape_text(&event.title)),
+        format!("DESCRIPTION:{}", escape_text(&event.context_name)),
+    ];
+    if !event.html_url.is_empty() {
+        lines.push(format!("URL:{}", event.html_url));
+    }
+    if event.kind != CalendarEventKind::Event {
+        lines.extend([
+            "BEGIN:VALARM".to_owned(),
+            "ACTION:DISPLAY".to_owned(),
+            format!("DESCRIPTION:{}", escape_text(&event.title)),
+            format!("TRIGGER:{}", DEADLINE_REMINDER),
+            "END:VALARM".to_owned(),
+        ]);
+    }
+    lines.push("END:VEVENT".to_owned());
+    lines
+}
+
+// DTSTART and DTEND properties, whole dates for all-day events
+fn event_time_range(event: &CalendarEvent) -> Option<(String, String)> {
+    let start_at = event.start_at.as_deref().and_then(parse_local_time);
+    if event.all_day {
+        let date = event
+            .all_day_date
+            .as_deref()
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            .or_else(|| start_at.map(|start_at| start_at.date_naive()))?;
+        // DTEND of an all-day event is exclusive
+        let end_date = date + Duration::days(1);
+        return Some((
+            format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")),
+            format!("DTEND;VALUE=DATE:{}", end_date.format("%Y%m%d")),
+        ));
+    }
+
+    let start_at = start_at?;
+    // deadlines start and end at the due time
+    let end_at = event
+        .end_at
+        .as_deref()
+        .and_then(parse_local_time)
+        .filter(|end_at| *end_at >= start_at)
+        .unwrap_or(start_at);
+    Some((
+        format!(
+            "DTSTART;TZID={}:{}",
+            TIME_ZONE,
+            format_local_time(&start_at)
+        ),
+        format!("DTEND;TZID={}:{}", TIME_ZONE, format_local_time(&end_at)),
+    ))
+}
+
+fn parse_local_time(time: &str) -> Option<DateTime<FixedOffset>> {
+    let offset = FixedOffset::east_opt(TIME_ZONE_OFFSET_SECS)?;
+    DateTime::parse_from_rfc3339(time)
+        .ok()
+        .map(|time| time.with_timezone(&offset))
+}
+
+fn format_local_time(time: &DateTime<FixedOffset>) -> String {
+    time.format("%Y%m%dT%H%M%S").to_string()
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// Splits a content line into chunks of at most 75 octets without breaking a
+// UTF-8 character, continuation lines start with a space
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut line_octets = 0;
+    for c in line.chars() {
+        if line_octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            line_octets = 1;
+        }
+        folded.push(c);
+        line_octets += c.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deadline(start_at: &str) -> CalendarEvent {
+        CalendarEvent {
+            title: "作业1; 第一章, 习题".to_owned(),
+            id: "assignment_1".to_owned(),
+            kind: CalendarEventKind::Assignment,
+            assignment_id: Some(1),
+            context_name: "数据结构".to_owned(),
+            html_url: "https://oc.sjtu.edu.cn/courses/1/assignments/1".to_owned(),
+            start_at: Some(start_at.to_owned()),
+            end_at: Some(start_at.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_export_ics() {
+        let now = DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let timed = deadline("2024-03-10T15:59:00Z");
+        let all_day = CalendarEvent {
+            id: "calendar_event_2".to_owned(),
+            title: "校运会".to_owned(),
+            kind: CalendarEventKind::Event,
+            all_day: true,
+            all_day_date: Some("2024-03-12".to_owned()),
+            start_at: Some("2024-03-11T16:00:00Z".to_owned()),
+            ..Default::default()
+        };
+        let unscheduled = CalendarEvent {
+            id: "calendar_event_3".to_owned(),
+            ..Default::default()
+        };
+        let ics = export_ics_at(&[timed, all_day, unscheduled], now);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(2, ics.matches("BEGIN:VEVENT").count());
+        assert!(ics.contains("DTSTAMP:20240301T000000Z\r\n"));
+
+        // converted to Shanghai time
+        assert!(ics.contains("DTSTART;TZID=Asia/Shanghai:20240310T235900\r\n"));
+        assert!(ics.contains("DTEND;TZID=Asia/Shanghai:20240310T235900\r\n"));
+        assert!(ics.contains("SUMMARY:作业1\\; 第一章\\, 习题\r\n"));
+        assert!(ics.contains("TRIGGER:-P1D\r\n"));
+        assert_eq!(1, ics.matches("BEGIN:VALARM").count());
+
+        assert!(ics.contains("DTSTART;VALUE=DATE:20240312\r\n"));
+        assert!(ics.contains("DTEND;VALUE=DATE:20240313\r\n"));
+        assert!(!ics.contains("calendar_event_3"));
+    }
+
+    #[test]
+    fn test_fold_line() {
+        let short = "SUMMARY:short";
+        assert_eq!(short, fold_line(short));
+
+        let long = format!("SUMMARY:{}", "作业".repeat(30));
+        let folded = fold_line(&long);
+        for line in folded.split("\r\n") {
+            assert!(line.len() <= MAX_LINE_OCTETS);
+        }
+        assert_eq!(long, folded.replace("\r\n ", ""));
+    }
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+
+pub struct M3uEntry {
+    pub title: String,
+    pub duration_secs: Option<i64>,
+    pub url: String,
+}
+
+// Renders an extended m3u playlist. Players don't agree on how to pass a Referer, so it's
+// noted in the header for the user and given to VLC-based players as an option per entry.
+pub fn export_m3u(entries: &[M3uEntry], referer: &str, expires_at: Option<u64>) -> String {
+    let mut lines = vec![
+        "#EXTM3U".to_owned(),
+        format!("# Referer: {}", referer),
+        "# Links are signed for this session, regenerate the playlist once they stop playing"
+            .to_owned(),
+    ];
+    if let Some(expires_at) =
+        expires_at.and_then(|secs| DateTime::<Utc>::from_timestamp(secs as i64, 0))
+    {
+        lines.push(format!("# Expires: {}", expires_at.to_rfc3339()));
+    }
+    for entry in entries {
+        // -1 is the m3u way of saying the duration is unknown
+        let duration = entry.duration_secs.unwrap_or(-1);
+        // a title is the rest of its line, a line break would start a bogus entry
+        let title = entry.title.replace(['\r', '\n'], " ");
+        lines.push(format!("#EXTINF:{},{}", duration, title));
+        lines.push(format!("#EXTVLCOPT:http-referrer={}", referer));
+        lines.push(entry.url.clone());
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_m3u() {
+        let entries = vec![
+            M3uEntry {
+                title: "数据结构, 第1讲".to_owned(),
+                duration_secs: Some(5400),
+                url: "https://example.com/1.mp4?sign=a".to_owned(),
+            },
+            M3uEntry {
+                title: "第2讲\n(补)".to_owned(),
+                duration_secs: None,
+                url: "https://example.com/2.mp4".to_owned(),
+            },
+        ];
+        let playlist = export_m3u(&entries, "https://courses.sjtu.edu.cn", Some(0));
+        let lines: Vec<_> = playlist.lines().collect();
+        assert_eq!("#EXTM3U", lines[0]);
+        assert_eq!("# Referer: https://courses.sjtu.edu.cn", lines[1]);
+        assert_eq!("# Expires: 1970-01-01T00:00:00+00:00", lines[3]);
+        assert_eq!(
+            &lines[4..],
+            &[
+                "#EXTINF:5400,数据结构, 第1讲",
+                "#EXTVLCOPT:http-referrer=https://courses.sjtu.edu.cn",
+                "https://example.com/1.mp4?sign=a",
+                "#EXTINF:-1,第2讲 (补)",
+                "#EXTVLCOPT:http-referrer=https://courses.sjtu.edu.cn",
+                "https://example.com/2.mp4",
+            ]
+        );
+
+        let playlist = export_m3u(&entries, "https://courses.sjtu.edu.cn", None);
+        assert!(!playlist.contains("# Expires"));
+    }
+}
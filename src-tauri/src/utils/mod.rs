@@ -1,13 +1,27 @@
 use std::{
+    collections::HashSet,
     fs::{self, File},
-    io::{Seek, SeekFrom, Write},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use crate::error::{AppError, Result};
+use crate::{
+    error::{AppError, Result},
+    model::{ProgressPayload, ProgressStage, VideoFilenameFields},
+};
 use serde::de::DeserializeOwned;
 use std::any::type_name;
 use uuid::Uuid;
 
+mod csv;
+mod ics;
+mod m3u;
+
+pub use csv::export_csv;
+pub use ics::export_ics;
+pub use m3u::{export_m3u, M3uEntry};
+
 // RAII temp file
 pub struct TempFile {
     path: String,
@@ -79,15 +93,198 @@ pub fn parse_json<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
     })
 }
 
+// Replace characters that are illegal in Windows/macOS file names
+pub fn sanitize_filename(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+// Substitute `{key}` placeholders from `fields`, unknown placeholders are left empty
+pub fn render_template(template: &str, fields: &[(&str, String)]) -> String {
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(begin) = rest.find('{') {
+        let Some(end) = rest[begin..].find('}') else {
+            break;
+        };
+        rendered.push_str(&rest[..begin]);
+        let key = &rest[begin + 1..begin + end];
+        if let Some((_, value)) = fields.iter().find(|(name, _)| *name == key) {
+            rendered.push_str(value);
+        }
+        rest = &rest[begin + end + 1..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+// Supports {course}, {subject}, {teacher}, {classroom}, {title}, {date}, {week} and {index},
+// {course_name} and {video_name} are aliases of {course} and {title}
+pub fn format_video_filename(template: &str, fields: &VideoFilenameFields) -> String {
+    let fields = [
+        ("course", sanitize_filename(&fields.course)),
+        ("course_name", sanitize_filename(&fields.course)),
+        ("subject", sanitize_filename(&fields.subject)),
+        ("teacher", sanitize_filename(&fields.teacher)),
+        ("classroom", sanitize_filename(&fields.classroom)),
+        ("title", sanitize_filename(&fields.title)),
+        ("video_name", sanitize_filename(&fields.title)),
+        ("date", sanitize_filename(&fields.date)),
+        ("week", sanitize_filename(&fields.week)),
+        ("index", fields.index.to_string()),
+    ];
+    sanitize_filename(&render_template(template, &fields))
+}
+
+// First free path among `name (1).ext`, `name (2).ext`... next to `path`
+pub fn unique_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    let mut counter = 1;
+    loop {
+        let candidate = path.with_file_name(format!("{} ({}){}", stem, counter, extension));
+        if !candidate.exists() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+// `name` itself if it isn't in `used` yet, otherwise the first free `name (1).ext`...
+// The returned name is added to `used`.
+pub fn unique_name(name: &str, used: &mut HashSet<String>) -> String {
+    let mut candidate = name.to_owned();
+    let mut counter = 1;
+    while used.contains(&candidate) {
+        let path = Path::new(name);
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let extension = path
+            .extension()
+            .map(|extension| format!(".{}", extension.to_string_lossy()))
+            .unwrap_or_default();
+        candidate = format!("{} ({}){}", stem, counter, extension);
+        counter += 1;
+    }
+    used.insert(candidate.clone());
+    candidate
+}
+
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+// Fail fast instead of ending up with a truncated file when the volume of `dir` is too small
+pub fn ensure_disk_space(dir: &str, needed: u64) -> Result<()> {
+    let available = fs2::available_space(dir)?;
+    if needed > available {
+        return Err(AppError::InsufficientDiskSpace { needed, available });
+    }
+    Ok(())
+}
+
+// fallback of `write_file_at` where positional writes aren't available
+#[cfg(not(any(unix, windows)))]
 pub fn write_file_at_offset(file: &mut File, data: &[u8], offset: u64) -> std::io::Result<()> {
+    use std::io::{Seek, SeekFrom};
     file.seek(SeekFrom::Start(offset))?;
     file.write_all(data)?;
     Ok(())
 }
 
+// Positional write that doesn't move a shared cursor, so several handles can write concurrently
+pub fn write_file_at(file: &mut File, data: &[u8], offset: u64) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileExt;
+        file.write_all_at(data, offset)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::FileExt;
+        let mut written = 0;
+        while written < data.len() {
+            let n = file.seek_write(&data[written..], offset + written as u64)?;
+            if n == 0 {
+                return Err(std::io::ErrorKind::WriteZero.into());
+            }
+            written += n;
+        }
+        Ok(())
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        write_file_at_offset(file, data, offset)
+    }
+}
+
+// Chunked downloads report progress far more often than the UI can show it. Updates
+// closer than `interval` to the last reported one are held back, except stage changes
+// and the final one. `flush` gives the held back update once the task is over.
+pub struct ProgressThrottle {
+    interval: Duration,
+    last_emitted: Option<(Instant, ProgressStage)>,
+    pending: Option<ProgressPayload>,
+}
+
+impl ProgressThrottle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_emitted: None,
+            pending: None,
+        }
+    }
+
+    pub fn update(&mut self, payload: ProgressPayload) -> Option<ProgressPayload> {
+        self.update_at(payload, Instant::now())
+    }
+
+    pub fn update_at(&mut self, payload: ProgressPayload, now: Instant) -> Option<ProgressPayload> {
+        let finished = payload.total > 0 && payload.processed >= payload.total;
+        let due = match self.last_emitted {
+            Some((last, stage)) => {
+                stage != payload.stage || now.saturating_duration_since(last) >= self.interval
+            }
+            None => true,
+        };
+        if finished || due {
+            self.last_emitted = Some((now, payload.stage));
+            self.pending = None;
+            Some(payload)
+        } else {
+            self.pending = Some(payload);
+            None
+        }
+    }
+
+    pub fn flush(&mut self) -> Option<ProgressPayload> {
+        self.pending.take()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::CanvasVideo;
     use serde::Deserialize;
 
     #[test]
@@ -126,4 +323,117 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_unique_path() -> Result<()> {
+        let dir = Path::new("test_unique_path");
+        fs::create_dir_all(dir)?;
+        let path = dir.join("video.mp4");
+        assert_eq!(dir.join("video (1).mp4"), unique_path(&path));
+
+        // skip numbered files that already exist
+        fs::write(&path, b"")?;
+        fs::write(dir.join("video (1).mp4"), b"")?;
+        fs::write(dir.join("video (2).mp4"), b"")?;
+        assert_eq!(dir.join("video (3).mp4"), unique_path(&path));
+
+        // fill the first gap
+        fs::remove_file(dir.join("video (1).mp4"))?;
+        assert_eq!(dir.join("video (1).mp4"), unique_path(&path));
+
+        // no extension
+        assert_eq!(dir.join("notes (1)"), unique_path(&dir.join("notes")));
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let mut used = HashSet::new();
+        assert_eq!("lecture.pdf", unique_name("lecture.pdf", &mut used));
+        assert_eq!("lecture (1).pdf", unique_name("lecture.pdf", &mut used));
+        assert_eq!("lecture (2).pdf", unique_name("lecture.pdf", &mut used));
+        assert_eq!("slides", unique_name("slides", &mut used));
+        assert_eq!("slides (1)", unique_name("slides", &mut used));
+    }
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(
+            "a_b_c_d_e_f_g_h_i",
+            sanitize_filename("a/b\\c:d*e?f\"g<h>i")
+        );
+        assert_eq!("数据结构_第1讲", sanitize_filename(" 数据结构|第1讲 "));
+    }
+
+    #[test]
+    fn test_format_video_filename() {
+        let video = CanvasVideo {
+            video_name: "第3周 周一 1-2节".to_owned(),
+            user_name: "张三".to_owned(),
+            course_begin_time: "2024-03-11 08:00:00".to_owned(),
+            ..Default::default()
+        };
+        let fields = VideoFilenameFields::from_canvas_video(&video, "操作系统", 2);
+        assert_eq!(
+            "操作系统-第3周 周一 1-2节-3-2024-03-11-2.mp4",
+            format_video_filename(
+                "{course_name}-{video_name}-{week}-{date}-{index}.mp4",
+                &fields
+            )
+        );
+        // unknown placeholders are dropped and illegal characters are replaced
+        assert_eq!(
+            "张三_.mp4",
+            format_video_filename("{teacher}/{unknown}.mp4", &fields)
+        );
+        // unclosed braces are kept as is
+        assert_eq!("{date", render_template("{date", &[]));
+    }
+
+    #[test]
+    fn test_progress_throttle() {
+        let interval = Duration::from_millis(200);
+        let mut throttle = ProgressThrottle::new(interval);
+        let start = Instant::now();
+        let payload = |processed| ProgressPayload {
+            uuid: "video".to_owned(),
+            processed,
+            total: 100,
+            ..Default::default()
+        };
+
+        // an update every 10ms, capped to one per interval
+        let mut emitted = vec![];
+        for processed in 0..99 {
+            let now = start + Duration::from_millis(processed * 10);
+            if let Some(payload) = throttle.update_at(payload(processed), now) {
+                emitted.push(payload.processed);
+            }
+        }
+        assert_eq!(emitted, vec![0, 20, 40, 60, 80]);
+
+        // the last update is held back, and handed out by flush
+        assert_eq!(throttle.flush().map(|p| p.processed), Some(98));
+        assert_eq!(throttle.flush(), None);
+
+        // a finished download is reported right away
+        let now = start + Duration::from_millis(985);
+        assert_eq!(
+            throttle.update_at(payload(100), now).map(|p| p.processed),
+            Some(100)
+        );
+
+        // so is a new stage
+        let merge = ProgressPayload {
+            stage: ProgressStage::Merge,
+            ..payload(0)
+        };
+        let now = start + Duration::from_millis(986);
+        assert_eq!(
+            throttle.update_at(merge, now).map(|p| p.stage),
+            Some(ProgressStage::Merge)
+        );
+    }
 }
@@ -1,5 +1,5 @@
 import React, { useEffect, useState } from 'react';
-import { FileOutlined, SettingOutlined, UserOutlined, VideoCameraOutlined, FormOutlined, CalendarOutlined, CloudDownloadOutlined } from '@ant-design/icons';
+import { FileOutlined, SettingOutlined, UserOutlined, VideoCameraOutlined, FormOutlined, CalendarOutlined, CloudDownloadOutlined, HistoryOutlined } from '@ant-design/icons';
 import { Layout, Menu, Space, theme } from 'antd';
 import { Link, useLocation } from 'react-router-dom';
 import { getVersion } from "@tauri-apps/api/app";
@@ -61,6 +61,10 @@ export default function BasicLayout({ children }: React.PropsWithChildren) {
         key: 'relationship',
         icon: <FaPeopleGroup />,
         label: <Link to={'/relationship'}>人际关系图(beta)</Link>,
+    }, {
+        key: 'history',
+        icon: <HistoryOutlined />,
+        label: <Link to={'/history'}>下载历史</Link>,
     }, {
         key: 'settings',
         icon: <SettingOutlined />,
@@ -0,0 +1,53 @@
+import { Collapse, List, Space, Tag } from "antd";
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api";
+import { LearningOutcome } from "../lib/model";
+
+// Learning objectives of a course, hidden when the course has none
+export function OutcomePanel({ courseId }: { courseId: number }) {
+    const [outcomes, setOutcomes] = useState<LearningOutcome[]>([]);
+
+    useEffect(() => {
+        if (courseId === -1) {
+            setOutcomes([]);
+            return;
+        }
+        handleGetOutcomes(courseId);
+    }, [courseId]);
+
+    const handleGetOutcomes = async (courseId: number) => {
+        try {
+            const outcomes = await invoke("get_canvas_learning_outcomes", { courseId }) as LearningOutcome[];
+            setOutcomes(outcomes);
+        } catch (e) {
+            // outcomes are optional, most courses don't set them up
+            setOutcomes([]);
+        }
+    }
+
+    if (outcomes.length === 0) {
+        return null;
+    }
+
+    return <Collapse items={[{
+        key: "outcomes",
+        label: `课程目标(${outcomes.length})`,
+        children: <List
+            dataSource={outcomes}
+            renderItem={outcome => <List.Item>
+                <List.Item.Meta
+                    title={<Space>
+                        {outcome.title}
+                        {outcome.mastery_points !== null && <Tag color="blue">掌握: {outcome.mastery_points} 分</Tag>}
+                    </Space>}
+                    description={<Space direction="vertical">
+                        {outcome.description && <div dangerouslySetInnerHTML={{ __html: outcome.description }} />}
+                        <Space wrap>
+                            {outcome.ratings.map(rating => <Tag key={rating.description}>{rating.description}: {rating.points}</Tag>)}
+                        </Space>
+                    </Space>}
+                />
+            </List.Item>}
+        />
+    }]} />
+}
@@ -1,6 +1,7 @@
 import DocViewer, { IDocument } from "@cyntler/react-doc-viewer";
-import { Modal } from "antd";
-import { File } from "../lib/model";
+import { Alert, Modal } from "antd";
+import { invoke } from "@tauri-apps/api";
+import { File, UsageRights } from "../lib/model";
 
 // fix https://github.com/wojtekmaj/react-pdf/issues/991
 import { pdfjs } from "react-pdf";
@@ -18,11 +19,42 @@ export default function PreviewModal({ open, files, handleCancelPreview, title,
     bodyStyle?: CSSProperties,
 }) {
     const [docs, setDocs] = useState<IDocument[]>([]);
+    const [usageRights, setUsageRights] = useState<UsageRights | undefined>(undefined);
 
     useEffect(() => {
         initDocs();
+        initUsageRights();
     }, [files]);
 
+    const initUsageRights = async () => {
+        setUsageRights(undefined);
+        if (files.length !== 1 || !files[0].id) {
+            return;
+        }
+        try {
+            let usageRights = await invoke("get_canvas_file_usage_rights", { fileId: files[0].id }) as UsageRights;
+            setUsageRights(usageRights);
+        } catch (e) {
+            // usage rights are optional, most files don't have them set
+            console.log(e);
+        }
+    }
+
+    const getCopyrightNotice = (usageRights: UsageRights) => {
+        if (usageRights.public_domain) {
+            return "该文件属于公共领域";
+        }
+        let license = usageRights.license_name ?? usageRights.license;
+        let notice = license ? `版权许可：${license}` : "该文件受版权保护";
+        if (usageRights.use_justification) {
+            notice += `（${usageRights.use_justification}）`;
+        }
+        if (!usageRights.free_use_and_share) {
+            notice += "，下载后请勿随意传播";
+        }
+        return notice;
+    }
+
     const initDocs = async () => {
         let docs = [];
         for (let file of files) {
@@ -57,6 +89,7 @@ export default function PreviewModal({ open, files, handleCancelPreview, title,
     }, [docs])
     return <Modal title={title} width={"90%"} styles={{ body: { marginTop: "0px" } }} style={{ top: "10px" }}
         open={open} footer={null} onCancel={handleCancelPreview}>
+        {usageRights && <Alert type="info" showIcon message={getCopyrightNotice(usageRights)} />}
         {Viewer}
         {footer}
     </Modal>
@@ -10,6 +10,7 @@ import QRCodePage from "../page/qrcode";
 import DiscussionsPage from "../page/discussions";
 import GradePage from "../page/grades";
 import RelationshipPage from "../page/relationship";
+import HistoryPage from "../page/history";
 
 export default function AppRouter() {
     return <BrowserRouter>
@@ -26,6 +27,7 @@ export default function AppRouter() {
             <Route path="/settings" element={<SettingsPage />} />
             <Route path="/qrcode" element={<QRCodePage />} />
             <Route path="/relationship" element={<RelationshipPage />} />
+            <Route path="/history" element={<HistoryPage />} />
             <Route path="*" element={<Navigate to={"/"} />} />
         </Routes>
     </BrowserRouter>
@@ -6,6 +6,8 @@ import { FaUpload } from "react-icons/fa";
 import useMessage from "antd/es/message/useMessage";
 import { useForm } from "antd/lib/form/Form";
 import { invoke } from "@tauri-apps/api";
+import { appWindow } from "@tauri-apps/api/window";
+import { ProgressPayload } from "../lib/model";
 
 interface SubmitParam {
     filePaths: string[];
@@ -79,6 +81,23 @@ export function SubmitModal({ open, onCancel, onSubmit, allowed_extensions, cour
 }) {
     const [form] = useForm<SubmitParam>();
     const [messageApi, contextHolder] = useMessage();
+
+    useEffect(() => {
+        let unlisten = appWindow.listen<ProgressPayload>("submission_upload://progress", ({ payload }) => {
+            if (payload.uuid !== `${courseId}-${assignmentId}` || payload.total === 0) {
+                return;
+            }
+            messageApi.open({
+                key: "submitting",
+                type: "loading",
+                content: `正在提交中😄...已上传 ${Math.ceil(payload.processed / payload.total * 100)}%`
+            });
+        });
+        return () => {
+            unlisten.then(f => f());
+        }
+    }, [courseId, assignmentId]);
+
     const handleSubmit = async ({ filePaths, comment }: SubmitParam) => {
         if (!comment) {
             comment = undefined;
@@ -1,9 +1,9 @@
 import { Button, Progress, Space, Table } from "antd";
-import { DownloadState, VideoDownloadTask, ProgressPayload } from "../lib/model";
+import { DownloadState, LOG_LEVEL_ERROR, VideoDownloadTask, DownloadTask, VideoRepairReport, TransferStats, DownloadResult } from "../lib/model";
 import { appWindow } from "@tauri-apps/api/window";
-import React, { useEffect, useState } from "react";
+import React, { useEffect, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api";
-import { sleep } from "../lib/utils";
+import { consoleLog, formatDuration, formatSize, sleep } from "../lib/utils";
 import { message } from "antd/lib";
 
 export default function VideoDownloadTable({
@@ -14,17 +14,39 @@ export default function VideoDownloadTable({
     handleRemoveTask?: (task: VideoDownloadTask) => void,
 }) {
     const [currentTasks, setCurrentTasks] = useState<VideoDownloadTask[]>([]);
+    const [transferStats, setTransferStats] = useState<TransferStats | undefined>(undefined);
     const taskSet = new Set<string>(currentTasks.map(task => task.key));
+    // downloads started while others are still running are summarized together once all finish
+    const batch = useRef({ pending: 0, startedAt: 0, results: [] as DownloadResult[] });
+
+    const applyDownloadTask = (task: DownloadTask) => {
+        if (task.state === "Failed") {
+            updateTaskProgress(task.task_id, undefined, task.error ?? "下载失败");
+            return;
+        }
+        // total is 0 when the server doesn't report the size, keep the progress indeterminate
+        let progress = task.state === "Succeeded" ? 100 : task.total === 0 ? undefined : task.processed / task.total * 100;
+        updateTaskProgress(task.task_id, progress);
+    }
 
     useEffect(() => {
-        let unlisten = appWindow.listen<ProgressPayload>("video_download://progress", ({ payload }) => {
-            updateTaskProgress(payload.uuid, payload.processed / payload.total * 100);
+        let unlisten = appWindow.listen<DownloadTask>("download_task://progress", ({ payload }) => {
+            applyDownloadTask(payload);
         });
+        // pick up downloads started before this page was mounted
+        invoke("list_download_tasks").then(tasks => (tasks as DownloadTask[]).forEach(applyDownloadTask));
         return () => {
             unlisten.then(f => f());
         }
     }, []);
 
+    useEffect(() => {
+        let timer = setInterval(() => {
+            invoke("get_transfer_stats").then(stats => setTransferStats(stats as TransferStats));
+        }, 1000);
+        return () => clearInterval(timer);
+    }, []);
+
     useEffect(() => {
         setCurrentTasks(tasks);
         for (let task of tasks) {
@@ -39,14 +61,23 @@ export default function VideoDownloadTable({
         let video = task.video;
         let uuid = video.id + "";
         updateTaskProgress(uuid, 0);
+        if (batch.current.pending === 0) {
+            batch.current = { pending: 0, startedAt: Date.now(), results: [] };
+        }
+        batch.current.pending += 1;
+
+        if (task.resume && await resumeDownload(task)) {
+            finishBatchTask();
+            return;
+        }
 
         let retries = 0;
         let maxRetries = 3;
         while (retries < maxRetries) {
             try {
-                await invoke("download_video", { video, saveName: task.video.name });
+                let result = await invoke("download_video", { video, saveName: task.video.name, quality: task.quality }) as DownloadResult;
+                batch.current.results.push(result);
                 updateTaskProgress(uuid, 100);
-                // messageApi.success("下载成功！", 0.5);
                 break;
             } catch (e) {
                 message.error(e as string);
@@ -55,6 +86,43 @@ export default function VideoDownloadTable({
             }
             await sleep(1000);
         }
+        finishBatchTask();
+    }
+
+    const finishBatchTask = () => {
+        batch.current.pending -= 1;
+        if (batch.current.pending === 0) {
+            summarizeBatch();
+        }
+    }
+
+    // continue an interrupted download from its part file, false when it has to start over
+    const resumeDownload = async (task: VideoDownloadTask) => {
+        task.resume = false;
+        try {
+            let report = await invoke("resume_video_download", {
+                video: task.video,
+                saveName: task.video.name,
+                quality: task.quality,
+            }) as VideoRepairReport | null;
+            if (report) {
+                updateTaskProgress(task.key, 100);
+            }
+            return report !== null;
+        } catch (e) {
+            consoleLog(LOG_LEVEL_ERROR, e);
+            return false;
+        }
+    }
+
+    const summarizeBatch = () => {
+        let downloaded = batch.current.results.filter(result => result.status === "Downloaded");
+        if (downloaded.length === 0) {
+            return;
+        }
+        let bytes = downloaded.reduce((total, result) => total + result.bytes_written, 0);
+        let elapsed = Date.now() - batch.current.startedAt;
+        message.success(`共下载 ${downloaded.length} 个文件，${formatSize(bytes)}，用时 ${formatDuration(elapsed)}🎉`);
     }
 
     const handleRetryTask = (task: VideoDownloadTask) => {
@@ -65,6 +133,25 @@ export default function VideoDownloadTable({
         handleDownloadVideo(task);
     }
 
+    const handleRepairTask = async (task: VideoDownloadTask) => {
+        if (task.state === "downloading") {
+            message.warning("任务正在下载中，请勿修复☹️");
+            return;
+        }
+        try {
+            let report = await invoke("repair_video", { video: task.video, saveName: task.video.name }) as VideoRepairReport;
+            if (report.repaired.length === 0) {
+                message.success("文件完整，无需修复🎉");
+            } else {
+                let ranges = report.repaired.map(([begin, end]) => `${begin}-${end}`).join(", ");
+                message.success(`修复成功🎉，重新下载的区间：${ranges}`);
+            }
+            updateTaskProgress(task.key, 100);
+        } catch (e) {
+            message.error(`修复失败🥹：${e}`);
+        }
+    }
+
     const handleRemoveTasks = () => {
         for (let task of selectedTasks) {
             taskSet.delete(task.key);
@@ -120,6 +207,10 @@ export default function VideoDownloadTable({
                         e.preventDefault();
                         handleRetryTask(task);
                     }}>重试</a>
+                    <a onClick={e => {
+                        e.preventDefault();
+                        handleRepairTask(task);
+                    }}>修复</a>
                 </Space>
             ),
         }
@@ -142,6 +233,7 @@ export default function VideoDownloadTable({
         <Space>
             <Button onClick={handleOpenSaveDir}>打开保存目录</Button>
             <Button onClick={handleRemoveTasks}>删除</Button>
+            {transferStats && <span>传输中 {transferStats.in_flight}/{transferStats.limit}，排队 {transferStats.queued}</span>}
         </Space>
     </Space>
 }
\ No newline at end of file
@@ -1,20 +1,16 @@
 import { CSSProperties, Dispatch, ReactNode, SetStateAction, useEffect, useState } from "react";
 import PreviewModal from "../components/preview_modal";
-import { Assignment, Course, Entry, File, Folder, isFile, LOG_LEVEL_ERROR, LOG_LEVEL_INFO, LoginMessage, RelationshipTopo, User, UserSubmissions } from "./model";
+import { Assignment, Course, Entry, File, Folder, isFile, LOG_LEVEL_ERROR, LOG_LEVEL_INFO, LoginStatus, QrSession, RelationshipTopo, User, UserSubmissions } from "./model";
 import PDFMerger from 'pdf-merger-js/browser';
 import { Button, Input, Progress, Space, message } from "antd";
 import dayjs from "dayjs";
 import { invoke } from "@tauri-apps/api";
-import { getConfig, saveConfig } from "./store";
-import useWebSocket, { ReadyState } from "react-use-websocket";
+import { getConfig } from "./store";
 import { LoginAlertModal } from "../components/login_alert_modal";
 import { BASE_URL, JI_BASE_URL } from "./constants";
 import { consoleLog } from "./utils";
 
-const UPDATE_QRCODE_MESSAGE = "{ \"type\": \"UPDATE_QR_CODE\" }";
-const SEND_INTERVAL = 1000 * 50;
-const QRCODE_BASE_URL = "https://jaccount.sjtu.edu.cn/jaccount/confirmscancode";
-const WEBSOCKET_BASE_URL = "wss://jaccount.sjtu.edu.cn/jaccount/sub";
+const POLL_INTERVAL = 1000;
 
 const EMPTY_ARRAY: any[] = [];
 
@@ -291,71 +287,55 @@ export function useQRCode({ onScanSuccess }: {
 }) {
     const [uuid, setUuid] = useState<string>("");
     const [qrcode, setQrcode] = useState<string>("");
-    const [wsURL, setWsURL] = useState<string>("");
-    const { sendMessage, lastMessage, readyState } = useWebSocket(wsURL, undefined, wsURL.length > 0);
 
     const showQRCode = async () => {
-        let uuid = await invoke("get_uuid") as string | null;
-        if (uuid) {
-            setUuid(uuid);
-            setWsURL(`${WEBSOCKET_BASE_URL}/${uuid}`);
-        }
-    }
-
-    const handleScanSuccess = async () => {
         try {
-            let JAAuthCookie = await invoke("express_login", { uuid }) as string | null;
-            if (!JAAuthCookie) {
-                return;
-            }
-            consoleLog(LOG_LEVEL_INFO, "读取到 JAAuthCookie: ", JAAuthCookie);
-            let config = await getConfig();
-            config.ja_auth_cookie = JAAuthCookie;
-            await saveConfig(config);
-            onScanSuccess?.();
+            let session = await invoke("start_qr_login") as QrSession;
+            setUuid(session.uuid);
+            setQrcode(session.qr_url);
         } catch (e) {
-            message.error(`登录失败🥹：${e}`);
+            message.error(`获取登录二维码失败🥹：${e}`);
         }
     }
 
     useEffect(() => {
-        if (readyState == ReadyState.OPEN) {
-            sendMessage(UPDATE_QRCODE_MESSAGE);
-            let handle = setInterval(() => {
-                refreshQRCode();
-            }, SEND_INTERVAL);
-            return () => {
-                clearInterval(handle);
-            }
+        if (!uuid) {
+            return;
         }
-    }, [readyState]);
-
-    useEffect(() => {
-        if (lastMessage) {
+        let polling = false;
+        let handle = setInterval(async () => {
+            if (polling) {
+                return;
+            }
+            polling = true;
             try {
-                let loginMessage = JSON.parse(lastMessage.data) as LoginMessage;
-                switch (loginMessage.type.toUpperCase()) {
-                    case "UPDATE_QR_CODE":
-                        handleUpdateQrcode(loginMessage);
+                let status = await invoke("poll_qr_login", { uuid }) as LoginStatus;
+                switch (status) {
+                    case "Confirmed":
+                        clearInterval(handle);
+                        consoleLog(LOG_LEVEL_INFO, "扫码登录成功");
+                        // JAAuthCookie was saved by the backend
+                        await getConfig(true);
+                        onScanSuccess?.();
                         break;
-                    case "LOGIN":
-                        handleScanSuccess();
+                    case "Expired":
+                        clearInterval(handle);
+                        showQRCode();
                         break;
                 }
             } catch (e) {
-                consoleLog(LOG_LEVEL_ERROR, e);
+                clearInterval(handle);
+                message.error(`登录失败🥹：${e}`);
             }
+            polling = false;
+        }, POLL_INTERVAL);
+        return () => {
+            clearInterval(handle);
         }
-    }, [lastMessage]);
-
-    const handleUpdateQrcode = (loginMessage: LoginMessage) => {
-        let payload = loginMessage.payload;
-        let qrcode = `${QRCODE_BASE_URL}?uuid=${uuid}&ts=${payload.ts}&sig=${payload.sig}`;
-        setQrcode(qrcode);
-    }
+    }, [uuid]);
 
     const refreshQRCode = () => {
-        sendMessage(UPDATE_QRCODE_MESSAGE);
+        showQRCode();
     }
 
     return { qrcode, showQRCode, refreshQRCode }
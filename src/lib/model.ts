@@ -64,6 +64,90 @@ export interface File {
     size: number;
 }
 
+export interface LearningOutcome {
+    id: number;
+    title: string;
+    description: string | null;
+    mastery_points: number | null;
+    ratings: OutcomeRating[];
+}
+
+export interface OutcomeRollup {
+    user_id: number;
+    scores: OutcomeScore[];
+}
+
+export interface OutcomeScore {
+    outcome_id: number;
+    title: string;
+    score: number | null;
+    count: number;
+    mastery_points: number | null;
+    mastered: boolean;
+}
+
+export interface OutcomeRating {
+    description: string;
+    points: number;
+}
+
+export interface Collaboration {
+    id: number,
+    title: string,
+    collaboration_type: string,
+    url?: string,
+    created_at: string,
+}
+
+export interface CanvasGroup {
+    id: number;
+    name: string;
+    members_count: number;
+    join_level: string;
+    description: string | null;
+}
+
+export interface AppointmentGroup {
+    id: number;
+    title: string;
+    location_name: string | null;
+    start_at: string | null;
+    end_at: string | null;
+    appointments_count: number;
+}
+
+export interface MediaRecording {
+    media_id: string;
+    title: string;
+    media_type: string;
+    created_at: string;
+    duration?: number;
+    url: string;
+}
+
+export interface ContentMigration {
+    id: number;
+    migration_type: string;
+    progress_url: string | null;
+    workflow_state: string;
+}
+
+export interface MediaTrack {
+    id: number;
+    kind: string;
+    locale: string;
+    content?: string | null;
+    url: string;
+}
+
+export interface UsageRights {
+    use_justification?: string;
+    license?: string;
+    license_name?: string;
+    public_domain: boolean;
+    free_use_and_share: boolean;
+}
+
 export interface Folder {
     key: string;
     id: number;
@@ -111,15 +195,21 @@ export interface Colors {
     custom_colors: { [key: string]: string };
 }
 
+export type CalendarEventKind = "Event" | "Assignment" | "Quiz";
+
 export interface CalendarEvent {
     title: string;
     workflow_state: string;
     id: string;
     type_field: string;
-    assignment: Assignment;
+    assignment: Assignment | null;
+    kind: CalendarEventKind;
+    assignment_id: number | null;
     html_url: string;
     end_at?: string | null;
     start_at?: string | null;
+    all_day: boolean;
+    all_day_date: string | null;
     context_code: string;
     context_name: string;
     url: string;
@@ -180,6 +270,8 @@ export interface Submission {
     id: number;
     key: number;
     grade: string | null;
+    score?: number | null;
+    graded_at?: string | null;
     submitted_at?: string;
     assignment_id: number;
     user_id: number;
@@ -233,6 +325,30 @@ export interface VideoDownloadTask {
     video: VideoPlayInfo;
     progress: number;
     state: DownloadState;
+    quality?: VideoQuality;
+    // restored from the persisted queue, continued from its part file when there is one
+    resume?: boolean;
+}
+
+export type VideoIdSource = "Canvas" | "Platform";
+
+export interface VideoDirectUrl {
+    play_id: number;
+    url: string;
+    quality: VideoQuality;
+    referer?: string;
+    expires_at?: number;
+}
+
+export interface QueuedVideoDownload {
+    video: VideoPlayInfo;
+    save_name: string;
+    quality: VideoQuality;
+    state: DownloadTaskState;
+    processed: number;
+    total: number;
+    error?: string;
+    updated_at: number;
 }
 
 export type DownloadState = "downloading" | "succeed" | "fail" | "wait_retry";
@@ -247,8 +363,33 @@ export interface AppConfig {
     proxy_port: number;
     course_assignment_file_bindings: Record<number, File[]>;
     show_alert_map: Record<string, boolean>;
+    video_download_concurrency?: number;
+    conflict_policy?: ConflictPolicy;
+    video_filename_template?: string;
+    write_video_metadata?: boolean;
+    save_video_subtitles?: boolean;
+    save_video_subtitles_vtt?: boolean;
+    network_timeouts?: NetworkTimeouts;
+    progress_interval_ms?: number;
+    max_concurrent_transfers?: number;
+    download_history_limit?: number;
+    network_proxy?: string;
+}
+
+export interface TransferStats {
+    limit: number;
+    in_flight: number;
+    queued: number;
+}
+
+export interface NetworkTimeouts {
+    connect_secs: number;
+    request_secs: number;
+    download_secs: number;
 }
 
+export type ConflictPolicy = "Overwrite" | "Skip" | "Rename";
+
 export interface AccountInfo {
     current_account: string;
     all_accounts: string[];
@@ -258,22 +399,39 @@ export interface ExportUsersConfig {
     save_name: string;
 }
 
+export type ProgressStage = "Download" | "Extract" | "Merge";
+
+export type VideoStream = "Camera" | "Screen" | "Both";
+
+export interface VideoStreamsDownload {
+    paths: string[];
+    merge_skipped: boolean;
+}
+
+export type DownloadTaskState = "Queued" | "Downloading" | "Succeeded" | "Failed";
+
+export interface DownloadTask {
+    task_id: string;
+    state: DownloadTaskState;
+    processed: number;
+    total: number;
+    stage: ProgressStage;
+    error?: string | null;
+}
+
 export interface ProgressPayload {
     uuid: string;
     processed: number;
     total: number;
+    stage?: ProgressStage;
 }
 
-export interface Payload {
-    sig: string;
-    ts: number;
+export interface QrSession {
+    uuid: string;
+    qr_url: string;
 }
 
-export interface LoginMessage {
-    error: number;
-    payload: Payload;
-    type: string;
-}
+export type LoginStatus = "Pending" | "Confirmed" | "Expired";
 
 export interface Subject {
     subjectId: number;
@@ -292,6 +450,18 @@ export interface Subject {
     endYear: number;
 }
 
+export interface SubjectClass {
+    tecl_id: number;
+    teacher_name: string;
+    class_name: string;
+}
+
+export interface Term {
+    beginYear: number;
+    endYear: number;
+    termTime: number;
+}
+
 export interface VideoCourse {
     videPlayCount: number;
     videCommentAverage: number;
@@ -320,6 +490,46 @@ export interface CanvasVideo {
     classroomName: string;
     courseBeginTime: string,
     courseEndTime: string,
+    videoImgUrl?: string | null,
+    videoPlayTime?: number | null,
+    courseWeek?: number | null,
+}
+
+export type CanvasVideoSortKey = "Date" | "Duration" | "Title";
+
+export type SortOrder = "Asc" | "Desc";
+
+export type VideoListFormat = "Csv" | "Json";
+
+export interface VideoDownloadFailure {
+    name: string,
+    error: string,
+}
+
+export interface VideoPlaylist {
+    path: string,
+    entries: number,
+    failed: VideoDownloadFailure[],
+    expires_at?: number,
+}
+
+export interface VideoListEntry {
+    title: string,
+    recorded_at: string,
+    duration_secs?: number,
+    video_id: string,
+    download_urls?: string[],
+}
+
+export interface VideoSearchHit {
+    subject_id: number;
+    tecl_id: number;
+    subject_name: string;
+    teacher: string;
+    cour_id: number;
+    video_id: number;
+    video_name: string;
+    cour_begin_time: number;
 }
 
 export interface Video {
@@ -383,12 +593,65 @@ export interface VideoInfo {
     teclId: number;
     rtmpUrlHdv: string;
     userAvatar: string;
+    videImgUrl?: string | null;
     loginUserId: number;
     videBeginTimeMs: number;
     videEndTimeMs: number;
     videoPlayResponseVoList: VideoPlayInfo[];
 }
 
+export type VideoDownloadResult = "Downloaded" | "Skipped";
+
+export interface DownloadResult {
+    status: VideoDownloadResult;
+    path: string;
+    source_url: string | null;
+    bytes_written: number;
+    duration_ms: number;
+}
+
+export interface VideoUrlProbe {
+    url: string;
+    status: number | null;
+    available: boolean;
+    size: number;
+    error: string | null;
+}
+
+export interface VideoUrlStatus {
+    play_id: number;
+    hd: VideoUrlProbe | null;
+    sd: VideoUrlProbe | null;
+    usable_quality: VideoQuality | null;
+}
+
+export interface VideoRepairReport {
+    save_path: string;
+    // byte ranges [begin, end]
+    repaired: [number, number][];
+}
+
+export interface VideoClipRange {
+    start: number;
+    end: number;
+}
+
+export interface TranscriptSegment {
+    start_ms: number;
+    end_ms: number;
+    text: string;
+}
+
+export interface ThumbnailReady {
+    video_id: string;
+    path: string;
+}
+
+export interface VideoInfoChanges {
+    video_id: number;
+    changes: string[];
+}
+
 export interface Teacher {
     id: number;
     anonymous_id: string;
@@ -467,6 +730,74 @@ export interface DiscussionTopic {
     assignment: Assignment | null;
 }
 
+export interface Announcement {
+    id: number;
+    title: string;
+    posted_at: string | null;
+    message: string;
+    author: Participant | null;
+    read_state: string;
+    html_url: string;
+    attachments: Attachment[];
+    file_links: AnnouncementFileLink[];
+}
+
+export interface AnnouncementFileLink {
+    title: string;
+    url: string;
+    file_id: number | null;
+}
+
+export type VideoQuality = "Hd" | "Sd" | "Auto";
+
+export type DownloadSource =
+    { kind: "Video", video: VideoPlayInfo, save_name: string, quality: VideoQuality } |
+    { kind: "File", file: File } |
+    { kind: "CourseFile", file: File, course: Course, folder_path: string } |
+    { kind: "MyFile", file: File, folder_path: string };
+
+export interface DownloadHistoryEntry {
+    id: string;
+    name: string;
+    course: string | null;
+    save_path: string;
+    size: number;
+    duration_ms: number;
+    average_speed: number;
+    completed_at: number;
+    error: string | null;
+    source: DownloadSource;
+}
+
+export interface DownloadHistoryFilter {
+    keyword?: string;
+    since?: number;
+    failed_only?: boolean;
+}
+
+export interface FileDownloadFailure {
+    path: string;
+    error: string;
+}
+
+export interface FolderDownloadReport {
+    succeeded: string[];
+    locked: string[];
+    failed: FileDownloadFailure[];
+}
+
+export interface GradeEntry {
+    assignment_id: number;
+    assignment_name: string;
+    group_name: string | null;
+    group_weight: number | null;
+    score: number | null;
+    grade: string | null;
+    points_possible: number | null;
+    graded_at: string | null;
+    comments: SubmissionComment[];
+}
+
 export interface Permissions {
     attach: boolean;
     update: boolean;
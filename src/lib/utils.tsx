@@ -22,6 +22,26 @@ export function formatDate(inputDate: string | undefined | null): string {
     return `${year}/${month}/${day} ${hours}:${minutes}`;
 }
 
+export function formatSize(bytes: number) {
+    const units = ["B", "KB", "MB", "GB"];
+    let size = bytes;
+    let unit = 0;
+    while (size >= 1024 && unit < units.length - 1) {
+        size /= 1024;
+        unit += 1;
+    }
+    return `${size.toFixed(unit === 0 ? 0 : 1)} ${units[unit]}`;
+}
+
+export function formatDuration(ms: number) {
+    const seconds = Math.round(ms / 1000);
+    if (seconds < 60) {
+        return `${seconds} 秒`;
+    }
+    const minutes = Math.floor(seconds / 60);
+    return seconds % 60 === 0 ? `${minutes} 分钟` : `${minutes} 分 ${seconds % 60} 秒`;
+}
+
 export function sleep(time: number) {
     return new Promise((resolve) => setTimeout(resolve, time));
 }
@@ -11,6 +11,7 @@ import dayjs from "dayjs";
 import ModifyDDLModal from "../components/modify_ddl_modal";
 import { SubmitModal } from "../components/submit_modal";
 import { GradeOverviewChart } from "../components/grade_overview";
+import { OutcomePanel } from "../components/outcome_panel";
 import { useSearchParams } from "react-router-dom";
 
 export default function AssignmentsPage() {
@@ -267,6 +268,15 @@ export default function AssignmentsPage() {
         ),
     }];
 
+    const handleExportCalendar = async () => {
+        try {
+            await invoke("export_course_calendar", { courseId: selectedCourseId, saveName: `course-${selectedCourseId}.ics` });
+            messageApi.success("导出成功！🎉", 0.5);
+        } catch (e) {
+            messageApi.error(e as string);
+        }
+    }
+
     const handleCourseSelect = async (courseId: number) => {
         let selectedCourse = courses.data.find(course => course.id === courseId);
         if (selectedCourse) {
@@ -356,6 +366,8 @@ export default function AssignmentsPage() {
         <Space direction="vertical" style={{ width: "100%", overflow: "scroll" }} size={"large"}>
             <CourseSelect onChange={handleCourseSelect} disabled={operating} courses={courses.data} value={selectedCourseId === -1 ? undefined : selectedCourseId} />
             {!isTAOrTeacher(selectedCourseId) && <Checkbox disabled={operating} onChange={handleSetOnlyShowUnfinished} defaultChecked>只显示未完成</Checkbox>}
+            {selectedCourseId !== -1 && <Button onClick={handleExportCalendar}>导出课程日程(.ics)</Button>}
+            <OutcomePanel courseId={selectedCourseId} />
             <GradeOverviewChart gradeMap={gradeMap} />
             <Table style={{ width: "100%" }}
                 loading={operating}
@@ -395,8 +407,14 @@ export default function AssignmentsPage() {
                                     }}>删除</a>] : undefined}>
                                         <List.Item.Meta
                                             avatar={<Avatar src={baseURL.data + comment.avatar_path} />}
-                                            title={comment.author_name}
-                                            description={comment.comment}
+                                            title={<Space>{comment.author_name}<span style={{ color: "gray" }}>{formatDate(comment.created_at)}</span></Space>}
+                                            description={<Space direction="vertical">
+                                                {comment.comment}
+                                                {comment.attachments?.map(attachment => <a key={attachment.id} onClick={(e) => {
+                                                    e.preventDefault();
+                                                    setPreviewEntry(attachmentToFile(attachment));
+                                                }}>{attachment.display_name}</a>)}
+                                            </Space>}
                                         />
                                     </List.Item>
                                 )}
@@ -1,4 +1,4 @@
-import { Alert, Badge, Calendar, Space, Spin, Tooltip } from "antd";
+import { Alert, Badge, Button, Calendar, Space, Spin, Tooltip } from "antd";
 import BasicLayout from "../components/layout";
 import { useEffect, useRef, useState } from "react";
 import dayjs, { Dayjs } from "dayjs";
@@ -51,11 +51,11 @@ export default function CalendarPage() {
             const startDate = firstDayOfMonth(currentDate);
             const endDate = lastDayOfMonth(currentDate);
             let events = await handleGetCalendarEvents(contextCodes, startDate, endDate);
-            let assignmentSet = new Set<number>();
-            events.map(event => assignmentSet.add(event.assignment.id));
+            let assignmentSet = new Set<number | null>();
+            events.map(event => assignmentSet.add(event.assignment_id));
             events = events.filter(event => {
-                if (assignmentSet.has(event.assignment.id)) {
-                    assignmentSet.delete(event.assignment.id);
+                if (assignmentSet.has(event.assignment_id)) {
+                    assignmentSet.delete(event.assignment_id);
                     return true;
                 } else {
                     return false;
@@ -123,6 +123,16 @@ export default function CalendarPage() {
         }
     }
 
+    const handleExportEvents = async () => {
+        try {
+            const saveName = `calendar-${currentDate.format("YYYY-MM")}.ics`;
+            await invoke("export_calendar_events", { events, saveName });
+            messageApi.success("导出成功！🎉", 0.5);
+        } catch (e) {
+            messageApi.error(e as string);
+        }
+    }
+
     const getCourseId = (event: CalendarEvent) => {
         const parts = event.context_code.split('_');
         const courseId = parts[parts.length - 1];
@@ -145,6 +155,7 @@ export default function CalendarPage() {
         <Space direction="vertical">
             <Alert message="DDL 提示" description={hintList} type="warning" showIcon />
             <ClosableAlert message="温馨提示" description="按下左右键可以切换月份哦😙" alertType="info" configKey={CALENDAR_PAGE_HINT_ALERT_KEY} />
+            <Button onClick={handleExportEvents} disabled={loading || events.length === 0}>导出本月日程(.ics)</Button>
             <Spin spinning={loading}>
                 <Calendar onPanelChange={handlePanelChange} cellRender={cellRender} value={currentValue} onChange={setCurrentValue} />
             </Spin>
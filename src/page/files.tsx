@@ -1,8 +1,9 @@
 import { Button, Checkbox, CheckboxProps, Divider, Input, Space, Table, Tabs, TabsProps, message } from "antd";
 import BasicLayout from "../components/layout";
 import { useEffect, useMemo, useState } from "react";
-import { Course, Entry, entryName, File, FileDownloadTask, Folder, isFile, LOG_LEVEL_ERROR } from "../lib/model";
+import { Course, Entry, entryName, File, FileDownloadTask, Folder, FolderDownloadReport, isFile, LOG_LEVEL_ERROR } from "../lib/model";
 import { invoke } from "@tauri-apps/api";
+import { open } from "@tauri-apps/api/dialog";
 import useMessage from "antd/es/message/useMessage";
 import CourseSelect from "../components/course_select";
 import FileDownloadTable from "../components/file_download_table";
@@ -296,6 +297,29 @@ export default function FilesPage() {
         }
     }
 
+    const handleDownloadFolder = async () => {
+        let localDir = await open({ directory: true });
+        if (typeof localDir !== "string") {
+            return;
+        }
+        try {
+            messageApi.open({
+                type: "loading",
+                key: "downloading_folder",
+                content: "正在下载文件夹🚀...",
+                duration: 0,
+            });
+            let folderId = currentFolderId > 0 ? currentFolderId : null;
+            let report = await invoke("download_folder", { courseId: selectedCourseId, folderId, localDir }) as FolderDownloadReport;
+            messageApi.destroy("downloading_folder");
+            messageApi.success(`下载完成🎉：成功 ${report.succeeded.length} 个，无权限跳过 ${report.locked.length} 个，失败 ${report.failed.length} 个`);
+        } catch (e) {
+            messageApi.destroy("downloading_folder");
+            consoleLog(LOG_LEVEL_ERROR, e);
+            messageApi.error(`下载文件夹失败😑：${e}`);
+        }
+    }
+
     const handleSyncFiles = async () => {
         try {
             const course = getSelectedCourse()!;
@@ -510,7 +534,10 @@ export default function FilesPage() {
             <Divider orientation="left">PDF/PPTX (混合)合并</Divider>
             {merger}
             <Divider orientation="left">文件下载</Divider>
-            {section === COURSE_FILES && selectedCourseId > 0 && <Button onClick={handleSyncFiles}>一键同步</Button>}
+            {section === COURSE_FILES && selectedCourseId > 0 && <Space>
+                <Button onClick={handleSyncFiles}>一键同步</Button>
+                <Button onClick={handleDownloadFolder}>下载当前文件夹</Button>
+            </Space>}
             <FileDownloadTable
                 tasks={downloadTasks}
                 handleRemoveTask={handleRemoveTask}
@@ -0,0 +1,128 @@
+import { Button, Checkbox, Input, Popconfirm, Space, Table, Tag } from "antd";
+import BasicLayout from "../components/layout";
+import useMessage from "antd/es/message/useMessage";
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api";
+import dayjs from "dayjs";
+import { DownloadHistoryEntry, DownloadHistoryFilter, DownloadSource, LOG_LEVEL_ERROR } from "../lib/model";
+import { consoleLog, formatSize } from "../lib/utils";
+
+// Start the download again through the command that recorded it
+async function redownload(source: DownloadSource) {
+    switch (source.kind) {
+        case "Video":
+            await invoke("download_video", { video: source.video, saveName: source.save_name, quality: source.quality });
+            break;
+        case "File":
+            await invoke("download_file", { file: source.file });
+            break;
+        case "CourseFile":
+            await invoke("download_course_file", { file: source.file, course: source.course, folderPath: source.folder_path });
+            break;
+        case "MyFile":
+            await invoke("download_my_file", { file: source.file, folderPath: source.folder_path });
+            break;
+    }
+}
+
+export default function HistoryPage() {
+    const [messageApi, contextHolder] = useMessage();
+    const [history, setHistory] = useState<DownloadHistoryEntry[]>([]);
+    const [filter, setFilter] = useState<DownloadHistoryFilter>({});
+
+    const handleGetHistory = async (filter: DownloadHistoryFilter) => {
+        try {
+            let history = await invoke("get_download_history", { filter }) as DownloadHistoryEntry[];
+            setHistory(history);
+        } catch (e) {
+            consoleLog(LOG_LEVEL_ERROR, e);
+            messageApi.error(`获取下载历史失败☹️：${e}`);
+        }
+    }
+
+    useEffect(() => {
+        handleGetHistory(filter);
+    }, [filter]);
+
+    const handleClearHistory = async () => {
+        try {
+            await invoke("clear_download_history");
+            setHistory([]);
+        } catch (e) {
+            messageApi.error(`清空下载历史失败☹️：${e}`);
+        }
+    }
+
+    const handleRedownload = async (entry: DownloadHistoryEntry) => {
+        messageApi.open({
+            key: entry.id,
+            type: "loading",
+            content: `正在重新下载 ${entry.name}🚀...`,
+            duration: 0,
+        });
+        try {
+            await redownload(entry.source);
+            messageApi.open({ key: entry.id, type: "success", content: `${entry.name} 下载完成🎉` });
+        } catch (e) {
+            messageApi.open({ key: entry.id, type: "error", content: `${entry.name} 下载失败☹️：${e}` });
+        }
+        handleGetHistory(filter);
+    }
+
+    const columns = [{
+        title: "文件名",
+        dataIndex: "name",
+        key: "name",
+    }, {
+        title: "课程",
+        dataIndex: "course",
+        key: "course",
+        render: (course: string | null) => course ?? "-",
+    }, {
+        title: "大小",
+        dataIndex: "size",
+        key: "size",
+        render: formatSize,
+    }, {
+        title: "耗时",
+        dataIndex: "duration_ms",
+        key: "duration_ms",
+        render: (duration: number) => `${(duration / 1000).toFixed(1)} 秒`,
+    }, {
+        title: "平均速度",
+        dataIndex: "average_speed",
+        key: "average_speed",
+        render: (speed: number) => `${formatSize(speed)}/s`,
+    }, {
+        title: "完成时间",
+        dataIndex: "completed_at",
+        key: "completed_at",
+        render: (completedAt: number) => dayjs.unix(completedAt).format("YYYY-MM-DD HH:mm:ss"),
+    }, {
+        title: "状态",
+        dataIndex: "error",
+        key: "error",
+        render: (error: string | null) => error ? <Tag color="red" title={error}>失败</Tag> : <Tag color="green">成功</Tag>,
+    }, {
+        title: "操作",
+        key: "action",
+        render: (_: any, entry: DownloadHistoryEntry) => <a onClick={(e) => {
+            e.preventDefault();
+            handleRedownload(entry);
+        }}>重新下载</a>,
+    }];
+
+    return <BasicLayout>
+        {contextHolder}
+        <Space direction="vertical" style={{ width: "100%" }} size="large">
+            <Space>
+                <Input.Search placeholder="按文件名或课程搜索" allowClear onSearch={(keyword) => setFilter({ ...filter, keyword: keyword || undefined })} />
+                <Checkbox checked={filter.failed_only} onChange={(e) => setFilter({ ...filter, failed_only: e.target.checked })}>只看失败</Checkbox>
+                <Popconfirm title="确定清空所有下载历史吗？" onConfirm={handleClearHistory}>
+                    <Button danger>清空历史</Button>
+                </Popconfirm>
+            </Space>
+            <Table style={{ width: "100%" }} columns={columns} dataSource={history} rowKey="id" />
+        </Space>
+    </BasicLayout>
+}
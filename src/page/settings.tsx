@@ -3,6 +3,7 @@ import BasicLayout from "../components/layout";
 import { useEffect, useRef, useState } from "react";
 import { AccountInfo, AppConfig, LOG_LEVEL_INFO, User } from "../lib/model";
 import { invoke } from "@tauri-apps/api";
+import { open } from "@tauri-apps/api/dialog";
 import useMessage from "antd/es/message/useMessage";
 import { getConfig, saveConfig } from "../lib/store";
 import type { InputRef, TourProps } from 'antd';
@@ -140,6 +141,48 @@ export default function SettingsPage() {
         }
     }
 
+    const handleLogout = async () => {
+        try {
+            await invoke("logout");
+            await initConfig();
+            messageApi.success("已退出 jAccount 登录🎉！");
+        } catch (e) {
+            messageApi.error(`退出登录失败😢：${e}`);
+        }
+    }
+
+    const handleCheckProxy = async () => {
+        const proxyUrl = form.getFieldValue("network_proxy") ?? "";
+        if (!proxyUrl) {
+            messageApi.warning("请先填写代理地址");
+            return;
+        }
+        try {
+            await invoke("check_network_proxy", { proxyUrl });
+            messageApi.success("代理可用🎉！");
+        } catch (e) {
+            messageApi.error(`代理不可用😢：${e}`);
+        }
+    }
+
+    const handleUploadProfilePicture = async () => {
+        const selected = await open({
+            filters: [{
+                name: "JPEG/PNG 图片（不超过 5MB）",
+                extensions: ["jpg", "jpeg", "png"]
+            }]
+        });
+        if (!selected || Array.isArray(selected)) {
+            return;
+        }
+        try {
+            await invoke("upload_canvas_profile_picture", { imagePath: selected });
+            messageApi.success("头像已更新🎉！");
+        } catch (e) {
+            messageApi.error(`上传头像失败😢：${e}`);
+        }
+    }
+
     const handleDeleteAccount = async () => {
         try {
             await invoke("delete_account", { account: currentAccount });
@@ -175,6 +218,8 @@ export default function SettingsPage() {
                 />
                 <Button onClick={() => setAccountMode("create")}>新建账号</Button>
                 <Button disabled={currentAccount === "Default"} type="primary" onClick={handleDeleteAccount}>删除当前账号</Button>
+                <Button onClick={handleLogout}>退出 jAccount 登录</Button>
+                <Button onClick={handleUploadProfilePicture}>更换 Canvas 头像</Button>
             </Space>}
             {accountMode === "create" && < Space >
                 <span>新建账号：</span>
@@ -216,6 +261,36 @@ export default function SettingsPage() {
                 <Form.Item name="serve_as_plaintext" label="以纯文本显示的文件拓展名">
                     <Input placeholder="请输入文件拓展名，以英文逗号隔开" />
                 </Form.Item>
+                <Form.Item label="网络超时（秒，重启后生效）">
+                    <Space>
+                        <Form.Item name={["network_timeouts", "connect_secs"]} noStyle>
+                            <InputNumber min={1} addonBefore="连接" />
+                        </Form.Item>
+                        <Form.Item name={["network_timeouts", "request_secs"]} noStyle>
+                            <InputNumber min={1} addonBefore="请求" />
+                        </Form.Item>
+                        <Form.Item name={["network_timeouts", "download_secs"]} noStyle>
+                            <InputNumber min={1} addonBefore="下载分块" />
+                        </Form.Item>
+                    </Space>
+                </Form.Item>
+                <Form.Item label="网络代理（留空则使用 HTTP_PROXY 环境变量，重启后生效）">
+                    <Space>
+                        <Form.Item name="network_proxy" noStyle>
+                            <Input style={{ width: 300 }} placeholder="http://127.0.0.1:7890 或 socks5://127.0.0.1:7890" />
+                        </Form.Item>
+                        <Button onClick={handleCheckProxy}>测试代理</Button>
+                    </Space>
+                </Form.Item>
+                <Form.Item name="progress_interval_ms" label="下载进度刷新间隔（毫秒，重启后生效）">
+                    <InputNumber min={0} step={100} />
+                </Form.Item>
+                <Form.Item name="max_concurrent_transfers" label="全局最大同时传输数（重启后生效）">
+                    <InputNumber min={1} />
+                </Form.Item>
+                <Form.Item name="download_history_limit" label="下载历史最多保留条数">
+                    <InputNumber min={0} />
+                </Form.Item>
                 <Space>
                     <Form.Item>
                         <Button ref={saveButtonRef} type="primary" htmlType="submit">
@@ -2,7 +2,7 @@ import { invoke } from "@tauri-apps/api";
 import { useEffect, useRef, useState } from "react";
 import BasicLayout from "../components/layout";
 import { SwapOutlined } from '@ant-design/icons';
-import { VideoInfo, VideoPlayInfo, VideoDownloadTask, CanvasVideo, LOG_LEVEL_ERROR } from "../lib/model";
+import { VideoInfo, VideoPlayInfo, VideoDownloadTask, CanvasVideo, LOG_LEVEL_ERROR, CanvasVideoSortKey, SortOrder, VideoUrlStatus, VideoListFormat, VideoPlaylist, QueuedVideoDownload, VideoDirectUrl } from "../lib/model";
 import useMessage from "antd/es/message/useMessage";
 import { getConfig, saveConfig } from "../lib/store";
 import { Button, Checkbox, Divider, Select, Space, Table } from "antd";
@@ -12,18 +12,22 @@ import { LoginAlert } from "../components/login_alert";
 import { useCourses, useQRCode } from "../lib/hooks";
 import CourseSelect from "../components/course_select";
 import ClosableAlert from "../components/closable_alert";
-import { VIDEO_PAGE_HINT_ALERT_KEY } from "../lib/constants";
+import { SESSION_CHECK_INTERVAL_MS, VIDEO_PAGE_HINT_ALERT_KEY } from "../lib/constants";
 import VideoAggregator from "../components/video_aggregator";
 import { consoleLog } from "../lib/utils";
 
 export default function VideoPage() {
     const [downloadTasks, setDownloadTasks] = useState<VideoDownloadTask[]>([]);
+    const [unfinishedDownloads, setUnfinishedDownloads] = useState<QueuedVideoDownload[]>([]);
     const [operating, setOperating] = useState<boolean>(false);
     const courses = useCourses();
     const [messageApi, contextHolder] = useMessage();
     const [plays, setPlays] = useState<VideoPlayInfo[]>([]);
+    const [videoInfo, setVideoInfo] = useState<VideoInfo | undefined>();
     const [selectedVideo, setSelectedVideo] = useState<CanvasVideo | undefined>();
     const [videos, setVideos] = useState<CanvasVideo[]>([]);
+    const [selectedCourseId, setSelectedCourseId] = useState<number>(-1);
+    const [videoSort, setVideoSort] = useState<string>("Date-Asc");
     const [notLogin, setNotLogin] = useState<boolean>(true);
     const [loaded, setLoaded] = useState<boolean>(false);
     const [playURLs, setPlayURLs] = useState<string[]>([]);
@@ -50,6 +54,12 @@ export default function VideoPage() {
         }
     }
 
+    useEffect(() => {
+        invoke("list_video_queue").then(queue => {
+            setUnfinishedDownloads((queue as QueuedVideoDownload[]).filter(entry => entry.state !== "Succeeded"));
+        }).catch(e => consoleLog(LOG_LEVEL_ERROR, e));
+    }, []);
+
     useEffect(() => {
         loginAndCheck();
         return () => {
@@ -59,6 +69,26 @@ export default function VideoPage() {
         }
     }, []);
 
+    useEffect(() => {
+        if (notLogin) {
+            return;
+        }
+        // find out the session expired before a download fails because of it
+        const timer = setInterval(async () => {
+            try {
+                let valid = await invoke("check_session_valid") as boolean;
+                if (!valid) {
+                    messageApi.warning("登录已过期，请重新扫码登录🥹");
+                    setNotLogin(true);
+                    showQRCode();
+                }
+            } catch (e) {
+                consoleLog(LOG_LEVEL_ERROR, e);
+            }
+        }, SESSION_CHECK_INTERVAL_MS);
+        return () => clearInterval(timer);
+    }, [notLogin]);
+
     const loginAndCheck = async (retry = false) => {
         let config = await getConfig(true);
         let success = await handleLoginWebsite();
@@ -84,10 +114,18 @@ export default function VideoPage() {
         setPlays([]);
         setMainPlayURL("");
         setMutedPlayURL("");
-        handleGetVideos(selected);
+        setSelectedCourseId(selected);
+        handleGetVideos(selected, videoSort);
         setOperating(false);
     }
 
+    const handleSortVideos = (videoSort: string) => {
+        setVideoSort(videoSort);
+        if (selectedCourseId !== -1) {
+            handleGetVideos(selectedCourseId, videoSort);
+        }
+    }
+
     const handleGetVideoInfo = async (video: CanvasVideo) => {
         try {
             let videoInfo = await invoke("get_canvas_video_info", { videoId: video.videoId }) as VideoInfo;
@@ -100,15 +138,84 @@ export default function VideoPage() {
                 play.name = `${video.videoName}${part}${suffix}`;
             });
             setPlays(plays);
+            setVideoInfo(videoInfo);
         } catch (e) {
             messageApi.error(`获取视频信息的时候出现错误🙅：${e}`);
         }
     }
 
+    const handleExportPlaylist = async (refresh: boolean) => {
+        try {
+            let playlist = await invoke("export_video_playlist", {
+                courseId: selectedCourseId,
+                saveName: `videos-${selectedCourseId}.m3u8`,
+                refresh,
+            }) as VideoPlaylist;
+            let expiry = playlist.expires_at ?
+                `，链接将于 ${new Date(playlist.expires_at * 1000).toLocaleString()} 失效` :
+                "，链接与当前登录绑定，失效后请刷新播放列表";
+            messageApi.success(`已写入 ${playlist.entries} 个视频${expiry}🎉`);
+            if (playlist.failed.length > 0) {
+                messageApi.warning(`${playlist.failed.length} 个录像获取失败：${playlist.failed.map(failure => failure.name).join("、")}`);
+            }
+        } catch (e) {
+            messageApi.error(`导出播放列表的时候发生了错误🙅：${e}`);
+        }
+    }
+
+    const handleNextVideo = async () => {
+        try {
+            let recommended = await invoke("get_recommended_videos", {
+                courseId: selectedCourseId,
+                videoId: selectedVideo?.videoId,
+            }) as CanvasVideo[];
+            if (recommended.length === 0) {
+                messageApi.info("已经是最后一个录像了");
+                return;
+            }
+            handleSelectVideo(recommended[0].videoId);
+        } catch (e) {
+            messageApi.error(`获取下一个录像的时候发生了错误🙅：${e}`);
+        }
+    }
+
+    const handleCopyDirectUrl = async (play: VideoPlayInfo) => {
+        try {
+            let urls = await invoke("get_video_direct_url", {
+                videoId: selectedVideo?.videoId,
+                source: "Canvas",
+            }) as VideoDirectUrl[];
+            let directUrl = urls.find(url => url.play_id === play.id);
+            if (!directUrl) {
+                messageApi.error("该视频没有可下载的视频流🥹");
+                return;
+            }
+            await navigator.clipboard.writeText(directUrl.url);
+            let referer = directUrl.referer ? `，请求时需带上 Referer: ${directUrl.referer}` : "";
+            let expiry = directUrl.expires_at ?
+                `，将于 ${new Date(directUrl.expires_at * 1000).toLocaleString()} 失效` :
+                "，在当前登录失效前有效";
+            messageApi.success(`已复制直链${referer}${expiry}`);
+        } catch (e) {
+            messageApi.error(`获取直链的时候发生了错误🙅：${e}`);
+        }
+    }
+
+    const handleCopyVideoSummary = async () => {
+        try {
+            let summary = await invoke("get_video_summary", { video: videoInfo }) as string;
+            await navigator.clipboard.writeText(summary);
+            messageApi.success("复制成功🎉！", 0.5);
+        } catch (e) {
+            messageApi.error(`复制失败🥹：${e}`);
+        }
+    }
+
     const handleSelectVideo = (selected: string) => {
         let video = videos.find(video => video.videoId === selected);
         if (video) {
             setPlays([]);
+            setVideoInfo(undefined);
             setPlayURLs([]);
             setMainPlayURL("");
             setMutedPlayURL("");
@@ -117,33 +224,86 @@ export default function VideoPage() {
         }
     }
 
-    const handleGetVideos = async (courseId: number) => {
+    const handleGetVideos = async (courseId: number, videoSort: string) => {
         try {
-            let videos = await invoke("get_canvas_videos", { courseId }) as CanvasVideo[];
+            const [sortBy, order] = videoSort.split("-") as [CanvasVideoSortKey, SortOrder];
+            let videos = await invoke("get_canvas_videos", { courseId, sortBy, order }) as CanvasVideo[];
             setVideos(videos);
+            // warm up the cover cache, each cover is announced by a "thumbnail-ready" event
+            invoke("prefetch_thumbnails", { videos }).catch(e => consoleLog(LOG_LEVEL_ERROR, e));
         } catch (e) {
             messageApi.error(`获取录像的时候发生了错误🙅：${e}`);
         }
     }
 
-    const handleDownloadVideo = (video: VideoPlayInfo) => {
+    const handleExportVideoList = async (format: VideoListFormat) => {
+        const extension = format === "Csv" ? "csv" : "json";
+        try {
+            await invoke("export_video_list", {
+                courseId: selectedCourseId,
+                format,
+                saveName: `videos-${selectedCourseId}.${extension}`,
+                includeUrls: true,
+            });
+            messageApi.success("导出成功！🎉", 0.5);
+        } catch (e) {
+            messageApi.error(`导出录像列表的时候发生了错误🙅：${e}`);
+        }
+    }
+
+    const handleDownloadVideo = async (video: VideoPlayInfo) => {
         let videoId = video.id + "";
-        if (!downloadTasks.find(task => task.key === videoId)) {
-            setDownloadTasks(tasks => [...tasks, {
-                key: videoId,
-                video,
-                video_name: video.name,
-                progress: 0,
-                state: "downloading"
-            } as VideoDownloadTask]);
-        } else {
+        if (downloadTasks.find(task => task.key === videoId)) {
             messageApi.warning("请勿重复添加任务！");
             return;
         }
+        // find dead links before the download starts instead of halfway through it
+        let status = await invoke("probe_video_urls", { video }) as VideoUrlStatus;
+        if (!status.usable_quality) {
+            messageApi.error("视频链接均不可用，请稍后重试🥹");
+            return;
+        }
+        if (status.hd && status.usable_quality === "Sd") {
+            messageApi.warning("高清不可用，将使用标清");
+        }
+        setDownloadTasks(tasks => [...tasks, {
+            key: videoId,
+            video,
+            video_name: video.name,
+            progress: 0,
+            state: "downloading",
+            quality: status.usable_quality,
+        } as VideoDownloadTask]);
+    }
+
+    const handleResumeDownloads = () => {
+        let restored = unfinishedDownloads
+            .filter(entry => !downloadTasks.find(task => task.key === entry.video.id + ""))
+            .map(entry => ({
+                key: entry.video.id + "",
+                // the name only lives in the frontend, the queue keeps it as the save name
+                video: { ...entry.video, name: entry.save_name },
+                progress: entry.total === 0 ? 0 : Math.floor(entry.processed / entry.total * 100),
+                state: "downloading",
+                quality: entry.quality,
+                resume: true,
+            } as VideoDownloadTask));
+        setDownloadTasks(tasks => [...tasks, ...restored]);
+        setUnfinishedDownloads([]);
+    }
+
+    const handleDiscardDownloads = async () => {
+        try {
+            await invoke("remove_queued_videos", { videoIds: unfinishedDownloads.map(entry => entry.video.id) });
+            setUnfinishedDownloads([]);
+        } catch (e) {
+            messageApi.error(e as string);
+        }
     }
 
     const handleRemoveTask = async (taskToRemove: VideoDownloadTask) => {
         setDownloadTasks(tasks => tasks.filter(task => task.key !== taskToRemove.key));
+        invoke("remove_queued_videos", { videoIds: [taskToRemove.video.id] }).catch(e => consoleLog(LOG_LEVEL_ERROR, e));
         try {
             await invoke("delete_file_with_name", { name: taskToRemove.video.name });
         } catch (e) {
@@ -244,6 +404,10 @@ export default function VideoPage() {
                         e.preventDefault();
                         handleDownloadVideo(play);
                     }}>下载</a>
+                    <a onClick={e => {
+                        e.preventDefault();
+                        handleCopyDirectUrl(play);
+                    }}>复制直链</a>
                     {/* <a onClick={e => {
                         e.preventDefault();
                         handlePlay(play);
@@ -343,6 +507,26 @@ export default function VideoPage() {
                             value: video.videoId,
                         }))}
                     />
+                    <Select
+                        disabled={operating}
+                        style={{ width: 150 }}
+                        value={videoSort}
+                        onChange={handleSortVideos}
+                        options={[
+                            { label: "时间从早到晚", value: "Date-Asc" },
+                            { label: "时间从晚到早", value: "Date-Desc" },
+                            { label: "时长从短到长", value: "Duration-Asc" },
+                            { label: "时长从长到短", value: "Duration-Desc" },
+                            { label: "按标题", value: "Title-Asc" },
+                        ]}
+                    />
+                    {videos.length > 0 && <span>共 {videos.length} 个录像</span>}
+                    <Button disabled={!selectedVideo || operating} onClick={handleNextVideo}>下一讲</Button>
+                    <Button disabled={!videoInfo} onClick={handleCopyVideoSummary}>复制视频信息</Button>
+                    <Button disabled={videos.length === 0} onClick={() => handleExportVideoList("Csv")}>导出录像列表(.csv)</Button>
+                    <Button disabled={videos.length === 0} onClick={() => handleExportVideoList("Json")}>导出录像列表(.json)</Button>
+                    <Button disabled={videos.length === 0} onClick={() => handleExportPlaylist(false)}>导出播放列表(.m3u8)</Button>
+                    <Button disabled={videos.length === 0} onClick={() => handleExportPlaylist(true)}>刷新播放列表</Button>
                 </Space>
                 <Table style={{ width: "100%" }} columns={columns} dataSource={plays} pagination={false} />
                 <Space direction="vertical">
@@ -367,6 +551,11 @@ export default function VideoPage() {
                         ref={getVideoRef(playURL)}
                         controls={playURL === mainPlayURL} autoPlay={false} src={playURL} muted={playURL === mutedPlayURL} />)}
                 </div>
+                {unfinishedDownloads.length > 0 && <Space>
+                    <span>上次还有 {unfinishedDownloads.length} 个视频未下载完成</span>
+                    <Button type="primary" onClick={handleResumeDownloads}>恢复下载</Button>
+                    <Button onClick={handleDiscardDownloads}>放弃</Button>
+                </Space>}
                 <VideoDownloadTable tasks={downloadTasks} handleRemoveTask={handleRemoveTask} />
             </>}
             <Divider orientation="left">视频合并</Divider>